@@ -1,4 +1,5 @@
 mod all_tests;
+mod attributes;
 mod events;
 mod text;
 mod ui;
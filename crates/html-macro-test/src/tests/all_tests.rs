@@ -7,7 +7,9 @@
 
 use html_macro::html;
 use std::collections::HashMap;
-use virtual_node::{IterableNodes, VElement, VText, View, VirtualNode};
+use virtual_node::{
+    Component, ComponentState, IterableNodes, VElement, VFragment, VText, View, VirtualNode,
+};
 
 pub(crate) struct HtmlMacroTest {
     pub generated: VirtualNode,
@@ -366,6 +368,33 @@ fn custom_component_children() {
     .test();
 }
 
+/// Verify that a type that implements `Component` becomes a component node.
+#[test]
+fn component_props() {
+    #[derive(PartialEq)]
+    struct Counter {
+        step: u8,
+    }
+
+    impl Component for Counter {
+        type State = u8;
+
+        fn render(&self, state: &ComponentState<u8>) -> VirtualNode {
+            html! {
+                <span>{format!("{} + {}", *state.get(), self.step)}</span>
+            }
+        }
+    }
+
+    let generated = html! {
+      <div><Counter step={2}/></div>
+    };
+
+    let child = &generated.as_velement_ref().unwrap().children[0];
+    assert_eq!(child, &VirtualNode::component(Counter { step: 2 }));
+    assert_eq!(child.rendered_node(), &html! { <span>{"0 + 2"}</span> });
+}
+
 /// Verify that we can properly render an empty list of virtual nodes that has a space after it.
 /// Before this test our code generation was assuming that all lists had more than one element when
 /// checking if we needed to insert space after an element.
@@ -380,14 +409,15 @@ fn space_before_and_after_empty_list() {
     .test()
 }
 
-/// Verify that an Option::None virtual node gets ignored.
+/// Verify that an Option::None virtual node becomes a placeholder comment, so that the nodes after
+/// it keep the same positions as when it is Some.
 #[test]
 fn option_none() {
     let element: Option<VirtualNode> = None;
 
     HtmlMacroTest {
-        generated: html! {<div> {element} </div>},
-        expected: html! {<div> </div>},
+        generated: html! {<div> {element} <span></span> </div>},
+        expected: html! {<div> {VirtualNode::placeholder()} <span></span> </div>},
     }
     .test()
 }
@@ -416,3 +446,47 @@ fn numbers() {
     }
     .test()
 }
+
+/// Verify that we can create a fragment as the root node.
+#[test]
+fn fragment_root() {
+    let mut expected = VFragment::new();
+    expected.children = vec![VirtualNode::element("td"), VirtualNode::element("td")];
+
+    HtmlMacroTest {
+        generated: html! { <><td></td><td></td></> },
+        expected: expected.into(),
+    }
+    .test();
+}
+
+/// Verify that fragments can be nested inside of elements and other fragments.
+#[test]
+fn nested_fragments() {
+    let mut inner = VFragment::new();
+    inner.children = vec![VirtualNode::text("Hello")];
+
+    let mut outer = VFragment::new();
+    outer.children = vec![VirtualNode::element("em"), inner.into()];
+
+    let mut expected = VElement::new("div");
+    expected.children = vec![outer.into(), VirtualNode::element("br")];
+
+    HtmlMacroTest {
+        generated: html! { <div><><em></em><>Hello</></><br /></div> },
+        expected: expected.into(),
+    }
+    .test();
+}
+
+/// Verify that we maintain the spacing around text that is next to a fragment's tags.
+#[test]
+fn fragment_text_spacing() {
+    let world = "world";
+
+    HtmlMacroTest {
+        generated: html! { <div>Hello <> {world} </>!</div> },
+        expected: html! { <div>{"Hello "} <>{" world "}</>{"!"}</div> },
+    }
+    .test();
+}
@@ -0,0 +1,209 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::tests::all_tests::HtmlMacroTest;
+use percy_dom::event::EventName;
+use percy_dom::prelude::*;
+use virtual_node::{AttributeValue, NodeRef, VElement};
+
+/// Verify that an attribute whose value is `None` is not added to the element.
+#[test]
+fn optional_attribute() {
+    let title: Option<&str> = None;
+    let id = Some("main".to_string());
+
+    let mut expected = VElement::new("div");
+    expected.attrs.insert("id".to_string(), "main".into());
+
+    HtmlMacroTest {
+        generated: html! { <div id=id title=title></div> },
+        expected: expected.into(),
+    }
+    .test();
+}
+
+/// Verify that numbers stay typed.
+#[test]
+fn number_attributes() {
+    let step = 0.5;
+
+    let mut expected = VElement::new("input");
+    expected
+        .attrs
+        .insert("tabindex".to_string(), AttributeValue::Int(3));
+    expected
+        .attrs
+        .insert("step".to_string(), AttributeValue::Float(0.5));
+
+    HtmlMacroTest {
+        generated: html! { <input tabindex=3 step=step /> },
+        expected: expected.into(),
+    }
+    .test();
+}
+
+/// Verify that lists can mix strings and optional strings, and that `None` items are skipped.
+#[test]
+fn class_list() {
+    let is_active = true;
+    let is_disabled = false;
+    let size = String::from("large");
+
+    let generated = html! {
+        <button class=["button", is_active.then(|| "active"), is_disabled.then(|| "disabled"), &size]>
+        </button>
+    };
+
+    let mut expected = VElement::new("button");
+    expected.attrs.insert(
+        "class".to_string(),
+        AttributeValue::List(vec![
+            "button".to_string(),
+            "active".to_string(),
+            "large".to_string(),
+        ]),
+    );
+
+    assert_eq!(
+        generated.to_string(),
+        r#"<button class="button active large"></button>"#
+    );
+    HtmlMacroTest {
+        generated,
+        expected: expected.into(),
+    }
+    .test();
+}
+
+/// Verify that lists items can be anything that implements `AsRef<str>`.
+#[test]
+fn class_list_as_ref_str() {
+    enum CssClass {
+        Big,
+    }
+    impl AsRef<str> for CssClass {
+        fn as_ref(&self) -> &str {
+            match self {
+                CssClass::Big => "big",
+            }
+        }
+    }
+
+    assert_eq!(
+        html! { <div class=[CssClass::Big, Some(CssClass::Big)]></div> }.to_string(),
+        r#"<div class="big big"></div>"#
+    );
+}
+
+/// Verify that the style attribute gets parsed into the element's styles.
+#[test]
+fn style_attribute() {
+    let width = 5;
+    let is_hidden = true;
+
+    let mut expected = VElement::new("div");
+    expected.styles.set("color", "red");
+    expected.styles.set("width", "5px");
+    expected.styles.set("display", "none");
+
+    HtmlMacroTest {
+        generated: html! {
+            <div style=["color: red", &format!("width: {}px", width), is_hidden.then(|| "display: none")]>
+            </div>
+        },
+        expected: expected.into(),
+    }
+    .test();
+}
+
+/// Verify that we can use namespaced attributes such as `xlink:href`.
+#[test]
+fn namespaced_attribute() {
+    let mut expected = VElement::new("image");
+    expected
+        .attrs
+        .insert("xlink:href".to_string(), "#icon".into());
+    expected.attrs.insert("x".to_string(), "5".into());
+    expected.attrs.insert("y".to_string(), "6".into());
+
+    HtmlMacroTest {
+        generated: html! { <image x="5" xlink:href="#icon" y="6" /> },
+        expected: expected.into(),
+    }
+    .test();
+}
+
+/// Verify that the `ref` attribute sets the element's node ref instead of an attribute, without
+/// moving the node ref.
+#[test]
+fn node_ref() {
+    let canvas_ref = NodeRef::new();
+
+    let mut expected = VElement::new("canvas");
+    expected.special_attributes.set_node_ref(canvas_ref.clone());
+
+    HtmlMacroTest {
+        generated: html! { <canvas ref=canvas_ref></canvas> },
+        expected: expected.into(),
+    }
+    .test();
+
+    assert!(html! { <canvas ref=&canvas_ref></canvas> }
+        .as_velement_ref()
+        .unwrap()
+        .special_attributes
+        .node_ref()
+        .is_some());
+}
+
+/// Verify that `bind:value` sets the value and adds an oninput handler.
+#[test]
+fn bind_value() {
+    let name = Rc::new(RefCell::new("Alice".to_string()));
+
+    let node = html! { <input bind:value=name /> };
+    let element = node.as_velement_ref().unwrap();
+
+    assert_eq!(element.attrs.get("value"), Some(&"Alice".into()));
+    assert!(element
+        .events
+        .events()
+        .contains_key(&EventName::from("oninput")));
+}
+
+/// Verify that `bind:checked` sets whether or not the checkbox is checked.
+#[test]
+fn bind_checked() {
+    let agreed = Rc::new(RefCell::new(true));
+
+    let node = html! { <input type="checkbox" bind:checked=&agreed /> };
+    let element = node.as_velement_ref().unwrap();
+
+    assert_eq!(element.attrs.get("checked"), Some(&true.into()));
+    assert!(element
+        .events
+        .events()
+        .contains_key(&EventName::from("oninput")));
+}
+
+/// Verify that `bind:group` checks the radio button whose value is the group's value.
+#[test]
+fn bind_group() {
+    let color = Rc::new(RefCell::new("blue".to_string()));
+
+    let node = html! {
+        <div>
+          <input type="radio" bind:group=color value="red" />
+          <input type="radio" value="blue" bind:group=color />
+        </div>
+    };
+    let checked: Vec<Option<&AttributeValue>> = node
+        .as_velement_ref()
+        .unwrap()
+        .children
+        .iter()
+        .map(|radio| radio.as_velement_ref().unwrap().attrs.get("checked"))
+        .collect();
+
+    assert_eq!(checked, vec![Some(&false.into()), Some(&true.into())]);
+}
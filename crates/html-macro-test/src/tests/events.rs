@@ -60,6 +60,39 @@ fn stores_onclick_events() {
     assert!(matches!(event, EventHandler::MouseEvent(_)));
 }
 
+/// Verify that events with typed arguments are stored using the handler for their type, on every
+/// target.
+#[test]
+fn stores_typed_events() {
+    let node: VirtualNode = html! {
+        <form
+          oninput = |_: web_sys::InputEvent| {}
+          onkeydown = |_: web_sys::KeyboardEvent| {}
+          onsubmit = |_: web_sys::SubmitEvent| {}
+          onwheel = |_: web_sys::WheelEvent| {}
+          ondrop = |_: web_sys::DragEvent| {}
+          onpointerdown = |_: web_sys::PointerEvent| {}
+          ontouchstart = |_: web_sys::TouchEvent| {}
+          onfocus = |_: web_sys::FocusEvent| {}
+          onmousemove = |_: virtual_node::event::MouseEvent| {}
+        >
+        </form>
+    };
+    let events = &node.as_velement_ref().unwrap().events;
+
+    let handler = |name: &'static str| events.get(&name.into()).unwrap();
+
+    assert!(matches!(handler("oninput"), EventHandler::InputEvent(_)));
+    assert!(matches!(handler("onkeydown"), EventHandler::KeyboardEvent(_)));
+    assert!(matches!(handler("onsubmit"), EventHandler::SubmitEvent(_)));
+    assert!(matches!(handler("onwheel"), EventHandler::WheelEvent(_)));
+    assert!(matches!(handler("ondrop"), EventHandler::DragEvent(_)));
+    assert!(matches!(handler("onpointerdown"), EventHandler::PointerEvent(_)));
+    assert!(matches!(handler("ontouchstart"), EventHandler::TouchEvent(_)));
+    assert!(matches!(handler("onfocus"), EventHandler::FocusEvent(_)));
+    assert!(matches!(handler("onmousemove"), EventHandler::MouseEvent(_)));
+}
+
 /// Verify that we can set the on create element function.
 #[test]
 fn on_create_element() {
@@ -90,6 +123,37 @@ fn on_remove_element() {
     );
 }
 
+/// Verify that we can set the on update element function.
+#[test]
+fn on_update_element() {
+    let node: VirtualNode = html! {
+        <div key = "my-key" on_update_element=||{}> </div>
+    };
+    assert_eq!(
+        node.as_velement_ref()
+            .unwrap()
+            .special_attributes
+            .on_update_element_key(),
+        Some(&"my-key".into())
+    );
+}
+
+/// Verify that an on remove element function that takes a second argument defers the element's
+/// removal.
+#[test]
+fn on_remove_element_deferred() {
+    let node: VirtualNode = html! {
+        <div key = "my-key" on_remove_element=|_element, removal| removal.finish()> </div>
+    };
+    let special_attributes = &node.as_velement_ref().unwrap().special_attributes;
+
+    assert_eq!(
+        special_attributes.on_remove_element_key(),
+        Some(&"my-key".into())
+    );
+    assert!(special_attributes.defers_element_removal());
+}
+
 /// Verify that we do not need to provide the type for events that we support.
 ///
 /// We make use of the passed in type inside each closure.
@@ -103,13 +167,40 @@ fn eliding_event_arg_type() {
           onclick = |event| {
             event.stop_propagation();
           }
+          oninput = |event| {
+            event.data();
+          }
+          onkeydown = |event| {
+            event.key();
+          }
+          onsubmit = |event| {
+            event.submitter();
+          }
+          onwheel = |event| {
+            event.delta_y();
+          }
+          ondragstart = |event| {
+            event.client_x();
+          }
+          onpointermove = |event| {
+            event.pointer_id();
+          }
+          ontouchend = |event| {
+            event.alt_key();
+          }
+          onblur = |event| {
+            event.related_target();
+          }
           on_create_element = |element| {
             element.id();
           }
           on_remove_element = |element| {
             element.id();
           }
-          // TODO: As we add more supported events to `open_tag.rs` we can add handlers for them here.
+          on_update_element = |element, old_attrs, new_attrs| {
+            element.id();
+            assert_eq!(old_attrs.len(), new_attrs.len());
+          }
         >
         </div>
     };
@@ -0,0 +1,17 @@
+//! # To Run
+//!
+//! cargo test -p html-macro-test --lib ui -- trybuild=bind_with_oninput.rs
+
+extern crate percy_dom;
+use percy_dom::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Used `bind:value` along with an `oninput` handler, which `bind:value` already sets.
+fn main() {
+    let name = Rc::new(RefCell::new(String::new()));
+
+    html! {
+        <input bind:value=name oninput=|| {} />
+    };
+}
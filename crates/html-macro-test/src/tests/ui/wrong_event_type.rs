@@ -0,0 +1,13 @@
+//! # To Run
+//!
+//! cargo test -p html-macro-test --lib ui -- trybuild=wrong_event_type.rs
+
+extern crate percy_dom;
+use percy_dom::prelude::*;
+
+// Used a closure that takes a mouse event as an input event handler.
+fn main() {
+    html! {
+        <input oninput = |_: web_sys::MouseEvent| {} />
+    };
+}
@@ -0,0 +1,11 @@
+extern crate percy_dom;
+use percy_dom::prelude::*;
+
+// Expected a closing fragment tag, found a closing div tag
+fn main() {
+    html! {
+        <div>
+          <> <em></em> </div>
+        </div>
+    };
+}
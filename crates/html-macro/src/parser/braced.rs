@@ -53,6 +53,9 @@ impl HtmlParser {
             Some(Tag::Close {
                 first_angle_bracket_span,
                 ..
+            })
+            | Some(Tag::CloseFragment {
+                first_angle_bracket_span,
             }) => self.separated_by_whitespace(brace_span, &first_angle_bracket_span),
             Some(Tag::Braced {
                 brace_span: next_brace_span,
@@ -26,6 +26,20 @@ impl HtmlParser {
 
         let last_open_tag = parent_stack.pop().expect("Last open tag");
 
+        if self.fragments.contains(&last_open_tag.0) {
+            let error = format!(
+                r#"Wrong closing tag. Try changing "</{}>" into "</>""#,
+                close_tag
+            );
+
+            let error = quote_spanned! {close_span=> {
+                compile_error!(#error);
+            }};
+
+            self.push_tokens(error);
+            return;
+        }
+
         let last_open_tag = last_open_tag.1.to_string();
 
         // TODO: 2 compile_error!'s one pointing to the open tag and one pointing to the
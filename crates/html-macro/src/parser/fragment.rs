@@ -0,0 +1,55 @@
+use crate::parser::HtmlParser;
+use proc_macro2::{Ident, Span};
+use quote::{quote, quote_spanned};
+
+impl HtmlParser {
+    /// Parse an incoming Tag::OpenFragment
+    pub(crate) fn parse_open_fragment(&mut self, open_span: &Span, closing_span: &Span) {
+        self.set_most_recent_open_tag_end(*closing_span);
+
+        let idx = self.current_node_idx;
+
+        let var_name_node = Ident::new(format!("node_{}", idx).as_str(), *open_span);
+        self.push_tokens(quote! {
+            let mut #var_name_node = VirtualNode::fragment();
+        });
+
+        // Fragments don't have a name, but the parent stack needs one. We never compare it
+        // against a closing tag's name since we check whether or not the parent is a fragment
+        // first.
+        let name = Ident::new("fragment", *open_span);
+
+        if idx != 0 {
+            let parent_idx = self.parent_stack[self.parent_stack.len() - 1].0;
+
+            self.parent_to_children
+                .get_mut(&parent_idx)
+                .expect("Parent of this fragment")
+                .push(idx);
+            self.parent_to_children.insert(idx, vec![]);
+        }
+
+        self.parent_stack.push((idx, name));
+        self.node_order.push(idx);
+        self.fragments.insert(idx);
+
+        self.current_node_idx += 1;
+    }
+
+    /// Parse an incoming Tag::CloseFragment
+    pub(crate) fn parse_close_fragment(&mut self, close_span: &Span) {
+        let (last_open_idx, last_open_tag) = self.parent_stack.pop().expect("Last open tag");
+
+        if !self.fragments.contains(&last_open_idx) {
+            let error = format!(
+                r#"Wrong closing tag. Try changing "</>" into "</{}>""#,
+                last_open_tag
+            );
+
+            let error = quote_spanned! {*close_span=> {
+                compile_error!(#error);
+            }};
+            self.push_tokens(error);
+        }
+    }
+}
@@ -2,12 +2,13 @@ use crate::tag::TagKind;
 use crate::Tag;
 use proc_macro2::Span;
 use quote::{quote, quote_spanned};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use syn::spanned::Spanned;
 use syn::{Ident, Stmt};
 
 mod braced;
 mod close_tag;
+mod fragment;
 mod open_tag;
 mod statement;
 mod text;
@@ -39,6 +40,8 @@ pub struct HtmlParser {
     /// Key -> index of the parent node within the HTML tree
     /// Value -> vector of child node indices
     parent_to_children: HashMap<usize, Vec<usize>>,
+    /// The indices of the nodes that are fragments, `<>...</>`, instead of elements.
+    fragments: HashSet<usize>,
     /// The locations of the most recent spans that we parsed.
     /// Used to determine whether or not to put space around text nodes.
     recent_span_locations: RecentSpanLocations,
@@ -61,6 +64,7 @@ impl HtmlParser {
             node_order: vec![],
             parent_stack: vec![],
             parent_to_children,
+            fragments: HashSet::new(),
             recent_span_locations: RecentSpanLocations::default(),
             last_tag_kind: None,
         }
@@ -84,6 +88,19 @@ impl HtmlParser {
                 self.parse_close_tag(name);
                 self.last_tag_kind = Some(TagKind::Close);
             }
+            Tag::OpenFragment {
+                open_bracket_span,
+                closing_bracket_span,
+            } => {
+                self.parse_open_fragment(open_bracket_span, closing_bracket_span);
+                self.last_tag_kind = Some(TagKind::Open);
+            }
+            Tag::CloseFragment {
+                first_angle_bracket_span,
+            } => {
+                self.parse_close_fragment(first_angle_bracket_span);
+                self.last_tag_kind = Some(TagKind::Close);
+            }
             Tag::Text {
                 text,
                 start_span,
@@ -106,6 +123,7 @@ impl HtmlParser {
     pub fn finish(&mut self) -> proc_macro2::TokenStream {
         let node_order = &mut self.node_order;
         let parent_to_children = &mut self.parent_to_children;
+        let fragments = &self.fragments;
         let tokens = &mut self.tokens;
 
         if node_order.len() > 1 {
@@ -130,11 +148,18 @@ impl HtmlParser {
                             unreachable!("Non-elements cannot have children");
                         });
 
-                        let push_children = quote! {
-                            if let Some(ref mut element_node) = #parent_name.as_velement_mut() {
-                                element_node.children.extend(#children.into_iter());
-                            } else {
-                                #unreachable;
+                        let push_children = if fragments.contains(&parent_idx) {
+                            quote! {
+                                #parent_name.as_vfragment_mut().unwrap()
+                                    .children.extend(#children.into_iter());
+                            }
+                        } else {
+                            quote! {
+                                if let Some(ref mut element_node) = #parent_name.as_velement_mut() {
+                                    element_node.children.extend(#children.into_iter());
+                                } else {
+                                    #unreachable;
+                                }
                             }
                         };
 
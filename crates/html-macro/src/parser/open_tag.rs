@@ -1,11 +1,13 @@
+use crate::parser::open_tag::bind::insert_binding_tokens;
 use crate::parser::open_tag::event::insert_closure_tokens;
 use crate::parser::{is_self_closing, is_valid_tag, HtmlParser};
 use crate::tag::Attr;
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, quote_spanned};
-use syn::Expr;
 use syn::__private::TokenStream2;
+use syn::Expr;
 
+mod bind;
 mod event;
 
 impl HtmlParser {
@@ -90,28 +92,81 @@ fn create_valid_node(
     //   html! { <div key = "..." ></div>
     let key_attr = attrs
         .iter()
-        .find(|attr| attr.key.to_string() == "key")
+        .find(|attr| attr.name() == "key")
         .map(|attr| &attr.value);
 
+    let mut bindings = vec![];
+
     for attr in attrs.iter() {
-        let key = format!("{}", attr.key);
+        let key = attr.name();
         let value = &attr.value;
 
+        // html! { <input bind:value=name /> }
+        if attr
+            .namespace_prefix
+            .as_ref()
+            .map(|prefix| prefix == "bind")
+            == Some(true)
+        {
+            bindings.push(attr);
+            continue;
+        }
+
+        // html! { <canvas ref=canvas_ref></canvas> }
+        //
+        // Cloned so that both `ref=my_ref` and `ref=&my_ref` work.
+        if key == "ref" {
+            let set_node_ref = quote! {
+                #var_name_node.as_velement_mut().expect("Not an element")
+                    .special_attributes.set_node_ref((#value).clone());
+            };
+            tokens.push(set_node_ref);
+
+            continue;
+        }
+
         match value {
             Expr::Closure(closure) => {
                 let add_closure = insert_closure_tokens(var_name_node, attr, &closure, key_attr);
                 tokens.push(add_closure);
             }
+            Expr::Array(array) => {
+                let items = array.elems.iter();
+
+                // See `ListItem` in virtual-node's attribute_value.rs
+                let insert_list = quote! {
+                    {
+                        #[allow(unused_imports)]
+                        use __html_macro_helpers__::{ListItem, OptionalListItem, RequiredListItem};
+
+                        let mut list: Vec<String> = vec![];
+                        #(
+                            if let Some(item) = (&ListItem(&(#items))).list_item() {
+                                list.push(item.to_string());
+                            }
+                        )*
+
+                        #var_name_node.as_velement_mut().expect("Not an element")
+                            .set_attribute(#key, list);
+                    }
+                };
+
+                tokens.push(insert_list);
+            }
             _ => {
                 let insert_attribute = quote! {
                     #var_name_node.as_velement_mut().expect("Not an element")
-                        .attrs.insert(#key.to_string(), #value.into());
+                        .set_attribute(#key, #value);
                 };
 
                 tokens.push(insert_attribute);
             }
         };
     }
+
+    for binding in bindings {
+        tokens.push(insert_binding_tokens(var_name_node, binding, attrs));
+    }
 }
 
 fn invalid_tag_compile_error(name: &Ident, html_tag: &str, var_name_node: &Ident) -> TokenStream {
@@ -138,6 +193,8 @@ custom components: https://chinedufn.github.io/percy/html-macro/custom-component
     compile_err
 }
 
+/// A custom component is either a `View`, which gets rendered right away, or a `Component`, which
+/// becomes a `VirtualNode::Component` that gets rendered when it's needed.
 fn component_node(
     idx: usize,
     name: &Ident,
@@ -155,14 +212,14 @@ fn component_node(
             let value = &attr.value;
 
             quote! {
-                #key: #value,
+                #key: #value
             }
         })
         .collect();
 
     let node = quote! {
         let mut #var_name_component = #component_ident { #(#component_props),* };
-        let mut #var_name_node = #var_name_component.render();
+        let mut #var_name_node = #var_name_component.__into_virtual_node();
     };
 
     node
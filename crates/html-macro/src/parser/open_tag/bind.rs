@@ -0,0 +1,102 @@
+use crate::parser::open_tag::event::insert_closure_tokens;
+use crate::tag::Attr;
+use proc_macro2::{Ident, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::{parse_quote, Expr, ExprClosure};
+
+// Create the tokens for a `bind:...` attribute, which sets an attribute using a shared value and
+// adds an `oninput` handler that stores the form control's new state in the shared value.
+//
+// ex: `bind:value=name` where `name` is an `Rc<RefCell<String>>`.
+//
+// These run after the element's other attributes have been set, since `bind:group` compares the
+// element's `value` attribute to the shared value.
+//
+// Tests can be found in crates/html-macro-test/src/tests/attributes.rs
+pub(super) fn insert_binding_tokens(
+    var_name_node: &Ident,
+    bind_attr: &Attr,
+    attrs: &[Attr],
+) -> TokenStream {
+    let binding = bind_attr.key.to_string();
+    let key_span = bind_attr.key.span();
+
+    if attrs.iter().any(|attr| attr.name() == "oninput") {
+        let error = format!(
+            "`bind:{}` sets the element's `oninput` handler, so it can't be used along with `oninput=...`.",
+            binding
+        );
+        return quote_spanned! { key_span => compile_error!(#error); };
+    }
+
+    // `RefCell::borrow` instead of `.borrow()`, which would call `Borrow::borrow` on the `Rc` when
+    // the `Borrow` trait is in scope.
+    let (set_attribute, bind_fn) = match binding.as_str() {
+        "value" => (
+            quote! {
+                let value = std::cell::RefCell::borrow(&bound).clone();
+                #var_name_node.as_velement_mut().expect("Not an element")
+                    .set_attribute("value", value);
+            },
+            quote! { bind_value },
+        ),
+        "checked" => (
+            quote! {
+                let checked = *std::cell::RefCell::borrow(&bound);
+                #var_name_node.as_velement_mut().expect("Not an element")
+                    .set_attribute("checked", checked);
+            },
+            quote! { bind_checked },
+        ),
+        "group" => {
+            if !attrs.iter().any(|attr| attr.name() == "value") {
+                let error = "`bind:group` needs the radio button's `value=...` attribute.";
+                return quote_spanned! { key_span => compile_error!(#error); };
+            }
+
+            // A radio button is checked when the group's value is the radio button's value.
+            (
+                quote! {
+                    let checked = #var_name_node.as_velement_ref().expect("Not an element")
+                        .attrs
+                        .get("value")
+                        .map(|value| value.to_string())
+                        == Some(std::cell::RefCell::borrow(&bound).clone());
+                    #var_name_node.as_velement_mut().expect("Not an element")
+                        .set_attribute("checked", checked);
+                },
+                quote! { bind_group },
+            )
+        }
+        _ => {
+            let error = format!(
+                "Unknown binding `bind:{}`. Use `bind:value`, `bind:checked` or `bind:group`.",
+                binding
+            );
+            return quote_spanned! { key_span => compile_error!(#error); };
+        }
+    };
+
+    let closure: ExprClosure = parse_quote! {
+        move |event: __html_macro_helpers__::web_sys::InputEvent| {
+            __html_macro_helpers__::#bind_fn(&event, &bound)
+        }
+    };
+    let oninput = Attr {
+        namespace_prefix: None,
+        key: Ident::new("oninput", key_span),
+        value: Expr::Closure(closure.clone()),
+    };
+    let insert_closure = insert_closure_tokens(var_name_node, &oninput, &closure, None);
+
+    let bound = &bind_attr.value;
+
+    // Cloned so that both `bind:value=name` and `bind:value=&name` work.
+    quote! {
+        {
+            let bound = (#bound).clone();
+            #set_attribute
+            #insert_closure
+        }
+    }
+}
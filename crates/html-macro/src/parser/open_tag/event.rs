@@ -1,7 +1,8 @@
 use crate::tag::Attr;
-use proc_macro2::{Ident, TokenStream};
+use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, quote_spanned};
 use syn::__private::TokenStream2;
+use syn::spanned::Spanned;
 use syn::{Expr, ExprClosure, Pat, PatType, Type};
 
 // This only gets used in cases where we're generating a compile time error for not using a key..
@@ -22,30 +23,13 @@ pub(super) fn insert_closure_tokens(
 
     let attr_key_span = &event_attribute.key.span();
 
-    // TODO: Refactor duplicate code between these blocks.
     if event_name == "on_create_element" {
-        let mut maybe_missing_key_error = None;
-
-        let key_attr_value = if let Some(key_attr_value) = key_attr_value {
-            quote! {
-                #key_attr_value
-            }
-        } else {
-            let error = format!(
-                r#"Whenever you use the `on_create_element=...` attribute,
-you must also use must use the `key="..."` attribute.
-
-Documentation:
-  -> https://chinedufn.github.io/percy/html-macro/real-elements-and-nodes/on-create-elem/index.html
-            "#
-            );
-
-            maybe_missing_key_error = Some(quote_spanned! { attr_key_span.clone() => {
-                compile_error!(#error);
-            }});
-
-            quote! { #FAKE_KEY }
-        };
+        let (key_attr_value, maybe_missing_key_error) = special_attribute_key(
+            &event_name,
+            "on-create-elem",
+            key_attr_value,
+            *attr_key_span,
+        );
 
         let closure =
             maybe_set_arg_type(closure, quote! { __html_macro_helpers__::web_sys::Element });
@@ -65,36 +49,58 @@ Documentation:
             #maybe_missing_key_error
         }
     } else if event_name == "on_remove_element" {
-        let mut maybe_missing_key_error = None;
+        let (key_attr_value, maybe_missing_key_error) = special_attribute_key(
+            &event_name,
+            "on-remove-elem",
+            key_attr_value,
+            *attr_key_span,
+        );
 
-        let key_attr_value = if let Some(key_attr_value) = key_attr_value {
-            quote! {
-                #key_attr_value
-            }
-        } else {
-            let error = format!(
-                r#"Whenever you use the `on_remove_element=...` attribute,
-you must also use must use the `key="..."` attribute.
+        let closure =
+            maybe_set_arg_type(closure, quote! { __html_macro_helpers__::web_sys::Element });
+        let closure = maybe_set_nth_arg_type(
+            &closure,
+            1,
+            quote! { __html_macro_helpers__::DeferredRemoval },
+        );
+
+        // A second argument means that the function removes the element itself.
+        let setter = match arg_count {
+            0 => quote! { set_on_remove_element_no_args },
+            1 => quote! { set_on_remove_element },
+            _ => quote! { set_on_remove_element_deferred },
+        };
 
-Documentation:
-  -> https://chinedufn.github.io/percy/html-macro/real-elements-and-nodes/on-remove-elem/index.html
-            "#
-            );
+        quote! {
+            let event_callback = #closure;
 
-            maybe_missing_key_error = Some(quote_spanned! { attr_key_span.clone() => {
-                compile_error!(#error);
-            }});
+            #var_name_node.as_velement_mut().unwrap()
+              .special_attributes.#setter(#key_attr_value.to_string(), event_callback);
 
-            quote! { #FAKE_KEY }
+            #maybe_missing_key_error
+        }
+    } else if event_name == "on_update_element" {
+        let (key_attr_value, maybe_missing_key_error) = special_attribute_key(
+            &event_name,
+            "on-update-elem",
+            key_attr_value,
+            *attr_key_span,
+        );
+
+        // The attributes are borrowed, so without these types the closure would not accept
+        // references with any lifetime.
+        let attrs_type = quote! {
+            &std::collections::HashMap<String, __html_macro_helpers__::AttributeValue>
         };
-
         let closure =
             maybe_set_arg_type(closure, quote! { __html_macro_helpers__::web_sys::Element });
+        let closure = maybe_set_nth_arg_type(&closure, 1, attrs_type.clone());
+        let closure = maybe_set_nth_arg_type(&closure, 2, attrs_type);
 
         let setter = if arg_count == 0 {
-            quote! { set_on_remove_element_no_args }
+            quote! { set_on_update_element_no_args }
         } else {
-            quote! { set_on_remove_element }
+            quote! { set_on_update_element }
         };
 
         quote! {
@@ -115,34 +121,27 @@ Documentation:
                 )
             );
         }
-    } else if event_name == "onclick" {
-        let tokens = quote! {
-            #var_name_node.as_velement_mut().unwrap().events.insert_mouse_event(
-                #event_name.into(),
-                std::rc::Rc::new(
-                    std::cell::RefCell::new( event_callback )
-                )
-            );
+    } else if let Some(typed_event) = TypedEvent::from_event_name(&event_name) {
+        let arg_type = typed_event.arg_type();
+        let insert_fn = typed_event.insert_fn();
+
+        let closure = maybe_set_arg_type(closure, arg_type.clone());
+
+        // Annotating the callback's type means that a closure that takes the wrong type of event
+        // fails to compile with an error that names the event type that we expected.
+        let closure_span = closure.span();
+        let event_callback = quote_spanned! { closure_span =>
+            let event_callback: std::rc::Rc<std::cell::RefCell<dyn FnMut(#arg_type)>> =
+                std::rc::Rc::new(std::cell::RefCell::new(#closure));
         };
 
-        let tokens = if arg_count == 0 {
-            quote! {
-                let event_callback = #closure;
-                #tokens
-            }
-        } else {
-            let closure = maybe_set_arg_type(
-                closure,
-                quote! { __html_macro_helpers__::event::MouseEvent },
+        quote! {
+            #event_callback
+            #var_name_node.as_velement_mut().unwrap().events.#insert_fn(
+                #event_name.into(),
+                event_callback
             );
-
-            quote! {
-                let event_callback = #closure;
-                #tokens
-            }
-        };
-
-        tokens
+        }
     } else {
         let arg_type_placeholders: Vec<TokenStream2> =
             (0..arg_count).into_iter().map(|_| quote! { _ }).collect();
@@ -169,6 +168,110 @@ Documentation:
     }
 }
 
+/// The tokens for the `key` attribute's value, which special attribute functions such as
+/// `on_create_element` are required to have, along with a compile time error if it is missing.
+fn special_attribute_key(
+    attribute: &str,
+    docs_page: &str,
+    key_attr_value: Option<&Expr>,
+    attr_key_span: Span,
+) -> (TokenStream, Option<TokenStream>) {
+    if let Some(key_attr_value) = key_attr_value {
+        return (quote! { #key_attr_value }, None);
+    }
+
+    let error = format!(
+        r#"Whenever you use the `{}=...` attribute,
+you must also use must use the `key="..."` attribute.
+
+Documentation:
+  -> https://chinedufn.github.io/percy/html-macro/real-elements-and-nodes/{}/index.html
+            "#,
+        attribute, docs_page
+    );
+
+    let missing_key_error = quote_spanned! { attr_key_span => {
+        compile_error!(#error);
+    }};
+
+    (quote! { #FAKE_KEY }, Some(missing_key_error))
+}
+
+/// Events whose handlers receive a typed event argument.
+///
+/// Handlers for these events get stored on every target, and their argument's type can be
+/// elided.
+enum TypedEvent {
+    Mouse,
+    Input,
+    Keyboard,
+    Submit,
+    Wheel,
+    Drag,
+    Pointer,
+    Touch,
+    Focus,
+}
+
+impl TypedEvent {
+    fn from_event_name(event_name: &str) -> Option<Self> {
+        let typed_event = match event_name {
+            "onclick" | "onauxclick" | "ondblclick" | "oncontextmenu" | "onmousedown"
+            | "onmouseup" | "onmousemove" | "onmouseover" | "onmouseout" | "onmouseenter"
+            | "onmouseleave" => TypedEvent::Mouse,
+            "oninput" | "onbeforeinput" => TypedEvent::Input,
+            "onkeydown" | "onkeyup" | "onkeypress" => TypedEvent::Keyboard,
+            "onsubmit" => TypedEvent::Submit,
+            "onwheel" => TypedEvent::Wheel,
+            "ondrag" | "ondragend" | "ondragenter" | "ondragleave" | "ondragover"
+            | "ondragstart" | "ondrop" => TypedEvent::Drag,
+            "onpointerdown"
+            | "onpointerup"
+            | "onpointermove"
+            | "onpointerover"
+            | "onpointerout"
+            | "onpointerenter"
+            | "onpointerleave"
+            | "onpointercancel"
+            | "ongotpointercapture"
+            | "onlostpointercapture" => TypedEvent::Pointer,
+            "ontouchstart" | "ontouchend" | "ontouchmove" | "ontouchcancel" => TypedEvent::Touch,
+            "onfocus" | "onblur" | "onfocusin" | "onfocusout" => TypedEvent::Focus,
+            _ => return None,
+        };
+
+        Some(typed_event)
+    }
+
+    fn arg_type(&self) -> TokenStream2 {
+        match self {
+            TypedEvent::Mouse => quote! { __html_macro_helpers__::event::MouseEvent },
+            TypedEvent::Input => quote! { __html_macro_helpers__::web_sys::InputEvent },
+            TypedEvent::Keyboard => quote! { __html_macro_helpers__::web_sys::KeyboardEvent },
+            TypedEvent::Submit => quote! { __html_macro_helpers__::web_sys::SubmitEvent },
+            TypedEvent::Wheel => quote! { __html_macro_helpers__::web_sys::WheelEvent },
+            TypedEvent::Drag => quote! { __html_macro_helpers__::web_sys::DragEvent },
+            TypedEvent::Pointer => quote! { __html_macro_helpers__::web_sys::PointerEvent },
+            TypedEvent::Touch => quote! { __html_macro_helpers__::web_sys::TouchEvent },
+            TypedEvent::Focus => quote! { __html_macro_helpers__::web_sys::FocusEvent },
+        }
+    }
+
+    fn insert_fn(&self) -> TokenStream2 {
+        match self {
+            TypedEvent::Mouse => quote! { insert_mouse_event },
+            TypedEvent::Input => quote! { insert_input_event },
+            TypedEvent::Keyboard => quote! { insert_keyboard_event },
+            TypedEvent::Submit => quote! { insert_submit_event },
+            TypedEvent::Wheel => quote! { insert_wheel_event },
+            TypedEvent::Drag => quote! { insert_drag_event },
+            TypedEvent::Pointer => quote! { insert_pointer_event },
+            TypedEvent::Touch => quote! { insert_touch_event },
+            TypedEvent::Focus => quote! { insert_focus_event },
+        }
+    }
+}
+
 /// Clone the incoming closure tokens.
 ///
 /// - If the closure does not have any arguments, return the closure.
@@ -188,15 +291,20 @@ Documentation:
 ///
 /// `|foo: Bar| {}` -> BECOMES -> `|foo: Bar| {}`
 fn maybe_set_arg_type(closure: &ExprClosure, ty: TokenStream2) -> ExprClosure {
+    maybe_set_nth_arg_type(closure, 0, ty)
+}
+
+/// Like [`maybe_set_arg_type`], but for the closure's nth argument.
+fn maybe_set_nth_arg_type(closure: &ExprClosure, n: usize, ty: TokenStream2) -> ExprClosure {
     let mut closure = closure.clone();
 
-    if closure.inputs.len() == 0 {
+    if closure.inputs.len() <= n {
         return closure;
     }
 
-    let arg0 = closure.inputs.first_mut().unwrap();
+    let arg = &mut closure.inputs[n];
 
-    if let Pat::Ident(ident) = arg0 {
+    if let Pat::Ident(ident) = arg {
         // Add the type to the closure to avoid `type annotations needed` errors.
         // Example:
         //   Start: |arg| {}
@@ -204,7 +312,7 @@ fn maybe_set_arg_type(closure: &ExprClosure, ty: TokenStream2) -> ExprClosure {
 
         let ident = Pat::Ident(ident.clone());
 
-        *arg0 = Pat::Type(PatType {
+        *arg = Pat::Type(PatType {
             attrs: vec![],
             pat: Box::new(ident),
             colon_token: Default::default(),
@@ -22,12 +22,18 @@ impl HtmlParser {
             Some(Tag::Close {
                 first_angle_bracket_span,
                 ..
+            })
+            | Some(Tag::CloseFragment {
+                first_angle_bracket_span,
             }) => self.separated_by_whitespace(&text_end, first_angle_bracket_span),
             Some(Tag::Braced { brace_span, .. }) => {
                 self.separated_by_whitespace(&text_end, brace_span)
             }
             Some(Tag::Open {
                 open_bracket_span, ..
+            })
+            | Some(Tag::OpenFragment {
+                open_bracket_span, ..
             }) => self.separated_by_whitespace(&text_end, open_bracket_span),
             _ => false,
         };
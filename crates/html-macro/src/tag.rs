@@ -23,6 +23,13 @@ pub enum Tag {
         name: Ident,
         first_angle_bracket_span: Span,
     },
+    /// <>
+    OpenFragment {
+        open_bracket_span: Span,
+        closing_bracket_span: Span,
+    },
+    /// </>
+    CloseFragment { first_angle_bracket_span: Span },
     /// html! { <div> Hello World </div> }
     ///
     ///  -> Hello world
@@ -66,13 +73,26 @@ pub enum TagKind {
 
 /// id="my-id"
 /// class="some classes"
+/// xlink:href="#my-symbol"
 /// etc...
 #[derive(Debug)]
 pub struct Attr {
+    /// The `xlink` in `xlink:href`.
+    pub namespace_prefix: Option<Ident>,
     pub key: Ident,
     pub value: Expr,
 }
 
+impl Attr {
+    /// The attribute's name, including its namespace prefix if it has one.
+    pub fn name(&self) -> String {
+        match &self.namespace_prefix {
+            Some(prefix) => format!("{}:{}", prefix, self.key),
+            None => self.key.to_string(),
+        }
+    }
+}
+
 impl Parse for Tag {
     fn parse(input: ParseStream) -> Result<Self> {
         let mut input = input;
@@ -105,6 +125,16 @@ impl Parse for Tag {
 
 /// `<div id="app" class=*CSS>`
 fn parse_open_tag(input: &mut ParseStream, open_bracket_span: Span) -> Result<Tag> {
+    // <>
+    if input.peek(Token![>]) {
+        let closing_bracket = input.parse::<Token![>]>()?;
+
+        return Ok(Tag::OpenFragment {
+            open_bracket_span,
+            closing_bracket_span: closing_bracket.span(),
+        });
+    }
+
     let name: Ident = input.parse()?;
 
     let attrs = parse_attributes(input)?;
@@ -139,29 +169,18 @@ fn parse_attributes(input: &mut ParseStream) -> Result<Vec<Attr>> {
         || input.peek(Token![async])
         || input.peek(Token![for])
         || input.peek(Token![loop])
+        || input.peek(Token![ref])
         || input.peek(Token![type])
     {
-        // <link rel="stylesheet" type="text/css"
-        //   .. as, async, for, loop, type need to be handled specially since they are keywords
-        let maybe_as_key: Option<Token![as]> = input.parse()?;
-        let maybe_async_key: Option<Token![async]> = input.parse()?;
-        let maybe_for_key: Option<Token![for]> = input.parse()?;
-        let maybe_loop_key: Option<Token![loop]> = input.parse()?;
-        let maybe_type_key: Option<Token![type]> = input.parse()?;
-
-        let key = if maybe_as_key.is_some() {
-            Ident::new("as", maybe_as_key.unwrap().span())
-        } else if maybe_async_key.is_some() {
-            Ident::new("async", maybe_async_key.unwrap().span())
-        } else if maybe_for_key.is_some() {
-            Ident::new("for", maybe_for_key.unwrap().span())
-        } else if maybe_loop_key.is_some() {
-            Ident::new("loop", maybe_loop_key.unwrap().span())
-        } else if maybe_type_key.is_some() {
-            Ident::new("type", maybe_type_key.unwrap().span())
-        } else {
-            input.parse()?
-        };
+        let mut key = parse_attribute_key(input)?;
+
+        // xlink:href="#my-symbol"
+        let mut namespace_prefix = None;
+        if input.peek(Token![:]) {
+            input.parse::<Token![:]>()?;
+            namespace_prefix = Some(key);
+            key = parse_attribute_key(input)?;
+        }
 
         // =
         input.parse::<Token![=]>()?;
@@ -178,28 +197,90 @@ fn parse_attributes(input: &mut ParseStream) -> Result<Vec<Attr>> {
                 || input.peek(Token![async])
                 || input.peek(Token![for])
                 || input.peek(Token![loop])
+                || input.peek(Token![ref])
                 || input.peek(Token![type]);
             let peek_start_of_next_attr = has_attrib_key && input.peek2(Token![=]);
+            let peek_start_of_next_namespaced_attr = peek_namespaced_attribute(input);
 
             let peek_end_of_tag = input.peek(Token![>]);
 
             let peek_self_closing = input.peek(Token![/]);
 
-            if peek_end_of_tag || peek_start_of_next_attr || peek_self_closing {
+            if peek_end_of_tag
+                || peek_start_of_next_attr
+                || peek_start_of_next_namespaced_attr
+                || peek_self_closing
+            {
                 break;
             }
         }
 
         let value: Expr = syn::parse2(value_tokens)?;
 
-        attrs.push(Attr { key, value });
+        attrs.push(Attr {
+            namespace_prefix,
+            key,
+            value,
+        });
     }
 
     Ok(attrs)
 }
 
+/// Whether or not the next tokens are the start of an attribute such as `xlink:href=`.
+///
+/// We look for the `=` so that the `:` in a closure's `|event: MouseEvent|` doesn't count.
+fn peek_namespaced_attribute(input: &mut ParseStream) -> bool {
+    let fork = input.fork();
+
+    fork.parse::<Ident>().is_ok()
+        && fork.parse::<Token![:]>().is_ok()
+        && !fork.peek(Token![:])
+        && parse_attribute_key(&mut &fork).is_ok()
+        && fork.peek(Token![=])
+}
+
+/// Parse an attribute key such as `id`.
+fn parse_attribute_key(input: &mut ParseStream) -> Result<Ident> {
+    // <link rel="stylesheet" type="text/css"
+    //   .. as, async, for, loop, ref, type need to be handled specially since they are keywords
+    let maybe_as_key: Option<Token![as]> = input.parse()?;
+    let maybe_async_key: Option<Token![async]> = input.parse()?;
+    let maybe_for_key: Option<Token![for]> = input.parse()?;
+    let maybe_loop_key: Option<Token![loop]> = input.parse()?;
+    let maybe_ref_key: Option<Token![ref]> = input.parse()?;
+    let maybe_type_key: Option<Token![type]> = input.parse()?;
+
+    let key = if maybe_as_key.is_some() {
+        Ident::new("as", maybe_as_key.unwrap().span())
+    } else if maybe_async_key.is_some() {
+        Ident::new("async", maybe_async_key.unwrap().span())
+    } else if maybe_for_key.is_some() {
+        Ident::new("for", maybe_for_key.unwrap().span())
+    } else if maybe_loop_key.is_some() {
+        Ident::new("loop", maybe_loop_key.unwrap().span())
+    } else if let Some(ref_key) = maybe_ref_key {
+        Ident::new("ref", ref_key.span())
+    } else if maybe_type_key.is_some() {
+        Ident::new("type", maybe_type_key.unwrap().span())
+    } else {
+        input.parse()?
+    };
+
+    Ok(key)
+}
+
 /// </div>
 fn parse_close_tag(input: &mut ParseStream, first_angle_bracket_span: Span) -> Result<Tag> {
+    // </>
+    if input.peek(Token![>]) {
+        input.parse::<Token![>]>()?;
+
+        return Ok(Tag::CloseFragment {
+            first_angle_bracket_span,
+        });
+    }
+
     let name: Ident = input.parse()?;
 
     input.parse::<Token![>]>()?;
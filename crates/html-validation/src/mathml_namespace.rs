@@ -0,0 +1,58 @@
+use lazy_static::lazy_static;
+use std::collections::HashSet;
+
+lazy_static! {
+    // list of MathML elements
+    //  https://developer.mozilla.org/en-US/docs/Web/MathML/Element
+    static ref MATHML_NAMESPACED_TAGS: HashSet<&'static str> = [
+        "annotation",
+        "annotation-xml",
+        "maction",
+        "math",
+        "menclose",
+        "merror",
+        "mfenced",
+        "mfrac",
+        "mi",
+        "mmultiscripts",
+        "mn",
+        "mo",
+        "mover",
+        "mpadded",
+        "mphantom",
+        "mprescripts",
+        "mroot",
+        "mrow",
+        "ms",
+        "mspace",
+        "msqrt",
+        "mstyle",
+        "msub",
+        "msubsup",
+        "msup",
+        "mtable",
+        "mtd",
+        "mtext",
+        "mtr",
+        "munder",
+        "munderover",
+        "semantics",
+    ]
+    .iter()
+    .cloned()
+    .collect();
+}
+
+/// Whether or not this tag is a MathML element
+/// ```
+/// use html_validation::is_mathml_namespace;
+///
+/// assert_eq!(is_mathml_namespace("math"), true);
+///
+/// assert_eq!(is_mathml_namespace("mfrac"), true);
+///
+/// assert_eq!(is_mathml_namespace("div"), false);
+/// ```
+pub fn is_mathml_namespace(tag: &str) -> bool {
+    MATHML_NAMESPACED_TAGS.contains(tag)
+}
@@ -6,7 +6,7 @@ lazy_static! {
     //  https://developer.mozilla.org/en-US/docs/Web/SVG/Element
     //  a hashmap of `(tag, is_self_closing)`
     static ref SVG_NAMESPACED_TAGS: HashMap<&'static str, bool> = [
-        // TODO: can cause conflict with html `a`
+        // Shared with the html `a` tag, so it inherits its namespace from its parent
         //("a", true),
         ("animate", true),
         ("animateMotion", false),
@@ -70,19 +70,19 @@ lazy_static! {
         ("polyline", true),
         ("radialGradient", false),
         ("rect", true),
-        // TODO: can cause conflict with html `script` tag
+        // Shared with the html `script` tag, so it inherits its namespace from its parent
         //("script", false),
         ("set", true),
         ("solidcolor", true),
         ("stop", true),
-        // TODO: can cause conflict with html `style` tag
+        // Shared with the html `style` tag, so it inherits its namespace from its parent
         //("style", false),
         ("svg", false),
         ("switch", false),
         ("symbol", false),
         ("text", false),
         ("textPath", false),
-        // TODO: can cause conflict with html `title` tag
+        // Shared with the html `title` tag, so it inherits its namespace from its parent
         //("title", false),
         ("tspan", false),
         // TODO: undocumented
@@ -0,0 +1,239 @@
+//! Benchmarks for diffing virtual nodes.
+//!
+//! cargo bench -p percy-dom --bench diff
+
+// Criterion can't be compiled to wasm32, so there is nothing to benchmark there.
+#![cfg_attr(target_arch = "wasm32", no_main)]
+#![cfg(not(target_arch = "wasm32"))]
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use percy_dom::{diff, Patch};
+use std::cell::RefCell;
+use std::rc::Rc;
+use virtual_node::event::EventHandler;
+use virtual_node::{VElement, VirtualNode};
+
+/// Builds a tree whose text nodes contain the given text.
+type Tree = fn(&str) -> VirtualNode;
+
+fn diff_trees(c: &mut Criterion) {
+    let trees: [(&str, Tree); 3] = [
+        ("table", |text| table(100, text)),
+        ("nested_layout", |text| nested_layout(8, text)),
+        ("wide_list", |text| wide_list(2_000, text)),
+    ];
+
+    let mut group = c.benchmark_group("diff");
+
+    for (name, tree) in trees.iter() {
+        let old = tree("before");
+
+        // The most common case, where a re-render produced the same view.
+        let unchanged = tree("before");
+        group.bench_with_input(
+            BenchmarkId::new("unchanged", name),
+            &(&old, &unchanged),
+            |b, (old, new)| b.iter(|| diff(black_box(old), black_box(new))),
+        );
+
+        let mut one_change = tree("before");
+        change_last_text(&mut one_change);
+        group.bench_with_input(
+            BenchmarkId::new("one_text_changed", name),
+            &(&old, &one_change),
+            |b, (old, new)| b.iter(|| diff(black_box(old), black_box(new))),
+        );
+
+        let every_text_changed = tree("after");
+        group.bench_with_input(
+            BenchmarkId::new("every_text_changed", name),
+            &(&old, &every_text_changed),
+            |b, (old, new)| b.iter(|| diff(black_box(old), black_box(new))),
+        );
+    }
+
+    group.finish();
+}
+
+/// Views that get restructured, where most of the work is in removing, creating and moving nodes.
+///
+/// The number of patches and of DOM nodes that they create gets printed for each view, since a
+/// smaller patch means fewer DOM operations when it gets applied.
+fn diff_restructured_trees(c: &mut Criterion) {
+    let restructured = [
+        // The last half of a list moves to the end of another list.
+        (
+            "items_moved_to_other_list",
+            two_lists(2_000, 0),
+            two_lists(2_000, 1_000),
+        ),
+        // A wrapper's tag changes, replacing a table whose rows have event handlers.
+        (
+            "parent_of_handlers_replaced",
+            wrapped("div", clickable_table(100)),
+            wrapped("section", clickable_table(100)),
+        ),
+    ];
+
+    let mut group = c.benchmark_group("diff_restructured");
+
+    for (name, old, new) in restructured.iter() {
+        let patches = diff(old, new);
+        println!(
+            "{}: {} patches, {} created nodes",
+            name,
+            patches.len(),
+            created_nodes(&patches)
+        );
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &(old, new),
+            |b, (old, new)| b.iter(|| diff(black_box(old), black_box(new))),
+        );
+    }
+
+    group.finish();
+}
+
+/// A table with a header and many rows of cells, similar to a large page.
+fn table(rows: usize, text: &str) -> VirtualNode {
+    let mut table = VElement::new("table");
+    table.attrs.insert("class".into(), "data-table".into());
+
+    for row in 0..rows {
+        let mut tr = VElement::new("tr");
+        tr.attrs.insert("id".into(), format!("row-{}", row).into());
+
+        for col in 0..10 {
+            let mut td = VElement::new("td");
+            td.attrs.insert("class".into(), "cell".into());
+            td.children
+                .push(VirtualNode::text(format!("{} {} {}", text, row, col)));
+            tr.children.push(td.into());
+        }
+
+        table.children.push(tr.into());
+    }
+
+    table.into()
+}
+
+/// Layouts nested inside of layouts, where every level has a sidebar and a content area.
+fn nested_layout(depth: usize, text: &str) -> VirtualNode {
+    let mut layout = VElement::new("div");
+    layout.attrs.insert("class".into(), "layout".into());
+
+    let mut sidebar = VElement::new("aside");
+    sidebar.attrs.insert("class".into(), "sidebar".into());
+    sidebar
+        .children
+        .push(VirtualNode::text(format!("{} {}", text, depth)));
+    layout.children.push(sidebar.into());
+
+    let mut content = VElement::new("section");
+    content.attrs.insert("class".into(), "content".into());
+    if depth > 0 {
+        content.children.push(nested_layout(depth - 1, text));
+        content.children.push(nested_layout(depth - 1, text));
+    }
+    layout.children.push(content.into());
+
+    layout.into()
+}
+
+/// A list with many items, such as a feed or a long search result page.
+fn wide_list(items: usize, text: &str) -> VirtualNode {
+    let mut list = VElement::new("ul");
+
+    for item in 0..items {
+        let mut li = VElement::new("li");
+        li.attrs.insert("class".into(), "item".into());
+        li.children
+            .push(VirtualNode::text(format!("{} {}", text, item)));
+        list.children.push(li.into());
+    }
+
+    list.into()
+}
+
+/// Two lists, where the last `moved` items of the first list are at the end of the second list.
+fn two_lists(items: usize, moved: usize) -> VirtualNode {
+    let mut first = VElement::new("ul");
+    let mut second = VElement::new("ul");
+
+    for item in 0..items {
+        let mut li = VElement::new("li");
+        li.attrs.insert("class".into(), "item".into());
+        li.children
+            .push(VirtualNode::text(format!("item {}", item)));
+
+        if item < items - moved {
+            first.children.push(li.into());
+        } else {
+            second.children.push(li.into());
+        }
+    }
+
+    let mut lists = VElement::new("div");
+    lists.children.push(first.into());
+    lists.children.push(second.into());
+    lists.into()
+}
+
+/// A table where every row has a click handler.
+fn clickable_table(rows: usize) -> VirtualNode {
+    let mut clickable = table(rows, "text");
+
+    for row in clickable.as_velement_mut().unwrap().children.iter_mut() {
+        row.as_velement_mut().unwrap().events.insert(
+            "onclick".into(),
+            EventHandler::NoArgs(Rc::new(RefCell::new(|| {}))),
+        );
+    }
+
+    clickable
+}
+
+fn wrapped(tag: &str, child: VirtualNode) -> VirtualNode {
+    let mut wrapper = VElement::new(tag);
+    wrapper.children.push(child);
+    wrapper.into()
+}
+
+/// The number of DOM nodes that the patches create.
+fn created_nodes(patches: &[Patch]) -> usize {
+    patches
+        .iter()
+        .map(|patch| match patch {
+            Patch::Replace { new_node, .. } => node_count(new_node),
+            Patch::AppendChildren { new_nodes, .. } | Patch::InsertBefore { new_nodes, .. } => {
+                new_nodes.iter().map(|(_, node)| node_count(node)).sum()
+            }
+            _ => 0,
+        })
+        .sum()
+}
+
+fn node_count(node: &VirtualNode) -> usize {
+    match node {
+        VirtualNode::Element(element) => 1 + element.children.iter().map(node_count).sum::<usize>(),
+        _ => 1,
+    }
+}
+
+/// Change the text of the last text node in the tree.
+fn change_last_text(node: &mut VirtualNode) {
+    match node {
+        VirtualNode::Element(element) => {
+            if let Some(last) = element.children.last_mut() {
+                change_last_text(last);
+            }
+        }
+        VirtualNode::Text(text) => text.text += " changed",
+        _ => {}
+    }
+}
+
+criterion_group!(benches, diff_trees, diff_restructured_trees);
+criterion_main!(benches);
@@ -1,34 +1,133 @@
 use crate::event::{EventHandler, EventName};
-use crate::{AttributeValue, Patch, PatchSpecialAttribute};
+use crate::{AttributeTarget, AttributeValue, Patch, PatchSpecialAttribute};
 use crate::{VElement, VirtualNode};
 use std::cmp::min;
 use std::collections::HashMap;
 use std::mem;
 
+mod keyed_children;
+mod minimize;
+mod rerendered_components;
+
+pub(crate) use self::rerendered_components::{
+    diff_rerendered_components, rerender_changed_components,
+};
+
 /// Given two VirtualNode's generate Patch's that would turn the old virtual node's
 /// real DOM node equivalent into the new VirtualNode's real DOM node equivalent.
+///
+/// Children are diffed by their position, unless every old and new child has a unique `key`
+/// attribute in which case children get matched up by their key. This lets us move, insert and
+/// remove individual children without needing to patch all of their siblings.
+///
+/// Fragments are replaced by their children before children get compared, so
+/// `<div><>{a}{b}</>{c}</div>` gets diffed exactly like `<div>{a}{b}{c}</div>`.
+///
+/// Components are diffed using the nodes that they render. A new component that takes the place
+/// of an old component of the same type keeps the old component's state, and if its props are
+/// equal to the old props it reuses the old rendered node without rendering or diffing it. A
+/// [`Memo`] is a component whose props are its key, so a memo whose key did not change is skipped.
+///
+/// When the last children of one element get removed and equal children get appended to another
+/// element, such as when an item moves from the end of one list to the end of another, the old DOM
+/// nodes get moved instead of being removed and created again.
+///
+/// [`Memo`]: crate::Memo
 pub fn diff<'a>(old: &'a VirtualNode, new: &'a VirtualNode) -> Vec<Patch<'a>> {
-    diff_recursive(&old, &new, &mut 0, &mut 0)
+    let mut patches = vec![];
+    diff_recursive(old, new, &mut 0, &mut 0, &mut patches);
+    minimize::move_truncated_children(old, &mut patches);
+    patches
 }
 
+/// Push the patches that turn the old node into the new node.
+///
+/// Every node pushes onto the same `patches` buffer, and nothing gets allocated for nodes that did
+/// not change.
 fn diff_recursive<'a, 'b>(
     old: &'a VirtualNode,
     new: &'a VirtualNode,
     old_node_idx: &'b mut u32,
     new_node_idx: &'b mut u32,
-) -> Vec<Patch<'a>> {
-    let mut patches = vec![];
+    patches: &mut Vec<Patch<'a>>,
+) {
+    match (old, new) {
+        (VirtualNode::Component(old_component), VirtualNode::Component(new_component)) => {
+            if new_component.take_over(old_component) {
+                diff_unchanged(
+                    old_component.rendered(),
+                    old_node_idx,
+                    new_node_idx,
+                    patches,
+                );
+                return;
+            }
+
+            return diff_recursive(
+                old_component.rendered(),
+                new_component.rendered(),
+                old_node_idx,
+                new_node_idx,
+                patches,
+            );
+        }
+        (VirtualNode::Component(old_component), _) => {
+            return diff_recursive(
+                old_component.rendered(),
+                new,
+                old_node_idx,
+                new_node_idx,
+                patches,
+            );
+        }
+        (_, VirtualNode::Component(new_component)) => {
+            return diff_recursive(
+                old,
+                new_component.rendered(),
+                old_node_idx,
+                new_node_idx,
+                patches,
+            );
+        }
+        _ => {}
+    };
+
+    // This node's patches start here, after the patches of the nodes that came before it.
+    let first_patch = patches.len();
 
     let node_variants_different = mem::discriminant(old) != mem::discriminant(new);
     let mut element_tags_different = false;
+    let mut comments_different = false;
+    let mut portal_mounts_different = false;
 
     if let (VirtualNode::Element(old_element), VirtualNode::Element(new_element)) = (old, new) {
         element_tags_different = old_element.tag != new_element.tag;
     }
 
-    let should_fully_replace_node = node_variants_different || element_tags_different;
+    if let (VirtualNode::Portal(old_portal), VirtualNode::Portal(new_portal)) = (old, new) {
+        portal_mounts_different = old_portal.mount_selector != new_portal.mount_selector;
+    }
+
+    // Comments are rarely anything other than placeholders, so we replace them instead of having a
+    // patch for changing their text.
+    if let (VirtualNode::Comment(old_comment), VirtualNode::Comment(new_comment)) = (old, new) {
+        comments_different = old_comment != new_comment;
+    }
+
+    // Fragments only get diffed directly when they are the root node. There's no single DOM node
+    // to patch in that case, so we replace the whole thing.
+    let is_fragment =
+        matches!(old, VirtualNode::Fragment(_)) || matches!(new, VirtualNode::Fragment(_));
+
+    let should_fully_replace_node = node_variants_different
+        || element_tags_different
+        || comments_different
+        || portal_mounts_different
+        || is_fragment;
 
     if should_fully_replace_node {
+        let removal_patches = patches.len();
+
         if let Some(velem) = old.as_velement_ref() {
             if velem.events.has_events() {
                 patches.push(Patch::RemoveAllManagedEventsWithNodeIdx(*old_node_idx));
@@ -37,11 +136,10 @@ fn diff_recursive<'a, 'b>(
 
         let replaced_old_idx = *old_node_idx;
 
-        if let VirtualNode::Element(old_element_node) = old {
-            for child in old_element_node.children.iter() {
-                process_deleted_old_node_child(child, old_node_idx, &mut patches);
-            }
+        for child in child_nodes(old) {
+            process_deleted_old_node_child(child, old_node_idx, false, patches);
         }
+        collapse_removed_events(removal_patches, patches);
 
         patches.push(Patch::Replace {
             old_idx: replaced_old_idx,
@@ -50,20 +148,30 @@ fn diff_recursive<'a, 'b>(
         });
 
         if let Some(velem) = old.as_velement_ref() {
-            if velem.special_attributes.on_remove_element_key().is_some() {
+            if velem.special_attributes.node_ref().is_some() {
                 patches.push(Patch::SpecialAttribute(
-                    PatchSpecialAttribute::CallOnRemoveElem(*old_node_idx, old),
+                    PatchSpecialAttribute::ClearNodeRef(replaced_old_idx, old),
                 ));
             }
-        }
 
-        if let VirtualNode::Element(new_element_node) = new {
-            for child in new_element_node.children.iter() {
-                increment_idx_for_child(child, new_node_idx);
+            if velem.special_attributes.on_remove_element_key().is_some() {
+                // The root node gets replaced right away, since the `PercyDom` needs to find the
+                // new root node.
+                let removed_directly = replaced_old_idx != 0;
+
+                patches.push(on_remove_elem_patch(
+                    replaced_old_idx,
+                    old,
+                    removed_directly,
+                ));
             }
         }
 
-        return patches;
+        for child in child_nodes(new) {
+            increment_idx_for_child(child, new_node_idx);
+        }
+
+        return;
     }
 
     match (old, new) {
@@ -74,46 +182,40 @@ fn diff_recursive<'a, 'b>(
         }
 
         (VirtualNode::Element(old_element), VirtualNode::Element(new_element)) => {
-            let mut attributes_to_add: HashMap<&str, &AttributeValue> = HashMap::new();
+            let element_old_idx = *old_node_idx;
+
+            // Only created once we find an attribute or event that changed.
+            let mut attributes_to_add: Option<HashMap<&str, &AttributeValue>> = None;
             let mut attributes_to_remove: Vec<&str> = vec![];
 
-            let mut events_to_add = HashMap::new();
+            let mut events_to_add = None;
             let mut events_to_remove = vec![];
 
-            find_attributes_to_add(
-                *old_node_idx,
-                &mut attributes_to_add,
-                old_element,
-                new_element,
-                &mut patches,
-            );
+            find_attributes_to_add(&mut attributes_to_add, old_element, new_element);
 
             find_attributes_to_remove(
-                &mut attributes_to_add,
+                &attributes_to_add,
                 &mut attributes_to_remove,
                 old_element,
                 new_element,
             );
 
             find_events_to_add(&mut events_to_add, old_element, new_element);
-            find_events_to_remove(
-                &mut events_to_add,
-                &mut events_to_remove,
-                old_element,
-                new_element,
-            );
+            find_events_to_remove(&mut events_to_remove, old_element, new_element);
 
-            if attributes_to_add.len() > 0 {
+            if let Some(attributes_to_add) = attributes_to_add {
                 patches.push(Patch::AddAttributes(*old_node_idx, attributes_to_add));
             }
             if attributes_to_remove.len() > 0 {
                 patches.push(Patch::RemoveAttributes(*old_node_idx, attributes_to_remove));
             }
 
+            push_style_patches(*old_node_idx, old_element, new_element, patches);
+
             if events_to_remove.len() > 0 {
                 patches.push(Patch::RemoveEvents(*old_node_idx, events_to_remove));
             }
-            if events_to_add.len() > 0 {
+            if let Some(events_to_add) = events_to_add {
                 patches.push(Patch::AddEvents(*old_node_idx, events_to_add));
             }
 
@@ -181,6 +283,8 @@ fn diff_recursive<'a, 'b>(
                 _ => {}
             }
 
+            push_node_ref_patches(*old_node_idx, old, new, patches);
+
             let old_elem_has_events = old_element.events.has_events();
             let new_elem_has_events = new_element.events.has_events();
 
@@ -203,93 +307,176 @@ fn diff_recursive<'a, 'b>(
             generate_patches_for_children(
                 old_node_idx,
                 new_node_idx,
-                old_element,
-                new_element,
-                &mut patches,
+                FlatChildren::of(old),
+                FlatChildren::of(new),
+                patches,
+            );
+
+            // Pushed after the children's patches since a `<select>`'s value depends on its
+            // options.
+            push_dom_property_patches(element_old_idx, old_element, new_element, patches);
+
+            // Pushed last so that it gets called once the element and its children are patched.
+            if new_element
+                .special_attributes
+                .on_update_element_key()
+                .is_some()
+                && patches[first_patch..].iter().any(patch_changes_dom)
+            {
+                patches.push(Patch::SpecialAttribute(
+                    PatchSpecialAttribute::CallOnUpdateElem(element_old_idx, old, new),
+                ));
+            }
+        }
+        (VirtualNode::Comment(_), VirtualNode::Comment(_)) => {}
+        (VirtualNode::Portal(_), VirtualNode::Portal(_)) => {
+            generate_patches_for_children(
+                old_node_idx,
+                new_node_idx,
+                FlatChildren::of(old),
+                FlatChildren::of(new),
+                patches,
             );
         }
-        (VirtualNode::Text(_), VirtualNode::Element(_))
-        | (VirtualNode::Element(_), VirtualNode::Text(_)) => {
+        (VirtualNode::Fragment(_), _) | (_, VirtualNode::Fragment(_)) => {
+            unreachable!("Fragments should already have been replaced");
+        }
+        _ => {
             unreachable!("Unequal variant discriminants should already have been handled");
         }
     };
-
-    patches
 }
 
 /// Add attributes from the new element that are not already on the old one or that have changed.
 fn find_attributes_to_add<'a>(
-    cur_node_idx: u32,
-    attributes_to_add: &mut HashMap<&'a str, &'a AttributeValue>,
+    attributes_to_add: &mut Option<HashMap<&'a str, &'a AttributeValue>>,
     old_element: &VElement,
     new_element: &'a VElement,
-    patches: &mut Vec<Patch<'a>>,
 ) {
     for (new_attr_name, new_attr_val) in new_element.attrs.iter() {
-        match old_element.attrs.get(new_attr_name) {
-            Some(ref old_attr_val) => {
-                if old_attr_val != &new_attr_val {
-                    attributes_to_add.insert(new_attr_name, new_attr_val);
-                } else if new_attr_name == "value" {
-                    patches.push(Patch::ValueAttributeUnchanged(cur_node_idx, new_attr_val));
-                }
-            }
-            None => {
-                attributes_to_add.insert(new_attr_name, new_attr_val);
-            }
-        };
+        if old_element.attrs.get(new_attr_name) == Some(new_attr_val) {
+            continue;
+        }
+
+        if new_element.attribute_target(new_attr_name) != AttributeTarget::Property {
+            attributes_to_add
+                .get_or_insert_with(HashMap::new)
+                .insert(new_attr_name, new_attr_val);
+        }
     }
 }
 
 /// Remove attributes that were on the old element that are not present on the new element.
 fn find_attributes_to_remove<'a>(
-    attributes_to_add: &mut HashMap<&str, &AttributeValue>,
+    attributes_to_add: &Option<HashMap<&str, &AttributeValue>>,
     attributes_to_remove: &mut Vec<&'a str>,
     old_element: &'a VElement,
     new_element: &VElement,
 ) {
     for (old_attr_name, old_attr_val) in old_element.attrs.iter() {
-        if attributes_to_add.get(&old_attr_name[..]).is_some() {
+        if new_element.attrs.get(old_attr_name) == Some(old_attr_val) {
             continue;
-        };
+        }
 
-        match new_element.attrs.get(old_attr_name) {
-            Some(ref new_attr_val) => {
-                if new_attr_val != &old_attr_val {
-                    attributes_to_remove.push(old_attr_name);
-                }
-            }
-            None => {
-                attributes_to_remove.push(old_attr_name);
-            }
+        let is_being_added = attributes_to_add
+            .as_ref()
+            .is_some_and(|to_add| to_add.contains_key(old_attr_name.as_str()));
+        if is_being_added {
+            continue;
         };
+
+        if old_element.attribute_target(old_attr_name) == AttributeTarget::Property {
+            continue;
+        }
+
+        attributes_to_remove.push(old_attr_name);
+    }
+}
+
+/// Set the inline style properties that changed and remove the ones that the new element no longer
+/// has, so that we don't cancel CSS transitions on the properties that did not change.
+///
+/// Properties are removed before any are set, so that removing a shorthand property such as
+/// `margin` doesn't undo setting a longhand property such as `margin-top`.
+fn push_style_patches<'a>(
+    node_idx: u32,
+    old_element: &'a VElement,
+    new_element: &'a VElement,
+    patches: &mut Vec<Patch<'a>>,
+) {
+    let styles_to_remove: Vec<&str> = old_element
+        .styles
+        .iter()
+        .filter(|(name, _)| new_element.styles.get(name).is_none())
+        .map(|(name, _)| name)
+        .collect();
+    if styles_to_remove.len() > 0 {
+        patches.push(Patch::RemoveStyleProperties(node_idx, styles_to_remove));
+    }
+
+    let styles_to_set: Vec<(&str, &str)> = new_element
+        .styles
+        .iter()
+        .filter(|(name, value)| old_element.styles.get(name) != Some(value))
+        .collect();
+    if styles_to_set.len() > 0 {
+        patches.push(Patch::SetStyleProperties(node_idx, styles_to_set));
+    }
+}
+
+/// Set all of the attributes that get set as DOM properties, even the ones that did not change,
+/// since the user might have changed them. Reset the ones that the new element no longer has.
+///
+/// See [`AttributeTarget`].
+fn push_dom_property_patches<'a>(
+    node_idx: u32,
+    old_element: &'a VElement,
+    new_element: &'a VElement,
+    patches: &mut Vec<Patch<'a>>,
+) {
+    let dom_properties_to_remove: Vec<&str> = old_element
+        .dom_property_attributes()
+        .filter(|(name, _)| !new_element.attrs.contains_key(*name))
+        .map(|(name, _)| name.as_str())
+        .collect();
+    if dom_properties_to_remove.len() > 0 {
+        patches.push(Patch::RemoveDomProperties(
+            node_idx,
+            dom_properties_to_remove,
+        ));
+    }
+
+    let mut dom_properties = new_element.dom_property_attributes().peekable();
+    if dom_properties.peek().is_some() {
+        let dom_properties = dom_properties
+            .map(|(name, value)| (name.as_str(), value))
+            .collect();
+        patches.push(Patch::SetDomProperties(node_idx, dom_properties));
     }
 }
 
 /// Add attributes from the new element that are not already on the old one or that have changed.
 fn find_events_to_add<'a>(
-    events_to_add: &mut HashMap<&'a EventName, &'a EventHandler>,
+    events_to_add: &mut Option<HashMap<&'a EventName, &'a EventHandler>>,
     old_element: &VElement,
     new_element: &'a VElement,
 ) {
     for (new_event_name, new_event) in new_element.events.iter() {
         if !old_element.events.contains_key(new_event_name) {
-            events_to_add.insert(new_event_name, new_event);
+            events_to_add
+                .get_or_insert_with(HashMap::new)
+                .insert(new_event_name, new_event);
         }
     }
 }
 
 /// Remove non delegated that were on the old element that are not present on the new element.
 fn find_events_to_remove<'a>(
-    events_to_add: &mut HashMap<&'a EventName, &'a EventHandler>,
     events_to_remove: &mut Vec<(&'a EventName, &'a EventHandler)>,
     old_element: &'a VElement,
     new_element: &'a VElement,
 ) {
     for (old_event_name, old_event) in old_element.events.iter() {
-        if events_to_add.contains_key(old_event_name) {
-            continue;
-        };
         if new_element.events.contains_key(old_event_name) {
             continue;
         }
@@ -301,12 +488,22 @@ fn find_events_to_remove<'a>(
 fn generate_patches_for_children<'a, 'b>(
     old_node_idx: &'b mut u32,
     new_node_idx: &'b mut u32,
-    old_element: &'a VElement,
-    new_element: &'a VElement,
+    old_children: FlatChildren<'a>,
+    new_children: FlatChildren<'a>,
     patches: &mut Vec<Patch<'a>>,
 ) {
-    let old_child_count = old_element.children.len();
-    let new_child_count = new_element.children.len();
+    if keyed_children::diff_keyed_children(
+        old_node_idx,
+        new_node_idx,
+        &old_children,
+        &new_children,
+        patches,
+    ) {
+        return;
+    }
+
+    let old_child_count = old_children.len();
+    let new_child_count = new_children.len();
 
     let current_old_node_idx = *old_node_idx;
 
@@ -322,32 +519,28 @@ fn generate_patches_for_children<'a, 'b>(
         *old_node_idx += 1;
         *new_node_idx += 1;
 
-        let old_child = &old_element.children[index];
-        let new_child = &new_element.children[index];
-        patches.append(&mut diff_recursive(
-            &old_child,
-            &new_child,
-            old_node_idx,
-            new_node_idx,
-        ))
+        let old_child = old_children.get(index);
+        let new_child = new_children.get(index);
+        diff_recursive(old_child, new_child, old_node_idx, new_node_idx, patches);
     }
 
     if new_child_count < old_child_count {
-        for child in old_element.children[min_count..].iter() {
-            process_deleted_old_node_child(child, old_node_idx, patches);
+        // The truncated children are the last children, so their indices are consecutive.
+        let removal_patches = patches.len();
+        for child in old_children.iter().skip(min_count) {
+            process_deleted_old_node_child(child, old_node_idx, true, patches);
         }
+        collapse_removed_events(removal_patches, patches);
     } else if new_child_count > old_child_count {
         let mut append_patch = vec![];
 
-        for new_node in new_element.children[old_child_count..].iter() {
+        for new_node in new_children.iter().skip(old_child_count) {
             *new_node_idx += 1;
 
             append_patch.push((*new_node_idx, new_node));
 
-            if let Some(elem) = new_node.as_velement_ref() {
-                for child in elem.children.iter() {
-                    increment_idx_for_child(child, new_node_idx);
-                }
+            for child in child_nodes(new_node.rendered_node()) {
+                increment_idx_for_child(child, new_node_idx);
             }
         }
 
@@ -358,47 +551,309 @@ fn generate_patches_for_children<'a, 'b>(
     }
 }
 
+/// The children of an element or a portal, with any fragments replaced by their own children.
+///
+/// The children only get copied into a `Vec` when there is a fragment to flatten, so most elements
+/// get diffed without allocating.
+enum FlatChildren<'a> {
+    Unchanged(&'a [VirtualNode]),
+    Flattened(Vec<&'a VirtualNode>),
+}
+
+impl<'a> FlatChildren<'a> {
+    fn of(node: &'a VirtualNode) -> Self {
+        let children = child_nodes(node);
+
+        let has_fragment = children
+            .iter()
+            .any(|child| matches!(child, VirtualNode::Fragment(_)));
+        if !has_fragment {
+            return FlatChildren::Unchanged(children);
+        }
+
+        match node {
+            VirtualNode::Element(element) => FlatChildren::Flattened(element.flat_children()),
+            VirtualNode::Portal(portal) => FlatChildren::Flattened(portal.flat_children()),
+            _ => FlatChildren::Unchanged(&[]),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            FlatChildren::Unchanged(children) => children.len(),
+            FlatChildren::Flattened(children) => children.len(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn get(&self, index: usize) -> &'a VirtualNode {
+        match self {
+            FlatChildren::Unchanged(children) => &children[index],
+            FlatChildren::Flattened(children) => children[index],
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &'a VirtualNode> + '_ {
+        (0..self.len()).map(move |index| self.get(index))
+    }
+}
+
 /// Increment the `cur_node_idx` to account for this deleted node.
 ///
 /// Then iterate through all of its children, recursively, and increment the `cur_node_idx`.
 ///
 /// Along the way we also push patches to remove all tracked events for deleted nodes
 /// (if they had events).
+///
+/// Fragments don't have a node index of their own, so only their children get counted.
+///
+/// A deleted portal's children live outside of its parent's DOM element, so we also push a patch
+/// to remove them from the portal's mount.
+///
+/// `removed_directly` is `true` when the node gets removed from its parent, as opposed to along
+/// with one of its ancestors.
 fn process_deleted_old_node_child<'a>(
     old_node: &'a VirtualNode,
     cur_node_idx: &mut u32,
+    removed_directly: bool,
     patches: &mut Vec<Patch<'a>>,
 ) {
+    if let VirtualNode::Fragment(fragment) = old_node {
+        for child in fragment.children.iter() {
+            process_deleted_old_node_child(child, cur_node_idx, removed_directly, patches);
+        }
+        return;
+    }
+
+    let old_node = old_node.rendered_node();
+
     *cur_node_idx += 1;
     if let VirtualNode::Element(element_node) = old_node {
         if element_node.events.len() > 0 {
             patches.push(Patch::RemoveAllManagedEventsWithNodeIdx(*cur_node_idx));
         }
 
+        if element_node.special_attributes.node_ref().is_some() {
+            patches.push(Patch::SpecialAttribute(
+                PatchSpecialAttribute::ClearNodeRef(*cur_node_idx, old_node),
+            ));
+        }
+
         if element_node
             .special_attributes
             .on_remove_element_key()
             .is_some()
         {
-            patches.push(Patch::SpecialAttribute(
-                PatchSpecialAttribute::CallOnRemoveElem(*cur_node_idx, old_node),
+            patches.push(on_remove_elem_patch(
+                *cur_node_idx,
+                old_node,
+                removed_directly,
             ));
         }
 
         for child in element_node.children.iter() {
-            process_deleted_old_node_child(&child, cur_node_idx, patches);
+            process_deleted_old_node_child(&child, cur_node_idx, false, patches);
+        }
+    } else if let VirtualNode::Portal(portal) = old_node {
+        patches.push(Patch::RemovePortal(*cur_node_idx));
+
+        for child in portal.children.iter() {
+            process_deleted_old_node_child(&child, cur_node_idx, false, patches);
+        }
+    }
+}
+
+/// Replace the [`Patch::RemoveAllManagedEventsWithNodeIdx`] patches that were pushed since
+/// `first_patch` with a single [`Patch::RemoveAllManagedEventsInRange`], when there is more than
+/// one of them.
+///
+/// Only used after pushing the patches for removed nodes whose indices are consecutive, such as a
+/// removed node and its descendants, since every index in the range gets its events removed.
+fn collapse_removed_events(first_patch: usize, patches: &mut Vec<Patch>) {
+    let is_removal = |patch: &Patch| matches!(patch, Patch::RemoveAllManagedEventsWithNodeIdx(_));
+
+    let first = match patches[first_patch..].iter().position(is_removal) {
+        Some(position) => first_patch + position,
+        None => return,
+    };
+    let last = match patches[first + 1..].iter().rposition(is_removal) {
+        Some(position) => first + 1 + position,
+        None => return,
+    };
+
+    if let (
+        Patch::RemoveAllManagedEventsWithNodeIdx(first_idx),
+        Patch::RemoveAllManagedEventsWithNodeIdx(last_idx),
+    ) = (&patches[first], &patches[last])
+    {
+        patches[first] = Patch::RemoveAllManagedEventsInRange {
+            first_idx: *first_idx,
+            last_idx: *last_idx,
+        };
+    }
+
+    // Remove the other removal patches while keeping the rest of the patches in order.
+    let mut kept = first + 1;
+    for position in first + 1..patches.len() {
+        if !is_removal(&patches[position]) {
+            patches.swap(kept, position);
+            kept += 1;
         }
     }
+    patches.truncate(kept);
+}
+
+/// When an element is patched by a node with a different [`crate::NodeRef`], move the element from
+/// the old node's `NodeRef` to the new node's.
+fn push_node_ref_patches<'a>(
+    old_idx: u32,
+    old: &'a VirtualNode,
+    new: &'a VirtualNode,
+    patches: &mut Vec<Patch<'a>>,
+) {
+    let old_node_ref = old.as_velement_ref().unwrap().special_attributes.node_ref();
+    let new_node_ref = new.as_velement_ref().unwrap().special_attributes.node_ref();
+
+    if old_node_ref == new_node_ref {
+        return;
+    }
+
+    if old_node_ref.is_some() {
+        patches.push(Patch::SpecialAttribute(
+            PatchSpecialAttribute::ClearNodeRef(old_idx, old),
+        ));
+    }
+    if new_node_ref.is_some() {
+        patches.push(Patch::SpecialAttribute(PatchSpecialAttribute::SetNodeRef(
+            old_idx, new,
+        )));
+    }
+}
+
+/// The patch that calls the on remove element function of an element that is being removed.
+///
+/// Only an element that gets removed from its parent can defer its removal, since an element that
+/// gets removed along with one of its ancestors is already gone.
+fn on_remove_elem_patch(
+    node_idx: u32,
+    old_node: &VirtualNode,
+    removed_directly: bool,
+) -> Patch<'_> {
+    let defers_removal = old_node
+        .as_velement_ref()
+        .map(|element| element.special_attributes.defers_element_removal())
+        .unwrap_or(false);
+
+    if removed_directly && defers_removal {
+        Patch::SpecialAttribute(PatchSpecialAttribute::DeferRemoveElem(node_idx, old_node))
+    } else {
+        Patch::SpecialAttribute(PatchSpecialAttribute::CallOnRemoveElem(node_idx, old_node))
+    }
+}
+
+/// Whether the patch changes the DOM, as opposed to only changing which events and special
+/// attribute functions belong to which element.
+fn patch_changes_dom(patch: &Patch) -> bool {
+    match patch {
+        Patch::SpecialAttribute(special) => matches!(
+            special,
+            PatchSpecialAttribute::SetDangerousInnerHtml(..)
+                | PatchSpecialAttribute::RemoveDangerousInnerHtml(..)
+        ),
+        Patch::RemoveEventsId(..)
+        | Patch::SetEventsId { .. }
+        | Patch::AddEvents(..)
+        | Patch::RemoveEvents(..)
+        | Patch::RemoveAllManagedEventsWithNodeIdx(..)
+        | Patch::RemoveAllManagedEventsInRange { .. } => false,
+        _ => true,
+    }
 }
 
 /// Recursively increment the node idx for each child, depth first.
+///
+/// Fragments don't have a node index of their own, so only their children get counted.
 fn increment_idx_for_child(new_node: &VirtualNode, new_node_idx: &mut u32) {
-    *new_node_idx += 1;
+    match new_node {
+        VirtualNode::Element(element_node) => {
+            *new_node_idx += 1;
 
-    if let VirtualNode::Element(element_node) = new_node {
-        for child in element_node.children.iter() {
+            for child in element_node.children.iter() {
+                increment_idx_for_child(child, new_node_idx);
+            }
+        }
+        VirtualNode::Portal(portal) => {
+            *new_node_idx += 1;
+
+            for child in portal.children.iter() {
+                increment_idx_for_child(child, new_node_idx);
+            }
+        }
+        VirtualNode::Text(_) | VirtualNode::Comment(_) => {
+            *new_node_idx += 1;
+        }
+        VirtualNode::Fragment(fragment) => {
+            for child in fragment.children.iter() {
+                increment_idx_for_child(child, new_node_idx);
+            }
+        }
+        VirtualNode::Component(component) => {
+            increment_idx_for_child(component.rendered(), new_node_idx);
+        }
+    }
+}
+
+/// Generate patches for a node that is the same in the old and new virtual dom, such as the node
+/// rendered by a component that did not need to re-render.
+///
+/// The node's DOM is left as is. If the node's index changed, because the nodes before it changed,
+/// we move its events and its descendants' events to their new indices.
+fn diff_unchanged<'a>(
+    node: &'a VirtualNode,
+    old_node_idx: &mut u32,
+    new_node_idx: &mut u32,
+    patches: &mut Vec<Patch<'a>>,
+) {
+    let node = node.rendered_node();
+    let has_events = match node {
+        VirtualNode::Element(element) => element.events.has_events(),
+        VirtualNode::Portal(_) => false,
+        _ => return,
+    };
+    let children = FlatChildren::of(node);
+
+    if old_node_idx == new_node_idx {
+        for child in children.iter() {
             increment_idx_for_child(child, new_node_idx);
         }
+        *old_node_idx = *new_node_idx;
+        return;
+    }
+
+    if has_events {
+        patches.push(Patch::SetEventsId {
+            old_idx: *old_node_idx,
+            new_idx: *new_node_idx,
+        });
+    }
+
+    for child in children.iter() {
+        *old_node_idx += 1;
+        *new_node_idx += 1;
+
+        diff_unchanged(child, old_node_idx, new_node_idx, patches);
+    }
+}
+
+/// The children of an element or a portal, which get their node indices right after it.
+fn child_nodes(node: &VirtualNode) -> &[VirtualNode] {
+    match node {
+        VirtualNode::Element(element) => &element.children,
+        VirtualNode::Portal(portal) => &portal.children,
+        _ => &[],
     }
 }
 
@@ -409,10 +864,11 @@ mod diff_test_case;
 mod tests {
     use super::*;
     use crate::event::EventName;
-    use crate::{html, EventAttribFn, PatchSpecialAttribute, VText, VirtualNode};
+    use crate::{html, EventAttribFn, NodeRef, PatchSpecialAttribute, VPortal, VText, VirtualNode};
+    use std::cell::Cell;
     use std::collections::HashMap;
     use std::rc::Rc;
-    use virtual_node::IterableNodes;
+    use virtual_node::{Component, ComponentState, IterableNodes};
     use wasm_bindgen::JsValue;
 
     use super::diff_test_case::*;
@@ -659,69 +1115,156 @@ mod tests {
     }
 
     /// If an input or textarea has a value attribute we always push a patch for setting the value
-    /// attribute so that we can replace anything that might have been typed into the field.
+    /// property so that we can replace anything that might have been typed into the field.
     #[test]
     fn always_pushes_patch_for_value() {
         DiffTestCase {
             old: html! { <input value="abc" /> },
             new: html! { <input value="abc" /> },
-            expected: vec![Patch::ValueAttributeUnchanged(0, &"abc".into())],
+            expected: vec![Patch::SetDomProperties(
+                0,
+                vec![("value", &"abc".into())].into_iter().collect(),
+            )],
         }
         .test();
 
         DiffTestCase {
             old: html! { <textarea value="abc" /> },
             new: html! { <textarea value="abc" /> },
-            expected: vec![Patch::ValueAttributeUnchanged(0, &"abc".into())],
+            expected: vec![Patch::SetDomProperties(
+                0,
+                vec![("value", &"abc".into())].into_iter().collect(),
+            )],
         }
         .test();
 
         DiffTestCase {
             old: html! { <textarea value="abc" /> },
             new: html! { <textarea value="def" /> },
-            expected: vec![Patch::AddAttributes(
-                0,
-                vec![("value", &"def".into())].into_iter().collect(),
-            )],
+            expected: vec![
+                Patch::AddAttributes(0, vec![("value", &"def".into())].into_iter().collect()),
+                Patch::SetDomProperties(0, vec![("value", &"def".into())].into_iter().collect()),
+            ],
         }
         .test();
     }
 
-    /// Verify that we push an on create elem patch if the new node has the special attribute
-    /// and the old node does not.
+    /// Verify that we only patch the inline style properties that changed.
     #[test]
-    fn on_create_elem() {
-        let old = VirtualNode::element("div");
-
-        let mut new = VirtualNode::element("div");
-        set_on_create_elem_with_unique_id(&mut new, "150");
-
-        let mut expected = VirtualNode::element("div");
-        set_on_create_elem_with_unique_id(&mut expected, "150");
-
+    fn style_properties() {
         DiffTestCase {
-            old,
-            new,
-            expected: vec![Patch::SpecialAttribute(
-                PatchSpecialAttribute::CallOnCreateElem(0, &expected),
-            )],
+            old: html! { <div style="color: red; width: 5px; opacity: 0"></div> },
+            new: html! { <div style="color: blue; width: 5px; height: 2px"></div> },
+            expected: vec![
+                Patch::RemoveStyleProperties(0, vec!["opacity"]),
+                Patch::SetStyleProperties(0, vec![("color", "blue"), ("height", "2px")]),
+            ],
         }
         .test();
-    }
-
-    /// Verify that if two different nodes have the same on_create_elem unique identifiers we
-    /// do not push a CallOnCreateElem patch.
-    #[test]
-    fn same_on_create_elem_id() {
-        let mut old = VirtualNode::element("div");
-        set_on_create_elem_with_unique_id(&mut old, "70");
-
-        let mut new = VirtualNode::element("div");
-        set_on_create_elem_with_unique_id(&mut new, "70");
 
         DiffTestCase {
-            old,
-            new,
+            old: html! { <div style="color: red"></div> },
+            new: html! { <div style=" color:red; "></div> },
+            expected: vec![],
+        }
+        .test();
+    }
+
+    /// Verify that we set attributes that have no HTML attribute only as DOM properties.
+    #[test]
+    fn dom_property_without_attribute() {
+        DiffTestCase {
+            old: html! { <input /> },
+            new: html! { <input indeterminate=true /> },
+            expected: vec![Patch::SetDomProperties(
+                0,
+                vec![("indeterminate", &true.into())].into_iter().collect(),
+            )],
+        }
+        .test();
+
+        let mut old = VElement::new("video");
+        old.set_dom_property("playbackRate", "2");
+
+        DiffTestCase {
+            old: old.into(),
+            new: html! { <video></video> },
+            expected: vec![Patch::RemoveDomProperties(0, vec!["playbackRate"])],
+        }
+        .test();
+    }
+
+    /// Verify that we reset the DOM property when an attribute that is also set as a DOM property
+    /// gets removed.
+    #[test]
+    fn remove_dom_property() {
+        DiffTestCase {
+            old: html! { <input checked=true /> },
+            new: html! { <input /> },
+            expected: vec![
+                Patch::RemoveAttributes(0, vec!["checked"]),
+                Patch::RemoveDomProperties(0, vec!["checked"]),
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that we set a select's value after patching its options, since the value can only be
+    /// set to one of the options.
+    #[test]
+    fn dom_properties_set_after_children() {
+        DiffTestCase {
+            old: html! { <select value="a"><option value="a"></option></select> },
+            new: html! {
+                <select value="b"><option value="a"></option><option value="b"></option></select>
+            },
+            expected: vec![
+                Patch::AddAttributes(0, vec![("value", &"b".into())].into_iter().collect()),
+                Patch::AppendChildren {
+                    old_idx: 0,
+                    new_nodes: vec![(2, &html! { <option value="b"></option> })],
+                },
+                Patch::SetDomProperties(0, vec![("value", &"b".into())].into_iter().collect()),
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that we push an on create elem patch if the new node has the special attribute
+    /// and the old node does not.
+    #[test]
+    fn on_create_elem() {
+        let old = VirtualNode::element("div");
+
+        let mut new = VirtualNode::element("div");
+        set_on_create_elem_with_unique_id(&mut new, "150");
+
+        let mut expected = VirtualNode::element("div");
+        set_on_create_elem_with_unique_id(&mut expected, "150");
+
+        DiffTestCase {
+            old,
+            new,
+            expected: vec![Patch::SpecialAttribute(
+                PatchSpecialAttribute::CallOnCreateElem(0, &expected),
+            )],
+        }
+        .test();
+    }
+
+    /// Verify that if two different nodes have the same on_create_elem unique identifiers we
+    /// do not push a CallOnCreateElem patch.
+    #[test]
+    fn same_on_create_elem_id() {
+        let mut old = VirtualNode::element("div");
+        set_on_create_elem_with_unique_id(&mut old, "70");
+
+        let mut new = VirtualNode::element("div");
+        set_on_create_elem_with_unique_id(&mut new, "70");
+
+        DiffTestCase {
+            old,
+            new,
             expected: vec![],
         }
         .test();
@@ -899,6 +1442,156 @@ mod tests {
         .test();
     }
 
+    /// Verify that a truncated child defers its removal, while its children get removed along with
+    /// it.
+    #[test]
+    fn deferred_remove_elem_for_truncated_child() {
+        let deferred_child = || {
+            let mut grandchild = VirtualNode::element("strong");
+            set_deferred_on_remove_elem(&mut grandchild, "key");
+
+            let mut child = VirtualNode::element("em");
+            set_deferred_on_remove_elem(&mut child, "key");
+            child.as_velement_mut().unwrap().children.push(grandchild);
+
+            child
+        };
+        let expected_child = deferred_child();
+        let expected_grandchild = {
+            let mut grandchild = VirtualNode::element("strong");
+            set_deferred_on_remove_elem(&mut grandchild, "key");
+            grandchild
+        };
+
+        DiffTestCase {
+            old: html! { <div> <span></span> {deferred_child()} </div> },
+            new: html! { <div> <span></span> </div> },
+            expected: vec![
+                Patch::TruncateChildren(0, 1),
+                Patch::SpecialAttribute(PatchSpecialAttribute::DeferRemoveElem(2, &expected_child)),
+                Patch::SpecialAttribute(PatchSpecialAttribute::CallOnRemoveElem(
+                    3,
+                    &expected_grandchild,
+                )),
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that a replaced element defers its removal, using its own node index even though it
+    /// has children.
+    #[test]
+    fn deferred_remove_elem_for_replaced_elem() {
+        let deferred_child = || {
+            let mut child = html! { <em><b></b></em> };
+            set_deferred_on_remove_elem(&mut child, "key");
+            child
+        };
+        let expected_child = deferred_child();
+
+        DiffTestCase {
+            old: html! { <div> {deferred_child()} </div> },
+            new: html! { <div> <span></span> </div> },
+            expected: vec![
+                Patch::Replace {
+                    old_idx: 1,
+                    new_idx: 1,
+                    new_node: &html! { <span></span> },
+                },
+                Patch::SpecialAttribute(PatchSpecialAttribute::DeferRemoveElem(1, &expected_child)),
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that the root node does not defer its removal, since the new root node needs to take
+    /// its place right away.
+    #[test]
+    fn replaced_root_does_not_defer_remove_elem() {
+        let mut old = VirtualNode::element("div");
+        set_deferred_on_remove_elem(&mut old, "key");
+
+        let expected = {
+            let mut old = VirtualNode::element("div");
+            set_deferred_on_remove_elem(&mut old, "key");
+            old
+        };
+
+        DiffTestCase {
+            old,
+            new: VirtualNode::element("span"),
+            expected: vec![
+                Patch::Replace {
+                    old_idx: 0,
+                    new_idx: 0,
+                    new_node: &VirtualNode::element("span"),
+                },
+                Patch::SpecialAttribute(PatchSpecialAttribute::CallOnRemoveElem(0, &expected)),
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that when an element is patched by a node with a different node ref, the old node ref
+    /// gets cleared and the new node ref gets set.
+    #[test]
+    fn node_ref_changed() {
+        let old_ref = NodeRef::new();
+        let new_ref = NodeRef::new();
+
+        let old = html! { <div ref=old_ref></div> };
+        let new = html! { <div ref=new_ref></div> };
+
+        DiffTestCase {
+            old: html! { <div ref=old_ref></div> },
+            new: html! { <div ref=new_ref></div> },
+            expected: vec![
+                Patch::SpecialAttribute(PatchSpecialAttribute::ClearNodeRef(0, &old)),
+                Patch::SpecialAttribute(PatchSpecialAttribute::SetNodeRef(0, &new)),
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that an element that keeps its node ref does not get any node ref patches.
+    #[test]
+    fn node_ref_unchanged() {
+        let node_ref = NodeRef::new();
+
+        DiffTestCase {
+            old: html! { <div ref=node_ref></div> },
+            new: html! { <div ref=node_ref></div> },
+            expected: vec![],
+        }
+        .test();
+    }
+
+    /// Verify that the node refs of removed elements and of replaced elements get cleared.
+    #[test]
+    fn removed_and_replaced_elements_clear_node_refs() {
+        let removed_ref = NodeRef::new();
+        let replaced_ref = NodeRef::new();
+
+        let removed = html! { <em ref=removed_ref></em> };
+        let replaced = html! { <b ref=replaced_ref></b> };
+
+        DiffTestCase {
+            old: html! { <div> <b ref=replaced_ref></b> <em ref=removed_ref></em> </div> },
+            new: html! { <div> <i></i> </div> },
+            expected: vec![
+                Patch::TruncateChildren(0, 1),
+                Patch::Replace {
+                    old_idx: 1,
+                    new_idx: 1,
+                    new_node: &html! { <i></i> },
+                },
+                Patch::SpecialAttribute(PatchSpecialAttribute::ClearNodeRef(1, &replaced)),
+                Patch::SpecialAttribute(PatchSpecialAttribute::ClearNodeRef(2, &removed)),
+            ],
+        }
+        .test();
+    }
+
     /// Verify that when patching attributes, if the old has an on remove element callback but the
     /// new node does not, we call the on remove element callback.
     ///
@@ -1005,6 +1698,37 @@ mod tests {
         .test();
     }
 
+    /// Verify that the on update element function gets called after the element and its children
+    /// were patched.
+    #[test]
+    fn on_update_elem_after_patches() {
+        let old = html! { <div key="a" id="old" on_update_element=||{}> <em>old</em> </div> };
+        let new = html! { <div key="a" id="new" on_update_element=||{}> <em>new</em> </div> };
+
+        DiffTestCase {
+            old: html! { <div key="a" id="old" on_update_element=||{}> <em>old</em> </div> },
+            new: html! { <div key="a" id="new" on_update_element=||{}> <em>new</em> </div> },
+            expected: vec![
+                Patch::AddAttributes(0, vec![("id", &"new".into())].into_iter().collect()),
+                Patch::ChangeText(2, &VText::new("new")),
+                Patch::SpecialAttribute(PatchSpecialAttribute::CallOnUpdateElem(0, &old, &new)),
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that the on update element function does not get called when patching does not
+    /// change the element or its children.
+    #[test]
+    fn on_update_elem_not_called_if_unchanged() {
+        DiffTestCase {
+            old: html! { <div key="a" onclick=||{} on_update_element=||{}> <em></em> </div> },
+            new: html! { <div key="a" onclick=||{} on_update_element=||{}> <em></em> </div> },
+            expected: vec![],
+        }
+        .test();
+    }
+
     /// Verify that if the old node and new node have the same dangerous_inner_html we do not push
     /// an SetDangerousInnerHtml patch.
     #[test]
@@ -1510,44 +2234,859 @@ mod tests {
         .test();
     }
 
-    fn set_on_create_elem_with_unique_id(node: &mut VirtualNode, on_create_elem_id: &'static str) {
-        node.as_velement_mut()
-            .unwrap()
-            .special_attributes
-            .set_on_create_element(on_create_elem_id, |_: web_sys::Element| {});
-    }
+    /// Verify that when more than one node of a replaced subtree has events, we remove all of their
+    /// events using a single patch.
+    #[test]
+    fn removes_tracked_events_of_replaced_subtree_in_one_patch() {
+        // node idx 0
+        let mut old = VElement::new("div");
+        old.events.insert(onclick_name(), mock_event_handler());
+        // node idx 1
+        old.children.push(VirtualNode::Element(VElement::new("a")));
 
-    fn set_on_remove_elem_with_unique_id(node: &mut VirtualNode, on_remove_elem_id: &'static str) {
-        node.as_velement_mut()
-            .unwrap()
-            .special_attributes
-            .set_on_remove_element(on_remove_elem_id, |_: web_sys::Element| {});
-    }
+        // node idx 2, and its text is node idx 3
+        let mut button = VElement::new("button");
+        button.events.insert(onclick_name(), mock_event_handler());
+        button.children.push(VirtualNode::text("b"));
+        old.children.push(VirtualNode::Element(button));
 
-    fn set_dangerous_inner_html(node: &mut VirtualNode, html: &str) {
-        node.as_velement_mut()
-            .unwrap()
-            .special_attributes
-            .dangerous_inner_html = Some(html.to_string());
+        // node idx 4
+        let mut input = VElement::new("input");
+        input.events.insert(oninput_name(), mock_event_handler());
+        old.children.push(VirtualNode::Element(input));
+
+        let new = VElement::new("some-other-element");
+
+        DiffTestCase {
+            old: VirtualNode::Element(old),
+            new: VirtualNode::Element(new),
+            expected: vec![
+                Patch::RemoveAllManagedEventsInRange {
+                    first_idx: 0,
+                    last_idx: 4,
+                },
+                Patch::Replace {
+                    old_idx: 0,
+                    new_idx: 0,
+                    new_node: &VirtualNode::Element(VElement::new("some-other-element")),
+                },
+            ],
+        }
+        .test();
     }
 
-    /// Return a node that has an on remove element function.
-    ///
-    /// This node has a child that also has an on remove element function.
-    ///
-    /// <div>
-    ///   <div></div>
-    /// </div>
-    fn on_remove_node_with_on_remove_child() -> VirtualNode {
-        let mut child = VirtualNode::element("div");
-        set_on_remove_elem_with_unique_id(&mut child, "555");
+    /// Verify that the events of every truncated child get removed using a single patch, while the
+    /// truncated children's other patches are kept in order.
+    #[test]
+    fn removes_tracked_events_of_truncated_children_in_one_patch() {
+        let node_ref = NodeRef::new();
 
-        let mut node = VirtualNode::element("div");
-        set_on_remove_elem_with_unique_id(&mut node, "666");
+        let mut old = VElement::new("div");
+        old.children.push(VirtualNode::text("kept"));
 
-        node.as_velement_mut().unwrap().children.push(child);
+        let mut first = VElement::new("button");
+        first.events.insert(onclick_name(), mock_event_handler());
+        old.children.push(VirtualNode::Element(first));
 
-        node
+        old.children
+            .push(html! { <span ref=node_ref.clone()></span> });
+
+        let mut third = VElement::new("input");
+        third.events.insert(oninput_name(), mock_event_handler());
+        old.children.push(VirtualNode::Element(third));
+
+        let mut new = VElement::new("div");
+        new.children.push(VirtualNode::text("kept"));
+
+        DiffTestCase {
+            old: VirtualNode::Element(old),
+            new: VirtualNode::Element(new),
+            expected: vec![
+                Patch::TruncateChildren(0, 1),
+                Patch::RemoveAllManagedEventsInRange {
+                    first_idx: 2,
+                    last_idx: 4,
+                },
+                Patch::SpecialAttribute(PatchSpecialAttribute::ClearNodeRef(
+                    3,
+                    &html! { <span ref=node_ref></span> },
+                )),
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that when the last child of one element becomes the last child of another element,
+    /// the old child gets moved instead of being removed and created again.
+    #[test]
+    fn truncated_child_moved_to_appended_spot() {
+        DiffTestCase {
+            old: html! {
+                <div>
+                    <ul> <li>A</li> <li>B</li> </ul>
+                    <ul> <li>C</li> </ul>
+                </div>
+            },
+            new: html! {
+                <div>
+                    <ul> <li>A</li> </ul>
+                    <ul> <li>C</li> <li>B</li> </ul>
+                </div>
+            },
+            expected: vec![
+                Patch::TruncateChildren(1, 1),
+                Patch::MoveToEndOfSiblings {
+                    parent_old_idx: 6,
+                    siblings_to_move: vec![4],
+                },
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that the appended nodes that don't match a truncated node still get created, in
+    /// order around the moved node.
+    #[test]
+    fn appended_children_split_around_moved_child() {
+        DiffTestCase {
+            old: html! {
+                <div>
+                    <ul> <li>A</li> <li>B</li> </ul>
+                    <ul></ul>
+                </div>
+            },
+            new: html! {
+                <div>
+                    <ul> <li>A</li> </ul>
+                    <ul> <li>D</li> <li>B</li> <li>E</li> </ul>
+                </div>
+            },
+            expected: vec![
+                Patch::TruncateChildren(1, 1),
+                Patch::AppendChildren {
+                    old_idx: 6,
+                    new_nodes: vec![(5, &html! { <li>D</li> })],
+                },
+                Patch::MoveToEndOfSiblings {
+                    parent_old_idx: 6,
+                    siblings_to_move: vec![4],
+                },
+                Patch::AppendChildren {
+                    old_idx: 6,
+                    new_nodes: vec![(9, &html! { <li>E</li> })],
+                },
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that a truncated child with events is not moved, since its events would need to
+    /// follow it to its new index.
+    #[test]
+    fn truncated_child_with_events_not_moved() {
+        DiffTestCase {
+            old: html! {
+                <div>
+                    <ul> <li>A</li> <li onclick=|| {}>B</li> </ul>
+                    <ul></ul>
+                </div>
+            },
+            new: html! {
+                <div>
+                    <ul> <li>A</li> </ul>
+                    <ul> <li onclick=|| {}>B</li> </ul>
+                </div>
+            },
+            expected: vec![
+                Patch::TruncateChildren(1, 1),
+                Patch::RemoveAllManagedEventsWithNodeIdx(4),
+                Patch::AppendChildren {
+                    old_idx: 6,
+                    new_nodes: vec![(5, &html! { <li onclick=|| {}>B</li> })],
+                },
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that a truncated child is not moved to a parent where it would be created in a
+    /// different namespace.
+    #[test]
+    fn truncated_child_not_moved_to_other_namespace() {
+        DiffTestCase {
+            old: html! {
+                <div>
+                    <svg> <a></a> </svg>
+                    <div></div>
+                </div>
+            },
+            new: html! {
+                <div>
+                    <svg></svg>
+                    <div> <a></a> </div>
+                </div>
+            },
+            expected: vec![
+                Patch::TruncateChildren(1, 0),
+                Patch::AppendChildren {
+                    old_idx: 3,
+                    new_nodes: vec![(3, &html! { <a></a> })],
+                },
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that when every child is keyed, inserting a child at the beginning only creates
+    /// the new child instead of patching every child that comes after it.
+    #[test]
+    fn keyed_insert_at_beginning() {
+        DiffTestCase {
+            old: html! { <ul> <li key="a">A</li> <li key="b">B</li> </ul> },
+            new: html! { <ul> <li key="z">Z</li> <li key="a">A</li> <li key="b">B</li> </ul> },
+            expected: vec![Patch::InsertBefore {
+                anchor_old_idx: 1,
+                new_nodes: vec![(1, &html! { <li key="z">Z</li> })],
+            }],
+        }
+        .test();
+    }
+
+    /// Verify that numbers can be used as keys.
+    #[test]
+    fn keyed_numeric_keys() {
+        DiffTestCase {
+            old: html! { <ul> <li key=1>A</li> <li key=2>B</li> </ul> },
+            new: html! { <ul> <li key=0>Z</li> <li key=1>A</li> <li key=2>B</li> </ul> },
+            expected: vec![Patch::InsertBefore {
+                anchor_old_idx: 1,
+                new_nodes: vec![(1, &html! { <li key=0>Z</li> })],
+            }],
+        }
+        .test();
+    }
+
+    /// Verify that we can remove keyed children from anywhere in the list of children.
+    #[test]
+    fn keyed_remove_from_middle() {
+        DiffTestCase {
+            old: html! { <ul> <li key="a">A</li> <li key="b">B</li> <li key="c">C</li> </ul> },
+            new: html! { <ul> <li key="a">A</li> <li key="c">C</li> </ul> },
+            expected: vec![Patch::RemoveChildren {
+                parent_old_idx: 0,
+                to_remove: vec![3],
+            }],
+        }
+        .test();
+    }
+
+    /// Verify that we move keyed children that are out of order, leaving the children that are
+    /// already in the right order in place.
+    #[test]
+    fn keyed_move_children() {
+        DiffTestCase {
+            old: html! { <ul> <li key="a"></li> <li key="b"></li> <li key="c"></li> </ul> },
+            new: html! { <ul> <li key="c"></li> <li key="b"></li> <li key="a"></li> </ul> },
+            expected: vec![Patch::MoveNodesBefore {
+                anchor_old_idx: 1,
+                to_move: vec![3, 2],
+            }],
+        }
+        .test();
+
+        DiffTestCase {
+            old: html! { <ul> <li key="a"></li> <li key="b"></li> <li key="c"></li> </ul> },
+            new: html! { <ul> <li key="b"></li> <li key="c"></li> <li key="a"></li> </ul> },
+            expected: vec![Patch::MoveToEndOfSiblings {
+                parent_old_idx: 0,
+                siblings_to_move: vec![1],
+            }],
+        }
+        .test();
+    }
+
+    /// Verify that new keyed children are placed in between moved keyed children in the right
+    /// order.
+    #[test]
+    fn keyed_insert_and_move_children() {
+        DiffTestCase {
+            old: html! { <ul> <li key="a"></li> <li key="b"></li> </ul> },
+            new: html! { <ul> <li key="x"></li> <li key="b"></li> <li key="y"></li> <li key="a"></li> </ul> },
+            expected: vec![
+                Patch::InsertBefore {
+                    anchor_old_idx: 1,
+                    new_nodes: vec![(1, &html! { <li key="x"></li> })],
+                },
+                Patch::MoveNodesBefore {
+                    anchor_old_idx: 1,
+                    to_move: vec![2],
+                },
+                Patch::InsertBefore {
+                    anchor_old_idx: 1,
+                    new_nodes: vec![(3, &html! { <li key="y"></li> })],
+                },
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that matched keyed children are diffed against each other using their old and new
+    /// node indices.
+    #[test]
+    fn keyed_children_are_diffed() {
+        DiffTestCase {
+            old: html! { <ul> <li key="a">A</li> <li key="b" id="old">B</li> </ul> },
+            new: html! { <ul> <li key="b" id="new">B</li> <li key="a">A</li> </ul> },
+            expected: vec![
+                Patch::MoveNodesBefore {
+                    anchor_old_idx: 1,
+                    to_move: vec![3],
+                },
+                Patch::AddAttributes(3, vec![("id", &"new".into())].into_iter().collect()),
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that a keyed child that changes its tag is removed and a new node is created in
+    /// its place.
+    #[test]
+    fn keyed_child_tag_changed() {
+        DiffTestCase {
+            old: html! { <div> <em key="a"></em> <b key="b"></b> </div> },
+            new: html! { <div> <em key="a"></em> <i key="b"></i> </div> },
+            expected: vec![
+                Patch::RemoveChildren {
+                    parent_old_idx: 0,
+                    to_remove: vec![2],
+                },
+                Patch::AppendChildren {
+                    old_idx: 0,
+                    new_nodes: vec![(2, &html! { <i key="b"></i> })],
+                },
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that we move a keyed child's events to its new node index.
+    #[test]
+    fn keyed_child_events_moved() {
+        DiffTestCase {
+            old: html! { <ul> <li key="a" onclick=||{}></li> <li key="b"></li> </ul> },
+            new: html! { <ul> <li key="b"></li> <li key="a" onclick=||{}></li> </ul> },
+            expected: vec![
+                Patch::MoveNodesBefore {
+                    anchor_old_idx: 1,
+                    to_move: vec![2],
+                },
+                Patch::SetEventsId {
+                    old_idx: 1,
+                    new_idx: 2,
+                },
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that we call the on remove element function for removed keyed children.
+    #[test]
+    fn keyed_child_removed_calls_on_remove_elem() {
+        let mut removed = html! { <li key="b"></li> };
+        set_on_remove_elem_with_unique_id(&mut removed, "remove");
+
+        let expected = {
+            let mut removed = html! { <li key="b"></li> };
+            set_on_remove_elem_with_unique_id(&mut removed, "remove");
+            removed
+        };
+
+        DiffTestCase {
+            old: html! { <ul> <li key="a"></li> {removed} </ul> },
+            new: html! { <ul> <li key="a"></li> </ul> },
+            expected: vec![
+                Patch::RemoveChildren {
+                    parent_old_idx: 0,
+                    to_remove: vec![2],
+                },
+                Patch::SpecialAttribute(PatchSpecialAttribute::CallOnRemoveElem(2, &expected)),
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that if only some of the children are keyed we diff the children by index.
+    #[test]
+    fn partially_keyed_children_diffed_by_index() {
+        DiffTestCase {
+            old: html! { <ul> <li key="a"></li> <li></li> </ul> },
+            new: html! { <ul> <li key="b"></li> <li key="a"></li> <li></li> </ul> },
+            expected: vec![
+                Patch::AddAttributes(1, vec![("key", &"b".into())].into_iter().collect()),
+                Patch::AddAttributes(2, vec![("key", &"a".into())].into_iter().collect()),
+                Patch::AppendChildren {
+                    old_idx: 0,
+                    new_nodes: vec![(3, &html! { <li></li> })],
+                },
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that a fragment's children are diffed as if they were the children of the
+    /// fragment's parent.
+    #[test]
+    fn fragment_children_flattened() {
+        DiffTestCase {
+            old: html! { <div> <> <b></b> <i></i> </> </div> },
+            new: html! { <div> <b></b> <i></i> </div> },
+            expected: vec![],
+        }
+        .test();
+
+        DiffTestCase {
+            old: html! { <div> <> <b></b> </> <i></i> </div> },
+            new: html! { <div> <b></b> <> <i></i> <em></em> </> </div> },
+            expected: vec![Patch::AppendChildren {
+                old_idx: 0,
+                new_nodes: vec![(3, &html! { <em></em> })],
+            }],
+        }
+        .test();
+
+        DiffTestCase {
+            old: html! { <div> <> <b></b> <i></i> </> </div> },
+            new: html! { <div> <> <b></b> </> </div> },
+            expected: vec![Patch::TruncateChildren(0, 1)],
+        }
+        .test();
+    }
+
+    /// Verify that fragments do not get a node index of their own.
+    #[test]
+    fn fragment_node_indices() {
+        DiffTestCase {
+            old: html! { <div> <> <b><em></em></b> </> <i></i> </div> },
+            new: html! { <div> <> <b><em></em></b> </> <strong></strong> </div> },
+            expected: vec![Patch::Replace {
+                old_idx: 3,
+                new_idx: 3,
+                new_node: &html! { <strong></strong> },
+            }],
+        }
+        .test();
+
+        DiffTestCase {
+            old: html! { <div> <><></></> <b></b> </div> },
+            new: html! { <div> <> <i></i> </> <b></b> </div> },
+            expected: vec![
+                Patch::Replace {
+                    old_idx: 1,
+                    new_idx: 1,
+                    new_node: &html! { <i></i> },
+                },
+                Patch::AppendChildren {
+                    old_idx: 0,
+                    new_nodes: vec![(2, &html! { <b></b> })],
+                },
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that keyed children that are inside of fragments are diffed by key.
+    #[test]
+    fn keyed_children_in_fragment() {
+        DiffTestCase {
+            old: html! { <ul> <> <li key="a"></li> <li key="b"></li> </> <li key="c"></li> </ul> },
+            new: html! { <ul> <li key="c"></li> <> <li key="b"></li> <li key="a"></li> </> </ul> },
+            expected: vec![Patch::MoveNodesBefore {
+                anchor_old_idx: 1,
+                to_move: vec![3, 2],
+            }],
+        }
+        .test();
+    }
+
+    /// Verify that a fragment root node gets replaced, since there is no single DOM node to patch.
+    #[test]
+    fn fragment_root_replaced() {
+        DiffTestCase {
+            old: html! { <> <b></b> </> },
+            new: html! { <> <b></b> </> },
+            expected: vec![Patch::Replace {
+                old_idx: 0,
+                new_idx: 0,
+                new_node: &html! { <> <b></b> </> },
+            }],
+        }
+        .test();
+    }
+
+    /// Verify that a placeholder for a `None` block keeps the position of the siblings after it, so
+    /// showing the block only replaces the placeholder.
+    #[test]
+    fn placeholder_keeps_sibling_positions() {
+        let none: Option<VirtualNode> = None;
+
+        DiffTestCase {
+            old: html! { <div> {none} <span></span> </div> },
+            new: html! { <div> <em></em> <span></span> </div> },
+            expected: vec![Patch::Replace {
+                old_idx: 1,
+                new_idx: 1,
+                new_node: &html! { <em></em> },
+            }],
+        }
+        .test();
+
+        let none: Option<VirtualNode> = None;
+
+        DiffTestCase {
+            old: html! { <div> <em></em> <span></span> </div> },
+            new: html! { <div> {none} <span></span> </div> },
+            expected: vec![Patch::Replace {
+                old_idx: 1,
+                new_idx: 1,
+                new_node: &VirtualNode::placeholder(),
+            }],
+        }
+        .test();
+    }
+
+    /// Verify that comments with different text get replaced.
+    #[test]
+    fn comments() {
+        DiffTestCase {
+            old: VirtualNode::placeholder(),
+            new: VirtualNode::placeholder(),
+            expected: vec![],
+        }
+        .test();
+
+        DiffTestCase {
+            old: VirtualNode::comment("a"),
+            new: VirtualNode::comment("b"),
+            expected: vec![Patch::Replace {
+                old_idx: 0,
+                new_idx: 0,
+                new_node: &VirtualNode::comment("b"),
+            }],
+        }
+        .test();
+    }
+
+    /// Verify that a portal's children are diffed as if the portal was an element in its spot.
+    #[test]
+    fn portal_children_diffed() {
+        DiffTestCase {
+            old: html! { <div> {portal(vec![html! { <b></b> }])} <i></i> </div> },
+            new: html! {
+                <div> {portal(vec![html! { <b></b> }, html! { <em></em> }])} <span></span> </div>
+            },
+            expected: vec![
+                Patch::AppendChildren {
+                    old_idx: 1,
+                    new_nodes: vec![(3, &html! { <em></em> })],
+                },
+                Patch::Replace {
+                    old_idx: 3,
+                    new_idx: 4,
+                    new_node: &html! { <span></span> },
+                },
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that a portal gets replaced when its mount changes.
+    #[test]
+    fn portal_mount_changed() {
+        let mut new_portal = VPortal::new("#modals");
+        new_portal.children.push(html! { <b></b> });
+
+        DiffTestCase {
+            old: html! { <div> {portal(vec![html! { <b></b> }])} <i></i> </div> },
+            new: html! { <div> {VirtualNode::from(new_portal)} <span></span> </div> },
+            expected: vec![
+                Patch::Replace {
+                    old_idx: 1,
+                    new_idx: 1,
+                    new_node: &{
+                        let mut portal = VPortal::new("#modals");
+                        portal.children.push(html! { <b></b> });
+                        VirtualNode::from(portal)
+                    },
+                },
+                Patch::Replace {
+                    old_idx: 3,
+                    new_idx: 3,
+                    new_node: &html! { <span></span> },
+                },
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that we remove a removed portal's children from its mount, and call the on remove
+    /// element functions of its children.
+    #[test]
+    fn removed_portal() {
+        let mut child = VirtualNode::element("em");
+        set_on_remove_elem_with_unique_id(&mut child, "key");
+
+        let expected_child = {
+            let mut child = VirtualNode::element("em");
+            set_on_remove_elem_with_unique_id(&mut child, "key");
+            child
+        };
+
+        DiffTestCase {
+            old: html! { <div> <span></span> {portal(vec![child])} </div> },
+            new: html! { <div> <span></span> </div> },
+            expected: vec![
+                Patch::TruncateChildren(0, 1),
+                Patch::RemovePortal(2),
+                Patch::SpecialAttribute(PatchSpecialAttribute::CallOnRemoveElem(
+                    3,
+                    &expected_child,
+                )),
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that a component whose props did not change is not rendered or diffed again.
+    #[test]
+    fn component_with_equal_props_reused() {
+        DiffTestCase {
+            old: html! { <div> <Label text="a" /> </div> },
+            new: html! { <div> <Label text="a" /> </div> },
+            expected: vec![],
+        }
+        .test();
+    }
+
+    /// Verify that we diff what a component renders when its props change.
+    #[test]
+    fn component_props_changed() {
+        DiffTestCase {
+            old: html! { <div> <Label text="a" /> </div> },
+            new: html! { <div> <Label text="b" /> </div> },
+            expected: vec![Patch::ChangeText(2, &VText::new("b"))],
+        }
+        .test();
+    }
+
+    /// Verify that we diff what a component renders when it is replaced by a different type of
+    /// component.
+    #[test]
+    fn component_type_changed() {
+        DiffTestCase {
+            old: html! { <div> <Label text="a" /> </div> },
+            new: html! { <div> <Icon /> </div> },
+            expected: vec![Patch::Replace {
+                old_idx: 1,
+                new_idx: 1,
+                new_node: &html! { <i></i> },
+            }],
+        }
+        .test();
+    }
+
+    /// Verify that if nodes were added before a component that was not rendered again, we move the
+    /// events of the nodes that the component rendered.
+    #[test]
+    fn reused_component_events_moved() {
+        DiffTestCase {
+            old: html! { <div> <span></span> <Clickable /> </div> },
+            new: html! { <div> <span><em></em></span> <Clickable /> </div> },
+            expected: vec![
+                Patch::AppendChildren {
+                    old_idx: 1,
+                    new_nodes: vec![(2, &VirtualNode::element("em"))],
+                },
+                Patch::SetEventsId {
+                    old_idx: 2,
+                    new_idx: 3,
+                },
+            ],
+        }
+        .test();
+    }
+
+    /// Verify that a memo whose key did not change is not rendered or diffed again, and that the
+    /// nodes after it keep their indices.
+    #[test]
+    fn memo_with_equal_key_skipped() {
+        let renders = Rc::new(Cell::new(0));
+
+        let old = html! { <div> {counted_memo(1, &renders)} <span></span> </div> };
+        // Rendered, as it would have been when it was patched into the DOM.
+        old.to_string();
+
+        let new = html! { <div> {counted_memo(1, &renders)} <span id="after"></span> </div> };
+
+        assert_eq!(
+            diff(&old, &new),
+            vec![Patch::AddAttributes(
+                3,
+                vec![("id", &AttributeValue::from("after"))]
+                    .into_iter()
+                    .collect()
+            )]
+        );
+        assert_eq!(renders.get(), 1);
+    }
+
+    /// Verify that we diff what a memo renders when its key changes.
+    #[test]
+    fn memo_key_changed() {
+        let renders = Rc::new(Cell::new(0));
+
+        let old = html! { <div> {counted_memo(1, &renders)} </div> };
+        old.to_string();
+
+        let new = html! { <div> {counted_memo(2, &renders)} </div> };
+
+        assert_eq!(
+            diff(&old, &new),
+            vec![Patch::ChangeText(2, &VText::new("2"))]
+        );
+        assert_eq!(renders.get(), 2);
+    }
+
+    /// Verify that if nodes were added before a memo that was skipped, we move the events of the
+    /// nodes that the memo rendered.
+    #[test]
+    fn skipped_memo_events_moved() {
+        let old = html! { <div> <span></span> {clickable_memo()} </div> };
+        old.to_string();
+
+        let new = html! { <div> <span><em></em></span> {clickable_memo()} </div> };
+
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                Patch::AppendChildren {
+                    old_idx: 1,
+                    new_nodes: vec![(2, &VirtualNode::element("em"))],
+                },
+                Patch::SetEventsId {
+                    old_idx: 2,
+                    new_idx: 3,
+                },
+            ]
+        );
+    }
+
+    fn portal(children: Vec<VirtualNode>) -> VirtualNode {
+        let mut portal = VPortal::new("body");
+        portal.children = children;
+        portal.into()
+    }
+
+    fn set_on_create_elem_with_unique_id(node: &mut VirtualNode, on_create_elem_id: &'static str) {
+        node.as_velement_mut()
+            .unwrap()
+            .special_attributes
+            .set_on_create_element(on_create_elem_id, |_: web_sys::Element| {});
+    }
+
+    fn set_on_remove_elem_with_unique_id(node: &mut VirtualNode, on_remove_elem_id: &'static str) {
+        node.as_velement_mut()
+            .unwrap()
+            .special_attributes
+            .set_on_remove_element(on_remove_elem_id, |_: web_sys::Element| {});
+    }
+
+    fn set_deferred_on_remove_elem(node: &mut VirtualNode, on_remove_elem_id: &'static str) {
+        node.as_velement_mut()
+            .unwrap()
+            .special_attributes
+            .set_on_remove_element_deferred(on_remove_elem_id, |_, removal| removal.finish());
+    }
+
+    fn set_dangerous_inner_html(node: &mut VirtualNode, html: &str) {
+        node.as_velement_mut()
+            .unwrap()
+            .special_attributes
+            .dangerous_inner_html = Some(html.to_string());
+    }
+
+    /// Return a node that has an on remove element function.
+    ///
+    /// This node has a child that also has an on remove element function.
+    ///
+    /// <div>
+    ///   <div></div>
+    /// </div>
+    fn on_remove_node_with_on_remove_child() -> VirtualNode {
+        let mut child = VirtualNode::element("div");
+        set_on_remove_elem_with_unique_id(&mut child, "555");
+
+        let mut node = VirtualNode::element("div");
+        set_on_remove_elem_with_unique_id(&mut node, "666");
+
+        node.as_velement_mut().unwrap().children.push(child);
+
+        node
+    }
+
+    /// Renders its text inside of an `<em>`.
+    #[derive(PartialEq)]
+    struct Label {
+        text: &'static str,
+    }
+
+    impl Component for Label {
+        type State = ();
+
+        fn render(&self, _state: &ComponentState<()>) -> VirtualNode {
+            html! { <em>{self.text}</em> }
+        }
+    }
+
+    #[derive(PartialEq)]
+    struct Icon {}
+
+    impl Component for Icon {
+        type State = ();
+
+        fn render(&self, _state: &ComponentState<()>) -> VirtualNode {
+            html! { <i></i> }
+        }
+    }
+
+    #[derive(PartialEq)]
+    struct Clickable {}
+
+    impl Component for Clickable {
+        type State = ();
+
+        fn render(&self, _state: &ComponentState<()>) -> VirtualNode {
+            let mut button = VirtualNode::element("button");
+            button
+                .as_velement_mut()
+                .unwrap()
+                .events
+                .insert(onclick_name(), mock_event_handler());
+            button
+        }
+    }
+
+    /// A memo that renders its key inside of a `<p>`, counting how many times it gets rendered.
+    fn counted_memo(key: u32, renders: &Rc<Cell<u32>>) -> VirtualNode {
+        let renders = renders.clone();
+
+        VirtualNode::memo(key, move || {
+            renders.set(renders.get() + 1);
+            html! { <p>{key.to_string()}</p> }
+        })
+    }
+
+    fn clickable_memo() -> VirtualNode {
+        VirtualNode::memo((), || VirtualNode::component(Clickable {}))
     }
 
     fn mock_event_handler() -> EventHandler {
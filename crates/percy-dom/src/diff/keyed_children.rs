@@ -0,0 +1,348 @@
+//! Diffing children by their `key` attribute instead of by their position.
+//!
+//! When every child of both the old and new element is an element with a unique `key` attribute,
+//! we match up old and new children that have the same key (and tag) and diff them against each
+//! other.
+//!
+//! Old children without a matching new child are removed, new children without a matching old
+//! child are created, and matched children that are out of order are moved.
+//!
+//! To keep the number of moves small we leave the longest run of matched children that are
+//! already in the right relative order where they are, and move everything else around them.
+
+use crate::diff::{
+    collapse_removed_events, diff_recursive, increment_idx_for_child,
+    process_deleted_old_node_child, FlatChildren,
+};
+use crate::{AttributeValue, Patch, VirtualNode};
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Generate the patches that turn the old element's keyed children into the new element's keyed
+/// children.
+///
+/// Returns `false` without pushing any patches if the children cannot be diffed by key, in which
+/// case they should be diffed by index instead.
+pub(super) fn diff_keyed_children<'a, 'b>(
+    old_node_idx: &'b mut u32,
+    new_node_idx: &'b mut u32,
+    old_children: &FlatChildren<'a>,
+    new_children: &FlatChildren<'a>,
+    patches: &mut Vec<Patch<'a>>,
+) -> bool {
+    if old_children.is_empty() || new_children.is_empty() {
+        return false;
+    }
+
+    let old_keys = match unique_keys(old_children) {
+        Some(keys) => keys,
+        None => return false,
+    };
+    let new_keys = match unique_keys(new_children) {
+        Some(keys) => keys,
+        None => return false,
+    };
+
+    let parent_old_idx = *old_node_idx;
+
+    // The depth first index of each of the old children.
+    let mut old_child_indices = Vec::with_capacity(old_children.len());
+    let mut last_old_idx = parent_old_idx;
+    for old_child in old_children.iter() {
+        old_child_indices.push(last_old_idx + 1);
+        increment_idx_for_child(old_child, &mut last_old_idx);
+    }
+
+    let old_positions: HashMap<&str, usize> = old_keys
+        .iter()
+        .enumerate()
+        .map(|(position, key)| (key.as_ref(), position))
+        .collect();
+
+    // For each new child, the position of the old child that it will be diffed against.
+    let mut matched_old_positions: Vec<Option<usize>> = Vec::with_capacity(new_children.len());
+    let mut old_child_is_matched = vec![false; old_children.len()];
+
+    for (new_child, new_key) in new_children.iter().zip(new_keys.iter()) {
+        let matched = old_positions
+            .get(new_key.as_ref())
+            .copied()
+            .filter(|old_position| tag(old_children.get(*old_position)) == tag(new_child));
+
+        if let Some(old_position) = matched {
+            old_child_is_matched[old_position] = true;
+        }
+
+        matched_old_positions.push(matched);
+    }
+
+    let mut to_remove = vec![];
+    let mut removed_patches = vec![];
+    for (old_position, old_child) in old_children.iter().enumerate() {
+        if old_child_is_matched[old_position] {
+            continue;
+        }
+
+        let old_child_idx = old_child_indices[old_position];
+        to_remove.push(old_child_idx);
+
+        let mut cur_node_idx = old_child_idx - 1;
+        let first_patch = removed_patches.len();
+        process_deleted_old_node_child(old_child, &mut cur_node_idx, true, &mut removed_patches);
+        collapse_removed_events(first_patch, &mut removed_patches);
+    }
+    if !to_remove.is_empty() {
+        patches.push(Patch::RemoveChildren {
+            parent_old_idx,
+            to_remove,
+        });
+    }
+    patches.append(&mut removed_patches);
+
+    let stays_in_place = children_that_stay_in_place(&matched_old_positions);
+
+    // For each new child, the old index of the closest child to its right that is not getting
+    // moved. Nodes that get created or moved are placed right before this anchor.
+    let mut anchors = vec![None; new_children.len()];
+    let mut next_anchor = None;
+    for new_position in (0..new_children.len()).rev() {
+        anchors[new_position] = next_anchor;
+
+        if stays_in_place[new_position] {
+            let old_position = matched_old_positions[new_position].unwrap();
+            next_anchor = Some(old_child_indices[old_position]);
+        }
+    }
+
+    let mut placement_patches: Vec<Patch<'a>> = vec![];
+    let mut child_patches = vec![];
+
+    for (new_position, new_child) in new_children.iter().enumerate() {
+        let anchor = anchors[new_position];
+
+        match matched_old_positions[new_position] {
+            Some(old_position) => {
+                let old_child_idx = old_child_indices[old_position];
+
+                if !stays_in_place[new_position] {
+                    push_move(
+                        &mut placement_patches,
+                        parent_old_idx,
+                        anchor,
+                        old_child_idx,
+                    );
+                }
+
+                let mut cur_old_idx = old_child_idx;
+                *new_node_idx += 1;
+                diff_recursive(
+                    old_children.get(old_position),
+                    new_child,
+                    &mut cur_old_idx,
+                    new_node_idx,
+                    &mut child_patches,
+                );
+            }
+            None => {
+                push_insert(
+                    &mut placement_patches,
+                    parent_old_idx,
+                    anchor,
+                    (*new_node_idx + 1, new_child),
+                );
+
+                increment_idx_for_child(new_child, new_node_idx);
+            }
+        }
+    }
+
+    patches.append(&mut placement_patches);
+    patches.append(&mut child_patches);
+
+    *old_node_idx = last_old_idx;
+
+    true
+}
+
+/// The `key` attribute of every node, or `None` if any of the nodes are not keyed elements or if
+/// two of the nodes share the same key.
+fn unique_keys<'a>(nodes: &FlatChildren<'a>) -> Option<Vec<Cow<'a, str>>> {
+    // Most children are not keyed, so we check before allocating.
+    nodes.get(0).as_velement_ref()?.attrs.get("key")?;
+
+    let mut keys = Vec::with_capacity(nodes.len());
+
+    for node in nodes.iter() {
+        let key = match node.as_velement_ref()?.attrs.get("key")? {
+            AttributeValue::String(key) => Cow::Borrowed(key.as_str()),
+            AttributeValue::Bool(_) => return None,
+            key => Cow::Owned(key.to_string()),
+        };
+        keys.push(key);
+    }
+
+    let mut sorted = keys.clone();
+    sorted.sort_unstable();
+    sorted.dedup();
+
+    if sorted.len() != keys.len() {
+        return None;
+    }
+
+    Some(keys)
+}
+
+fn tag(node: &VirtualNode) -> &str {
+    node.as_velement_ref().map(|e| e.tag.as_str()).unwrap_or("")
+}
+
+/// Insert a newly created node, combining it with the previous patch if that patch inserts nodes
+/// into the same spot.
+fn push_insert<'a>(
+    placement_patches: &mut Vec<Patch<'a>>,
+    parent_old_idx: u32,
+    anchor: Option<u32>,
+    new_node: (u32, &'a VirtualNode),
+) {
+    match (placement_patches.last_mut(), anchor) {
+        (
+            Some(Patch::InsertBefore {
+                anchor_old_idx,
+                new_nodes,
+            }),
+            Some(anchor),
+        ) if *anchor_old_idx == anchor => {
+            new_nodes.push(new_node);
+        }
+        (Some(Patch::AppendChildren { new_nodes, .. }), None) => {
+            new_nodes.push(new_node);
+        }
+        (_, Some(anchor)) => {
+            placement_patches.push(Patch::InsertBefore {
+                anchor_old_idx: anchor,
+                new_nodes: vec![new_node],
+            });
+        }
+        (_, None) => {
+            placement_patches.push(Patch::AppendChildren {
+                old_idx: parent_old_idx,
+                new_nodes: vec![new_node],
+            });
+        }
+    };
+}
+
+/// Move an existing node, combining it with the previous patch if that patch moves nodes into the
+/// same spot.
+fn push_move(
+    placement_patches: &mut Vec<Patch>,
+    parent_old_idx: u32,
+    anchor: Option<u32>,
+    old_idx: u32,
+) {
+    match (placement_patches.last_mut(), anchor) {
+        (
+            Some(Patch::MoveNodesBefore {
+                anchor_old_idx,
+                to_move,
+            }),
+            Some(anchor),
+        ) if *anchor_old_idx == anchor => {
+            to_move.push(old_idx);
+        }
+        (
+            Some(Patch::MoveToEndOfSiblings {
+                siblings_to_move, ..
+            }),
+            None,
+        ) => {
+            siblings_to_move.push(old_idx);
+        }
+        (_, Some(anchor)) => {
+            placement_patches.push(Patch::MoveNodesBefore {
+                anchor_old_idx: anchor,
+                to_move: vec![old_idx],
+            });
+        }
+        (_, None) => {
+            placement_patches.push(Patch::MoveToEndOfSiblings {
+                parent_old_idx,
+                siblings_to_move: vec![old_idx],
+            });
+        }
+    };
+}
+
+/// Given the old position of every matched new child, determine which new children can stay
+/// where they are.
+///
+/// These are the children in the longest increasing subsequence of old positions, since they are
+/// already in the right order relative to each other.
+fn children_that_stay_in_place(matched_old_positions: &[Option<usize>]) -> Vec<bool> {
+    let mut stays_in_place = vec![false; matched_old_positions.len()];
+
+    let matched: Vec<(usize, usize)> = matched_old_positions
+        .iter()
+        .enumerate()
+        .filter_map(|(new_position, old_position)| old_position.map(|old| (new_position, old)))
+        .collect();
+
+    let old_positions: Vec<usize> = matched.iter().map(|(_, old)| *old).collect();
+
+    for idx in longest_increasing_subsequence(&old_positions) {
+        stays_in_place[matched[idx].0] = true;
+    }
+
+    stays_in_place
+}
+
+/// Return the indices of the elements that make up a longest strictly increasing subsequence.
+///
+/// O(n log n) - see https://en.wikipedia.org/wiki/Longest_increasing_subsequence
+fn longest_increasing_subsequence(sequence: &[usize]) -> Vec<usize> {
+    // tails[len] is the index of the smallest element that ends an increasing subsequence of
+    // length `len + 1`.
+    let mut tails: Vec<usize> = vec![];
+    let mut predecessors: Vec<Option<usize>> = vec![None; sequence.len()];
+
+    for (idx, value) in sequence.iter().enumerate() {
+        let len = tails.partition_point(|tail| sequence[*tail] < *value);
+
+        if len > 0 {
+            predecessors[idx] = Some(tails[len - 1]);
+        }
+
+        if len == tails.len() {
+            tails.push(idx);
+        } else {
+            tails[len] = idx;
+        }
+    }
+
+    let mut subsequence = Vec::with_capacity(tails.len());
+    let mut current = tails.last().copied();
+    while let Some(idx) = current {
+        subsequence.push(idx);
+        current = predecessors[idx];
+    }
+    subsequence.reverse();
+
+    subsequence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that we find a longest increasing subsequence.
+    #[test]
+    fn longest_increasing_subsequence_indices() {
+        assert_eq!(longest_increasing_subsequence(&[]), Vec::<usize>::new());
+        assert_eq!(longest_increasing_subsequence(&[0, 1, 2]), vec![0, 1, 2]);
+        assert_eq!(longest_increasing_subsequence(&[2, 1, 0]), vec![2]);
+        assert_eq!(
+            longest_increasing_subsequence(&[3, 0, 1, 4, 2]),
+            vec![1, 2, 4]
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! Turning patches that remove a node from one parent and create an equal node in another parent
+//! into a patch that moves the node.
+//!
+//! Children that aren't keyed get diffed by their position, so when the last item of one list
+//! becomes the last item of another list the first list gets a `TruncateChildren` and the second
+//! list gets an `AppendChildren`. Instead of removing the old DOM nodes and creating new ones we
+//! move the truncated nodes over.
+//!
+//! Only nodes that have nothing besides their tag, attributes, styles and children get moved, so
+//! that there are no events, node refs or special attribute functions that would need to follow
+//! the node to its new index.
+
+use std::collections::{BTreeSet, HashMap};
+use std::mem;
+
+use crate::diff::{increment_idx_for_child, FlatChildren};
+use crate::{Namespace, Patch, VElement, VirtualNode};
+
+/// The namespace of a node for each namespace that the root node might get created in, since the
+/// root node's namespace depends on where it is mounted.
+type Namespaces = [Namespace; 3];
+
+const ROOT_NAMESPACES: Namespaces = [Namespace::Html, Namespace::Svg, Namespace::MathMl];
+
+/// Replace the nodes of `AppendChildren` patches that are equal to nodes that a `TruncateChildren`
+/// patch removes with `MoveToEndOfSiblings` patches that move the truncated nodes.
+///
+/// A moved node keeps its DOM node, so we only move nodes between parents whose children get
+/// created in the same namespace.
+pub(super) fn move_truncated_children<'a>(old: &'a VirtualNode, patches: &mut Vec<Patch<'a>>) {
+    let mut parents = BTreeSet::new();
+    let mut has_truncate = false;
+    let mut has_append = false;
+
+    for patch in patches.iter() {
+        match patch {
+            Patch::TruncateChildren(parent_idx, _) => {
+                has_truncate = true;
+                parents.insert(*parent_idx);
+            }
+            Patch::AppendChildren { old_idx, .. } => {
+                has_append = true;
+                parents.insert(*old_idx);
+            }
+            _ => {}
+        }
+    }
+    if !has_truncate || !has_append {
+        return;
+    }
+
+    let mut found = HashMap::new();
+    find_parents(old, ROOT_NAMESPACES, 0, &parents, &mut found);
+
+    let mut truncated = truncated_children(patches, &found);
+    if truncated.is_empty() {
+        return;
+    }
+
+    // For each `AppendChildren` patch, the old index of the truncated node that takes the place of
+    // each of its new nodes, if any.
+    let mut moves = HashMap::new();
+    for (position, patch) in patches.iter().enumerate() {
+        if let Patch::AppendChildren { old_idx, new_nodes } = patch {
+            let parent = match found.get(old_idx) {
+                Some(parent) => parent,
+                None => continue,
+            };
+
+            let moved: Vec<Option<u32>> = new_nodes
+                .iter()
+                .map(|(_, new_node)| take_equal_truncated(new_node, parent, &mut truncated))
+                .collect();
+            if moved.iter().any(Option::is_some) {
+                moves.insert(position, moved);
+            }
+        }
+    }
+    if moves.is_empty() {
+        return;
+    }
+
+    for (position, patch) in mem::take(patches).into_iter().enumerate() {
+        match (patch, moves.get(&position)) {
+            (Patch::AppendChildren { old_idx, new_nodes }, Some(moved)) => {
+                push_appends_and_moves(old_idx, new_nodes, moved, patches);
+            }
+            (patch, _) => patches.push(patch),
+        }
+    }
+}
+
+/// An old node that had children appended to it or truncated.
+struct Parent<'a> {
+    node: &'a VirtualNode,
+    children_namespaces: Namespaces,
+}
+
+/// The truncated children that have the same tag.
+#[derive(Default)]
+struct SameTag<'a> {
+    candidates: Vec<Truncated<'a>>,
+    /// The position of the first candidate that wasn't moved yet.
+    first_unmoved: usize,
+}
+
+/// A truncated child that can be moved.
+struct Truncated<'a> {
+    old_idx: u32,
+    element: &'a VElement,
+    /// The namespaces of the truncated parent's children.
+    parent_namespaces: Namespaces,
+    moved: bool,
+}
+
+/// Find the old nodes with the given indices, along with the namespaces of their children.
+///
+/// Only the children whose descendants include one of the nodes get visited.
+fn find_parents<'a>(
+    node: &'a VirtualNode,
+    parent_namespaces: Namespaces,
+    node_idx: u32,
+    to_find: &BTreeSet<u32>,
+    found: &mut HashMap<u32, Parent<'a>>,
+) {
+    let node = node.rendered_node();
+    let children_namespaces = match node {
+        VirtualNode::Element(element) => {
+            parent_namespaces.map(|namespace| namespace.of_child(element).of_children(element))
+        }
+        // A portal's children are held by a `<div>`.
+        VirtualNode::Portal(_) => [Namespace::Html; 3],
+        _ => return,
+    };
+
+    if to_find.contains(&node_idx) {
+        found.insert(
+            node_idx,
+            Parent {
+                node,
+                children_namespaces,
+            },
+        );
+    }
+
+    let mut last_idx = node_idx;
+    for child in FlatChildren::of(node).iter() {
+        let child_idx = last_idx + 1;
+        increment_idx_for_child(child, &mut last_idx);
+
+        if to_find.range(child_idx..=last_idx).next().is_some() {
+            find_parents(child, children_namespaces, child_idx, to_find, found);
+        }
+    }
+}
+
+/// The truncated children that can be moved, by tag.
+fn truncated_children<'a>(
+    patches: &[Patch<'a>],
+    found: &HashMap<u32, Parent<'a>>,
+) -> HashMap<&'a str, SameTag<'a>> {
+    let mut truncated: HashMap<&str, SameTag> = HashMap::new();
+
+    for patch in patches {
+        let (parent_idx, remaining) = match patch {
+            Patch::TruncateChildren(parent_idx, remaining) => (*parent_idx, *remaining),
+            _ => continue,
+        };
+        let parent = match found.get(&parent_idx) {
+            Some(parent) => parent,
+            None => continue,
+        };
+
+        let mut node_idx = parent_idx;
+        for (position, child) in FlatChildren::of(parent.node).iter().enumerate() {
+            let child_idx = node_idx + 1;
+            increment_idx_for_child(child, &mut node_idx);
+
+            if position < remaining || !is_static(child) {
+                continue;
+            }
+
+            if let VirtualNode::Element(element) = child {
+                truncated
+                    .entry(element.tag.as_str())
+                    .or_default()
+                    .candidates
+                    .push(Truncated {
+                        old_idx: child_idx,
+                        element,
+                        parent_namespaces: parent.children_namespaces,
+                        moved: false,
+                    });
+            }
+        }
+    }
+
+    truncated
+}
+
+/// The old index of a truncated element that is equal to the appended node and whose parent's
+/// children are in the same namespace as the new parent's, marking it as moved.
+fn take_equal_truncated(
+    new_node: &VirtualNode,
+    parent: &Parent,
+    truncated: &mut HashMap<&str, SameTag>,
+) -> Option<u32> {
+    let element = match new_node {
+        VirtualNode::Element(element) if is_static(new_node) => element,
+        _ => return None,
+    };
+    let same_tag = truncated.get_mut(element.tag.as_str())?;
+
+    let position = same_tag.candidates[same_tag.first_unmoved..]
+        .iter()
+        .position(|truncated| {
+            !truncated.moved
+                && truncated.parent_namespaces == parent.children_namespaces
+                && truncated.element == element
+        })?
+        + same_tag.first_unmoved;
+    same_tag.candidates[position].moved = true;
+
+    // Nodes usually get moved in order, so we skip over the moved ones at the start.
+    while same_tag
+        .candidates
+        .get(same_tag.first_unmoved)
+        .is_some_and(|truncated| truncated.moved)
+    {
+        same_tag.first_unmoved += 1;
+    }
+
+    Some(same_tag.candidates[position].old_idx)
+}
+
+/// Split an `AppendChildren` patch into patches that append the new nodes that weren't matched
+/// and patches that move the truncated nodes that were, keeping the nodes in order.
+fn push_appends_and_moves<'a>(
+    parent_idx: u32,
+    new_nodes: Vec<(u32, &'a VirtualNode)>,
+    moved: &[Option<u32>],
+    patches: &mut Vec<Patch<'a>>,
+) {
+    let mut to_append = vec![];
+    let mut to_move = vec![];
+
+    for (new_node, moved) in new_nodes.into_iter().zip(moved) {
+        match moved {
+            Some(old_idx) => {
+                if !to_append.is_empty() {
+                    patches.push(Patch::AppendChildren {
+                        old_idx: parent_idx,
+                        new_nodes: mem::take(&mut to_append),
+                    });
+                }
+                to_move.push(*old_idx);
+            }
+            None => {
+                if !to_move.is_empty() {
+                    patches.push(Patch::MoveToEndOfSiblings {
+                        parent_old_idx: parent_idx,
+                        siblings_to_move: mem::take(&mut to_move),
+                    });
+                }
+                to_append.push(new_node);
+            }
+        }
+    }
+
+    if !to_append.is_empty() {
+        patches.push(Patch::AppendChildren {
+            old_idx: parent_idx,
+            new_nodes: to_append,
+        });
+    }
+    if !to_move.is_empty() {
+        patches.push(Patch::MoveToEndOfSiblings {
+            parent_old_idx: parent_idx,
+            siblings_to_move: to_move,
+        });
+    }
+}
+
+/// Whether the node and its descendants are only elements, text and comments, without any events,
+/// DOM properties or special attributes.
+fn is_static(node: &VirtualNode) -> bool {
+    match node {
+        VirtualNode::Element(element) => {
+            let special = &element.special_attributes;
+
+            !element.events.has_events()
+                && element.dom_property_attributes().next().is_none()
+                && special.on_create_element_key().is_none()
+                && special.on_remove_element_key().is_none()
+                && special.on_update_element_key().is_none()
+                && special.dangerous_inner_html.is_none()
+                && special.node_ref().is_none()
+                && element.children.iter().all(is_static)
+        }
+        VirtualNode::Text(_) | VirtualNode::Comment(_) => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,307 @@
+//! Re-rendering only the components whose state changed, and diffing their new rendered nodes
+//! against their old ones without re-rendering the rest of the virtual dom.
+
+use std::ptr;
+use std::rc::Rc;
+
+use crate::diff::{diff_recursive, diff_unchanged};
+use crate::{Patch, VirtualNode};
+
+/// A component that was rendered again.
+pub(crate) struct RerenderedComponent {
+    old: Rc<VirtualNode>,
+    /// Used to find the component again when diffing. Never dereferenced.
+    new: *const VirtualNode,
+}
+
+/// Re-render every component whose state changed since it was last rendered.
+///
+/// The components inside of a re-rendered component are not visited, since they get taken care of
+/// when the re-rendered component is diffed.
+///
+/// Returned in depth first order.
+pub(crate) fn rerender_changed_components(node: &mut VirtualNode) -> Vec<RerenderedComponent> {
+    let mut rerendered = vec![];
+    push_rerendered(node, &mut rerendered);
+    rerendered
+}
+
+fn push_rerendered(node: &mut VirtualNode, rerendered: &mut Vec<RerenderedComponent>) {
+    match node {
+        VirtualNode::Element(element) => {
+            for child in element.children.iter_mut() {
+                push_rerendered(child, rerendered);
+            }
+        }
+        VirtualNode::Fragment(fragment) => {
+            for child in fragment.children.iter_mut() {
+                push_rerendered(child, rerendered);
+            }
+        }
+        VirtualNode::Portal(portal) => {
+            for child in portal.children.iter_mut() {
+                push_rerendered(child, rerendered);
+            }
+        }
+        VirtualNode::Component(component) if component.state_changed() => {
+            if let Some(old) = component.rerender() {
+                let new = component.rendered() as *const VirtualNode;
+                rerendered.push(RerenderedComponent { old, new });
+            }
+        }
+        VirtualNode::Component(component) => match component.rendered_mut() {
+            Some(rendered) => push_rerendered(rendered, rerendered),
+            // The rendered node is shared with another component, such as an old memo that it took
+            // the place of, so we can't re-render the components inside of it. Instead we
+            // re-render the whole component and diff it.
+            None => {
+                if contains_changed_component(component.rendered()) {
+                    if let Some(old) = component.rerender() {
+                        let new = component.rendered() as *const VirtualNode;
+                        rerendered.push(RerenderedComponent { old, new });
+                    }
+                }
+            }
+        },
+        VirtualNode::Text(_) | VirtualNode::Comment(_) => {}
+    }
+}
+
+/// Whether or not the node contains a component whose state changed since it was rendered.
+fn contains_changed_component(node: &VirtualNode) -> bool {
+    match node {
+        VirtualNode::Element(element) => element.children.iter().any(contains_changed_component),
+        VirtualNode::Fragment(fragment) => fragment.children.iter().any(contains_changed_component),
+        VirtualNode::Portal(portal) => portal.children.iter().any(contains_changed_component),
+        VirtualNode::Component(component) => {
+            component.state_changed() || contains_changed_component(component.rendered())
+        }
+        VirtualNode::Text(_) | VirtualNode::Comment(_) => false,
+    }
+}
+
+/// Generate the patches that turn the DOM for the re-rendered components' old rendered nodes into
+/// the DOM for their new rendered nodes.
+///
+/// The rest of the virtual dom is unchanged, but nodes that come after a re-rendered component
+/// have their events moved if the component now renders a different number of nodes.
+pub(crate) fn diff_rerendered_components<'a>(
+    node: &'a VirtualNode,
+    rerendered: &'a [RerenderedComponent],
+) -> Vec<Patch<'a>> {
+    let mut patches = vec![];
+    let mut next_rerendered = 0;
+
+    diff_node(
+        node,
+        rerendered,
+        &mut next_rerendered,
+        &mut 0,
+        &mut 0,
+        &mut patches,
+    );
+
+    patches
+}
+
+fn diff_node<'a>(
+    node: &'a VirtualNode,
+    rerendered: &'a [RerenderedComponent],
+    next_rerendered: &mut usize,
+    old_node_idx: &mut u32,
+    new_node_idx: &mut u32,
+    patches: &mut Vec<Patch<'a>>,
+) {
+    if *next_rerendered == rerendered.len() {
+        diff_unchanged(node, old_node_idx, new_node_idx, patches);
+        return;
+    }
+
+    match node {
+        VirtualNode::Component(component) => {
+            let current = &rerendered[*next_rerendered];
+
+            if ptr::eq(component.rendered(), current.new) {
+                *next_rerendered += 1;
+
+                diff_recursive(
+                    &current.old,
+                    component.rendered(),
+                    old_node_idx,
+                    new_node_idx,
+                    patches,
+                );
+            } else {
+                diff_node(
+                    component.rendered(),
+                    rerendered,
+                    next_rerendered,
+                    old_node_idx,
+                    new_node_idx,
+                    patches,
+                );
+            }
+        }
+        VirtualNode::Element(element) => {
+            if element.events.has_events() && old_node_idx != new_node_idx {
+                patches.push(Patch::SetEventsId {
+                    old_idx: *old_node_idx,
+                    new_idx: *new_node_idx,
+                });
+            }
+
+            diff_children(
+                element.flat_children(),
+                rerendered,
+                next_rerendered,
+                old_node_idx,
+                new_node_idx,
+                patches,
+            );
+        }
+        VirtualNode::Portal(portal) => {
+            diff_children(
+                portal.flat_children(),
+                rerendered,
+                next_rerendered,
+                old_node_idx,
+                new_node_idx,
+                patches,
+            );
+        }
+        VirtualNode::Text(_) | VirtualNode::Comment(_) | VirtualNode::Fragment(_) => {}
+    }
+}
+
+fn diff_children<'a>(
+    children: Vec<&'a VirtualNode>,
+    rerendered: &'a [RerenderedComponent],
+    next_rerendered: &mut usize,
+    old_node_idx: &mut u32,
+    new_node_idx: &mut u32,
+    patches: &mut Vec<Patch<'a>>,
+) {
+    for child in children {
+        *old_node_idx += 1;
+        *new_node_idx += 1;
+
+        diff_node(
+            child,
+            rerendered,
+            next_rerendered,
+            old_node_idx,
+            new_node_idx,
+            patches,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::{EventHandler, EventName};
+    use crate::{html, EventAttribFn};
+    use std::cell::RefCell;
+    use virtual_node::{Component, ComponentState, IterableNodes};
+    use wasm_bindgen::JsValue;
+
+    /// Verify that we only re-render the components whose state changed, and that we move the
+    /// events of the nodes after a re-rendered component that now renders more nodes.
+    #[test]
+    fn rerender_and_diff_changed_component() {
+        let state = Rc::new(RefCell::new(None));
+
+        let mut button = VirtualNode::element("button");
+        button.as_velement_mut().unwrap().events.insert(
+            EventName::ONCLICK,
+            EventHandler::UnsupportedSignature(EventAttribFn(Rc::new(Box::new(JsValue::NULL)))),
+        );
+
+        let mut node = html! { <div> <List state=state.clone() /> {button} </div> };
+        // Render the component.
+        node.to_string();
+
+        assert!(rerender_changed_components(&mut node).is_empty());
+
+        state.borrow().as_ref().unwrap().update(|count| *count = 1);
+
+        let rerendered = rerender_changed_components(&mut node);
+        assert_eq!(rerendered.len(), 1);
+
+        assert_eq!(
+            diff_rerendered_components(&node, &rerendered),
+            vec![
+                Patch::AppendChildren {
+                    old_idx: 1,
+                    new_nodes: vec![(2, &VirtualNode::element("li"))],
+                },
+                Patch::SetEventsId {
+                    old_idx: 2,
+                    new_idx: 3,
+                },
+            ]
+        );
+    }
+
+    /// Verify that a component whose state changed gets re-rendered when it is inside of a memo
+    /// whose rendered node is shared with the memo that it took the place of.
+    #[test]
+    fn rerender_component_inside_shared_memo() {
+        let state = Rc::new(RefCell::new(None));
+
+        let old = memo_with_list(&state);
+        old.to_string();
+
+        let mut node = memo_with_list(&state);
+        crate::diff(&old, &node);
+
+        state.borrow().as_ref().unwrap().update(|count| *count = 1);
+
+        let rerendered = rerender_changed_components(&mut node);
+        assert_eq!(rerendered.len(), 1);
+
+        assert_eq!(
+            diff_rerendered_components(&node, &rerendered),
+            vec![Patch::AppendChildren {
+                old_idx: 1,
+                new_nodes: vec![(2, &VirtualNode::element("li"))],
+            }]
+        );
+    }
+
+    fn memo_with_list(state: &Rc<RefCell<Option<ComponentState<u32>>>>) -> VirtualNode {
+        let state = state.clone();
+        VirtualNode::memo(
+            (),
+            move || html! { <div> <List state=state.clone() /> </div> },
+        )
+    }
+
+    /// Renders a list item for every count, and hands out its state.
+    struct List {
+        state: Rc<RefCell<Option<ComponentState<u32>>>>,
+    }
+
+    impl PartialEq for List {
+        fn eq(&self, _other: &Self) -> bool {
+            true
+        }
+    }
+
+    impl Component for List {
+        type State = u32;
+
+        fn render(&self, state: &ComponentState<u32>) -> VirtualNode {
+            *self.state.borrow_mut() = Some(state.clone());
+
+            let mut list = VirtualNode::element("ul");
+            for _ in 0..*state.get() {
+                list.as_velement_mut()
+                    .unwrap()
+                    .children
+                    .push(VirtualNode::element("li"));
+            }
+            list
+        }
+    }
+}
@@ -40,7 +40,7 @@ pub mod prelude {
 
     #[cfg(feature = "macro")]
     pub use html_macro::html;
-    pub use virtual_node::{EventAttribFn, IterableNodes, View};
+    pub use virtual_node::{Component, ComponentState, EventAttribFn, IterableNodes, View};
 
     pub use crate::pdom::PercyDom;
     pub use crate::VirtualNode;
@@ -49,6 +49,10 @@ pub mod prelude {
     #[doc(hidden)]
     pub mod __html_macro_helpers__ {
         pub use virtual_node::event;
+        pub use virtual_node::{bind_checked, bind_group, bind_value};
+        pub use virtual_node::{
+            AttributeValue, DeferredRemoval, ListItem, OptionalListItem, RequiredListItem,
+        };
         pub use web_sys;
     }
 }
@@ -2,13 +2,18 @@
 //! The Percy Book.
 
 use std::collections::HashMap;
+use std::ops::RangeInclusive;
 
-pub use apply_patches::patch;
+pub use apply_patches::{patch, patch_owned};
+pub use owned_patch::*;
+pub use patch_error::{PatchError, PatchErrorKind};
 
 use crate::event::{EventHandler, EventName};
 use crate::{AttributeValue, VText, VirtualNode};
 
 mod apply_patches;
+mod owned_patch;
+mod patch_error;
 
 type NodeIdx = u32;
 
@@ -58,6 +63,40 @@ pub enum Patch<'a> {
     },
     /// For a `node_i32`, remove all children besides the first `len`
     TruncateChildren(NodeIdx, usize),
+    /// Create new nodes and insert them, in order, directly before the anchor node.
+    ///
+    /// Used when diffing keyed children.
+    #[allow(missing_docs)]
+    InsertBefore {
+        anchor_old_idx: NodeIdx,
+        new_nodes: Vec<(NodeIdx, &'a VirtualNode)>,
+    },
+    /// Move existing nodes, in order, so that they sit directly before the anchor node.
+    ///
+    /// Used when diffing keyed children.
+    #[allow(missing_docs)]
+    MoveNodesBefore {
+        anchor_old_idx: NodeIdx,
+        to_move: Vec<NodeIdx>,
+    },
+    /// Move existing nodes, in order, to the end of the parent's children.
+    ///
+    /// Used when diffing keyed children, and to move the truncated children of one parent to the
+    /// end of another parent when an equal node would otherwise be appended there.
+    #[allow(missing_docs)]
+    MoveToEndOfSiblings {
+        parent_old_idx: NodeIdx,
+        siblings_to_move: Vec<NodeIdx>,
+    },
+    /// Remove some of a parent node's children.
+    ///
+    /// Used when diffing keyed children, where the removed children are not necessarily the last
+    /// children of the parent.
+    #[allow(missing_docs)]
+    RemoveChildren {
+        parent_old_idx: NodeIdx,
+        to_remove: Vec<NodeIdx>,
+    },
     /// Replace a node with another node. This typically happens when a node's tag changes.
     /// ex: <div> becomes <span>
     #[allow(missing_docs)]
@@ -66,13 +105,29 @@ pub enum Patch<'a> {
         new_idx: NodeIdx,
         new_node: &'a VirtualNode,
     },
+    /// Set attributes as DOM properties. These get set every time that an element is patched,
+    /// even if they did not change, in case the user changed them.
+    /// ex: something was typed into an input field or a checkbox was clicked.
+    ///
+    /// See [`crate::AttributeTarget`].
+    SetDomProperties(NodeIdx, HashMap<&'a str, &'a AttributeValue>),
+    /// Reset DOM properties whose attributes the new node no longer has.
+    RemoveDomProperties(NodeIdx, Vec<&'a str>),
     /// The value attribute of a textarea or input element has not changed, but we will still patch
     /// it anyway in case something was typed into the field.
+    ///
+    /// Applied the same way as a [`Patch::SetDomProperties`] that sets the `value`.
+    #[deprecated(note = "the diff now sets the value using `Patch::SetDomProperties`")]
     ValueAttributeUnchanged(NodeIdx, &'a AttributeValue),
     /// Add attributes that the new node has that the old node does not
     AddAttributes(NodeIdx, HashMap<&'a str, &'a AttributeValue>),
     /// Remove attributes that the old node had that the new node doesn't
     RemoveAttributes(NodeIdx, Vec<&'a str>),
+    /// Set inline style properties that the new node has that the old node does not or that have
+    /// changed, in the order that the new node has them.
+    SetStyleProperties(NodeIdx, Vec<(&'a str, &'a str)>),
+    /// Remove inline style properties that the old node had that the new node doesn't
+    RemoveStyleProperties(NodeIdx, Vec<&'a str>),
     /// Change the text of a Text node.
     ChangeText(NodeIdx, &'a VText),
     /// Patches that apply to [`SpecialAttributes`].
@@ -92,6 +147,19 @@ pub enum Patch<'a> {
     /// Delete all events in the EventsByNodeIdx for the given index, since the node has been
     /// removed from the DOM.
     RemoveAllManagedEventsWithNodeIdx(NodeIdx),
+    /// Delete all events in the EventsByNodeIdx for every index from the first index to the last
+    /// index, inclusive.
+    ///
+    /// Used instead of a [`Patch::RemoveAllManagedEventsWithNodeIdx`] for each node when more than
+    /// one node in a removed subtree has events, since a removed subtree's nodes have consecutive
+    /// indices.
+    #[allow(missing_docs)]
+    RemoveAllManagedEventsInRange {
+        first_idx: NodeIdx,
+        last_idx: NodeIdx,
+    },
+    /// Remove the element that holds a removed portal's children from the portal's mount.
+    RemovePortal(NodeIdx),
 }
 
 /// Patches that apply to [`SpecialAttributes`].
@@ -101,6 +169,19 @@ pub enum PatchSpecialAttribute<'a> {
     CallOnCreateElem(NodeIdx, &'a VirtualNode),
     /// Call the [`SpecialAttributes.on_create_elem`] function on the node.
     CallOnRemoveElem(NodeIdx, &'a VirtualNode),
+    /// Call the [`SpecialAttributes.on_remove_element`] function of a node that is being removed
+    /// from its parent, leaving the node in the DOM until the function finishes removing it.
+    ///
+    /// See [`SpecialAttributes.set_on_remove_element_deferred`].
+    DeferRemoveElem(NodeIdx, &'a VirtualNode),
+    /// Call the new node's [`SpecialAttributes.on_update_element`] function with the old and new
+    /// nodes' attributes, after the element and its children were patched.
+    CallOnUpdateElem(NodeIdx, &'a VirtualNode, &'a VirtualNode),
+    /// Set the new node's [`NodeRef`](crate::NodeRef) to the element.
+    SetNodeRef(NodeIdx, &'a VirtualNode),
+    /// Clear the old node's [`NodeRef`](crate::NodeRef), since the element was removed or the node
+    /// no longer uses that `NodeRef`.
+    ClearNodeRef(NodeIdx, &'a VirtualNode),
     /// Set the node's innerHTML using the [`SpecialAttributes.dangerous_inner_html`].
     SetDangerousInnerHtml(NodeIdx, &'a VirtualNode),
     /// Set the node's innerHTML to an empty string.
@@ -115,27 +196,129 @@ impl<'a> Patch<'a> {
         match self {
             Patch::AppendChildren { old_idx, .. } => *old_idx,
             Patch::TruncateChildren(node_idx, _) => *node_idx,
+            Patch::InsertBefore { anchor_old_idx, .. } => *anchor_old_idx,
+            Patch::MoveNodesBefore { anchor_old_idx, .. } => *anchor_old_idx,
+            Patch::MoveToEndOfSiblings { parent_old_idx, .. } => *parent_old_idx,
+            Patch::RemoveChildren { parent_old_idx, .. } => *parent_old_idx,
             Patch::Replace { old_idx, .. } => *old_idx,
             Patch::AddAttributes(node_idx, _) => *node_idx,
             Patch::RemoveAttributes(node_idx, _) => *node_idx,
+            Patch::SetStyleProperties(node_idx, _) => *node_idx,
+            Patch::RemoveStyleProperties(node_idx, _) => *node_idx,
             Patch::ChangeText(node_idx, _) => *node_idx,
+            Patch::SetDomProperties(node_idx, _) => *node_idx,
+            Patch::RemoveDomProperties(node_idx, _) => *node_idx,
+            #[allow(deprecated)]
             Patch::ValueAttributeUnchanged(node_idx, _) => *node_idx,
             Patch::SpecialAttribute(special) => match special {
                 PatchSpecialAttribute::CallOnCreateElem(node_idx, _) => *node_idx,
                 PatchSpecialAttribute::SetDangerousInnerHtml(node_idx, _) => *node_idx,
                 PatchSpecialAttribute::RemoveDangerousInnerHtml(node_idx) => *node_idx,
                 PatchSpecialAttribute::CallOnRemoveElem(node_idx, _) => *node_idx,
+                PatchSpecialAttribute::DeferRemoveElem(node_idx, _) => *node_idx,
+                PatchSpecialAttribute::CallOnUpdateElem(node_idx, _, _) => *node_idx,
+                PatchSpecialAttribute::SetNodeRef(node_idx, _) => *node_idx,
+                PatchSpecialAttribute::ClearNodeRef(node_idx, _) => *node_idx,
             },
             Patch::RemoveEventsId(node_idx) => *node_idx,
             Patch::SetEventsId { old_idx, .. } => *old_idx,
             Patch::RemoveEvents(node_idx, _) => *node_idx,
             Patch::AddEvents(node_idx, _) => *node_idx,
-            Patch::RemoveAllManagedEventsWithNodeIdx(node_idx) => {
-                // TODO: We don't actually need the old node for this particular patch..
-                //  so we should stop making use of this. Perhaps use `unreachable!()` and fix
-                //  the places where we use this to stop using it.
-                *node_idx
-            }
+            Patch::RemoveAllManagedEventsWithNodeIdx(node_idx) => *node_idx,
+            Patch::RemoveAllManagedEventsInRange { first_idx, .. } => *first_idx,
+            Patch::RemovePortal(node_idx) => *node_idx,
         }
     }
+
+    /// The name of the patch's variant, such as `"Replace"`, used to describe a patch that could
+    /// not be applied.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Patch::AppendChildren { .. } => "AppendChildren",
+            Patch::TruncateChildren(..) => "TruncateChildren",
+            Patch::InsertBefore { .. } => "InsertBefore",
+            Patch::MoveNodesBefore { .. } => "MoveNodesBefore",
+            Patch::MoveToEndOfSiblings { .. } => "MoveToEndOfSiblings",
+            Patch::RemoveChildren { .. } => "RemoveChildren",
+            Patch::Replace { .. } => "Replace",
+            Patch::AddAttributes(..) => "AddAttributes",
+            Patch::RemoveAttributes(..) => "RemoveAttributes",
+            Patch::SetStyleProperties(..) => "SetStyleProperties",
+            Patch::RemoveStyleProperties(..) => "RemoveStyleProperties",
+            Patch::ChangeText(..) => "ChangeText",
+            Patch::SetDomProperties(..) => "SetDomProperties",
+            Patch::RemoveDomProperties(..) => "RemoveDomProperties",
+            #[allow(deprecated)]
+            Patch::ValueAttributeUnchanged(..) => "ValueAttributeUnchanged",
+            Patch::SpecialAttribute(special) => match special {
+                PatchSpecialAttribute::CallOnCreateElem(..) => "CallOnCreateElem",
+                PatchSpecialAttribute::SetDangerousInnerHtml(..) => "SetDangerousInnerHtml",
+                PatchSpecialAttribute::RemoveDangerousInnerHtml(..) => "RemoveDangerousInnerHtml",
+                PatchSpecialAttribute::CallOnRemoveElem(..) => "CallOnRemoveElem",
+                PatchSpecialAttribute::DeferRemoveElem(..) => "DeferRemoveElem",
+                PatchSpecialAttribute::CallOnUpdateElem(..) => "CallOnUpdateElem",
+                PatchSpecialAttribute::SetNodeRef(..) => "SetNodeRef",
+                PatchSpecialAttribute::ClearNodeRef(..) => "ClearNodeRef",
+            },
+            Patch::RemoveEventsId(..) => "RemoveEventsId",
+            Patch::SetEventsId { .. } => "SetEventsId",
+            Patch::RemoveEvents(..) => "RemoveEvents",
+            Patch::AddEvents(..) => "AddEvents",
+            Patch::RemoveAllManagedEventsWithNodeIdx(..) => "RemoveAllManagedEventsWithNodeIdx",
+            Patch::RemoveAllManagedEventsInRange { .. } => "RemoveAllManagedEventsInRange",
+            Patch::RemovePortal(..) => "RemovePortal",
+        }
+    }
+
+    /// The indices of any other old nodes, besides the [`Patch.old_node_idx`], that need to be
+    /// found in the DOM in order to apply this patch.
+    pub(crate) fn other_old_node_indices(&self) -> &[NodeIdx] {
+        match self {
+            Patch::MoveNodesBefore { to_move, .. } => to_move,
+            Patch::MoveToEndOfSiblings {
+                siblings_to_move, ..
+            } => siblings_to_move,
+            Patch::RemoveChildren { to_remove, .. } => to_remove,
+            _ => &[],
+        }
+    }
+
+    /// Whether or not this patch only updates the `EventsByNodeIdx` and the events related
+    /// properties of an existing DOM node.
+    pub(crate) fn is_events_patch(&self) -> bool {
+        matches!(
+            self,
+            Patch::AddEvents(..)
+                | Patch::RemoveEvents(..)
+                | Patch::SetEventsId { .. }
+                | Patch::RemoveAllManagedEventsWithNodeIdx(..)
+                | Patch::RemoveAllManagedEventsInRange { .. }
+        )
+    }
+
+    /// The indices whose events this patch removes from the `EventsByNodeIdx`, if it is a patch
+    /// that removes the events of removed nodes.
+    ///
+    /// These patches don't touch the DOM, so their nodes don't need to be found.
+    pub(crate) fn removed_events_node_indices(&self) -> Option<RangeInclusive<NodeIdx>> {
+        match self {
+            Patch::RemoveAllManagedEventsWithNodeIdx(node_idx) => Some(*node_idx..=*node_idx),
+            Patch::RemoveAllManagedEventsInRange {
+                first_idx,
+                last_idx,
+            } => Some(*first_idx..=*last_idx),
+            _ => None,
+        }
+    }
+
+    /// Whether or not this patch defers the removal of a node that is being removed.
+    ///
+    /// These get applied before any nodes get removed, so that the patches that remove nodes can
+    /// leave the deferred ones in place.
+    pub(crate) fn is_deferred_removal_patch(&self) -> bool {
+        matches!(
+            self,
+            Patch::SpecialAttribute(PatchSpecialAttribute::DeferRemoveElem(..))
+        )
+    }
 }
@@ -1,80 +1,230 @@
-use std::cmp::min;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::rc::Rc;
 
 use virtual_node::event::insert_non_delegated_event;
 use wasm_bindgen::JsCast;
 use wasm_bindgen::JsValue;
-use web_sys::{Element, HtmlInputElement, HtmlTextAreaElement, Node, Text};
+use web_sys::{CharacterData, Comment, Element, Node};
 
-use crate::event::{EventsByNodeIdx, ManagedEvent, EVENTS_ID_PROP};
-use crate::patch::Patch;
-use crate::{AttributeValue, PatchSpecialAttribute, VirtualNode};
+use crate::event::{EventName, EventsByNodeIdx, ManagedEvent, EVENTS_ID_PROP};
+use crate::patch::owned_patch::Materialized;
+use crate::patch::{OwnedPatches, Patch, PatchBindings, PatchError, PatchErrorKind};
+use crate::{
+    cancel_deferred_removal, is_removal_deferred, portal_container, portal_placeholder,
+    remove_dom_property, remove_element_attribute, remove_style_property, set_dom_property,
+    set_element_attribute, set_style_property, Namespace, PatchSpecialAttribute, VirtualNode,
+};
 
 /// Apply all of the patches to our old root node in order to create the new root node
 /// that we desire. Also, update the `EventsByNodeIdx` with the new virtual node's event callbacks.
 ///
 /// This is usually used after diffing two virtual nodes.
+///
+/// Returns an error describing the first patch that could not be applied, in which case the
+/// patches before it have already been applied.
 pub fn patch<N: Into<Node>>(
     root_node: N,
     new_vnode: &VirtualNode,
     managed_events: &mut EventsByNodeIdx,
     patches: &[Patch],
-) -> Result<(), JsValue> {
-    let root_node: Node = root_node.into();
+) -> Result<(), PatchError> {
+    apply_patches(root_node.into(), managed_events, patches)?;
+
+    overwrite_events(new_vnode, &mut 0, managed_events);
+
+    Ok(())
+}
 
+/// Apply owned patches, typically created on another thread or on the server, to our old root node.
+///
+/// Event handlers and special attribute functions get bound using the `PatchBindings`, see
+/// [`OwnedPatches`].
+pub fn patch_owned<N: Into<Node>>(
+    root_node: N,
+    patches: &OwnedPatches,
+    managed_events: &mut EventsByNodeIdx,
+    bindings: &Rc<dyn PatchBindings>,
+) -> Result<(), PatchError> {
+    let materialized: Vec<Materialized> = patches
+        .patches
+        .iter()
+        .map(|patch| Materialized::new(patch, bindings))
+        .collect();
+    let borrowed: Vec<Patch> = patches
+        .patches
+        .iter()
+        .zip(materialized.iter())
+        .map(|(patch, materialized)| materialized.patch(patch))
+        .collect();
+
+    apply_patches(root_node.into(), managed_events, &borrowed)?;
+
+    for (node_idx, event_names) in patches.events.iter() {
+        for event_name in event_names {
+            let event_name = EventName::from(event_name.clone());
+
+            if let Some(handler) = bindings.event_handler(*node_idx, &event_name) {
+                managed_events.overwrite_event_attrib_fn(*node_idx, &event_name, handler);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn apply_patches(
+    root_node: Node,
+    managed_events: &mut EventsByNodeIdx,
+    patches: &[Patch],
+) -> Result<(), PatchError> {
     let mut cur_node_idx = 0;
 
     let mut nodes_to_find = HashSet::new();
 
     for patch in patches {
+        if patch.removed_events_node_indices().is_some() {
+            continue;
+        }
+
         nodes_to_find.insert(patch.old_node_idx());
+
+        for other_idx in patch.other_old_node_indices() {
+            nodes_to_find.insert(*other_idx);
+        }
     }
 
     let mut element_nodes_to_patch = HashMap::new();
-    let mut text_nodes_to_patch = HashMap::new();
+    let mut character_data_nodes_to_patch = HashMap::new();
 
-    find_nodes(
+    let found = find_nodes(
         root_node,
         &mut cur_node_idx,
         &mut nodes_to_find,
         &mut element_nodes_to_patch,
-        &mut text_nodes_to_patch,
+        &mut character_data_nodes_to_patch,
     );
-
-    for patch in patches {
-        let patch_node_idx = patch.old_node_idx();
-
-        if let Some(element) = element_nodes_to_patch.get(&patch_node_idx) {
-            apply_element_patch(&element, &patch, managed_events)?;
-            continue;
+    if let Err(kind) = found {
+        // The search stopped at the node with the current index, so we blame the patch that
+        // needed that node, or else one of the patches whose node we did not get to.
+        let patch = patches
+            .iter()
+            .find(|patch| needs_node(patch, cur_node_idx))
+            .or_else(|| {
+                patches.iter().find(|patch| {
+                    nodes_to_find
+                        .iter()
+                        .any(|node_idx| needs_node(patch, *node_idx))
+                })
+            });
+
+        if let Some(patch) = patch {
+            return Err(PatchError::new(patch, kind));
         }
+    }
 
-        if let Some(text_node) = text_nodes_to_patch.get(&patch_node_idx) {
-            apply_text_patch(&text_node, &patch, managed_events)?;
+    // Events are tracked by node index, so we update the events of the old nodes before we
+    // create any new nodes. Otherwise a newly created node's events could get overwritten or
+    // moved if it happened to have the same index as one of the old nodes.
+    let mut events_to_move = vec![];
+    for patch in patches.iter().filter(|p| p.is_events_patch()) {
+        // The events of removed nodes are removed by index, without needing their DOM nodes.
+        if let Some(removed) = patch.removed_events_node_indices() {
+            for node_idx in removed {
+                managed_events.remove_node(&node_idx);
+            }
             continue;
         }
 
-        unreachable!(
-            "We didn't find the element or next node that we were supposed to patch ({}).",
-            patch_node_idx
+        element_to_patch(
+            patch,
+            &element_nodes_to_patch,
+            &character_data_nodes_to_patch,
         )
+        .and_then(|element| apply_events_patch(element, patch, managed_events, &mut events_to_move))
+        .map_err(|kind| PatchError::new(patch, kind))?;
+    }
+    managed_events.move_events_batch(&events_to_move);
+
+    // Nodes that defer their removal get marked before any nodes get removed, so that the patches
+    // that remove nodes leave them in place.
+    for patch in patches.iter().filter(|p| p.is_deferred_removal_patch()) {
+        element_to_patch(
+            patch,
+            &element_nodes_to_patch,
+            &character_data_nodes_to_patch,
+        )
+        .and_then(|element| {
+            apply_element_patch(element, patch, managed_events, &element_nodes_to_patch)
+        })
+        .map_err(|kind| PatchError::new(patch, kind))?;
     }
 
-    overwrite_events(new_vnode, &mut 0, managed_events);
+    for patch in patches
+        .iter()
+        .filter(|p| !p.is_events_patch() && !p.is_deferred_removal_patch())
+    {
+        let patch_node_idx = patch.old_node_idx();
+
+        let applied = if let Some(element) = element_nodes_to_patch.get(&patch_node_idx) {
+            apply_element_patch(element, patch, managed_events, &element_nodes_to_patch)
+        } else if let Some(character_data) = character_data_nodes_to_patch.get(&patch_node_idx) {
+            apply_character_data_patch(character_data, patch, managed_events)
+        } else {
+            Err(PatchErrorKind::NodeNotFound(patch_node_idx))
+        };
+
+        applied.map_err(|kind| PatchError::new(patch, kind))?;
+    }
 
     Ok(())
 }
 
+/// Whether or not the patch needs the DOM node with the index in order to be applied.
+fn needs_node(patch: &Patch, node_idx: u32) -> bool {
+    patch.removed_events_node_indices().is_none()
+        && (patch.old_node_idx() == node_idx || patch.other_old_node_indices().contains(&node_idx))
+}
+
+/// The element that a patch that only applies to elements should be applied to.
+fn element_to_patch<'a>(
+    patch: &Patch,
+    element_nodes_to_patch: &'a HashMap<u32, Element>,
+    character_data_nodes_to_patch: &HashMap<u32, CharacterData>,
+) -> Result<&'a Element, PatchErrorKind> {
+    let patch_node_idx = patch.old_node_idx();
+
+    match element_nodes_to_patch.get(&patch_node_idx) {
+        Some(element) => Ok(element),
+        None if character_data_nodes_to_patch.contains_key(&patch_node_idx) => {
+            Err(PatchErrorKind::UnexpectedNodeType)
+        }
+        None => Err(PatchErrorKind::NodeNotFound(patch_node_idx)),
+    }
+}
+
 fn find_nodes(
     root_node: Node,
     cur_node_idx: &mut u32,
     nodes_to_find: &mut HashSet<u32>,
     element_nodes_to_patch: &mut HashMap<u32, Element>,
-    text_nodes_to_patch: &mut HashMap<u32, Text>,
-) {
+    character_data_nodes_to_patch: &mut HashMap<u32, CharacterData>,
+) -> Result<(), PatchErrorKind> {
     if nodes_to_find.len() == 0 {
-        return;
+        return Ok(());
+    }
+
+    // A portal's children live in the element that holds them, which takes the place of the
+    // portal's placeholder comment.
+    if let Some(container) = portal_container(&root_node) {
+        find_nodes(
+            container.into(),
+            cur_node_idx,
+            nodes_to_find,
+            element_nodes_to_patch,
+            character_data_nodes_to_patch,
+        )?;
+        return Ok(());
     }
 
     // We use child_nodes() instead of children() because children() ignores text nodes
@@ -87,10 +237,10 @@ fn find_nodes(
             Node::ELEMENT_NODE => {
                 element_nodes_to_patch.insert(*cur_node_idx, root_node.unchecked_into());
             }
-            Node::TEXT_NODE => {
-                text_nodes_to_patch.insert(*cur_node_idx, root_node.unchecked_into());
+            Node::TEXT_NODE | Node::COMMENT_NODE => {
+                character_data_nodes_to_patch.insert(*cur_node_idx, root_node.unchecked_into());
             }
-            other => unimplemented!("Unsupported root node type: {}", other),
+            _ => return Err(PatchErrorKind::UnexpectedNodeType),
         }
         nodes_to_find.remove(&cur_node_idx);
     }
@@ -98,50 +248,77 @@ fn find_nodes(
     *cur_node_idx += 1;
 
     for i in 0..child_node_count {
-        let node = children.item(i).unwrap();
+        // The children can only be missing if something changed the DOM while we were looking.
+        let node = children
+            .item(i)
+            .ok_or(PatchErrorKind::NodeNotFound(*cur_node_idx))?;
 
         match node.node_type() {
+            Node::ELEMENT_NODE if portal_placeholder(&node).is_some() => {
+                // A portal's children get found through its placeholder comment, since their
+                // container can be mounted anywhere, even inside of this node.
+            }
+            Node::ELEMENT_NODE if is_removal_deferred(&node) => {
+                // This element was removed from the virtual dom, but its on remove element function
+                // is still finishing its removal, so it does not have a node index.
+            }
             Node::ELEMENT_NODE => {
                 find_nodes(
                     node,
                     cur_node_idx,
                     nodes_to_find,
                     element_nodes_to_patch,
-                    text_nodes_to_patch,
-                );
+                    character_data_nodes_to_patch,
+                )?;
+            }
+            Node::COMMENT_NODE if is_text_separator(&node) => {
+                // This comment was created by percy-dom in order to ensure that two neighboring
+                // text nodes did not get merged into one by the browser, so it does not have a
+                // node index.
+            }
+            Node::COMMENT_NODE if portal_container(&node).is_some() => {
+                find_nodes(
+                    node,
+                    cur_node_idx,
+                    nodes_to_find,
+                    element_nodes_to_patch,
+                    character_data_nodes_to_patch,
+                )?;
             }
-            Node::TEXT_NODE => {
+            Node::TEXT_NODE | Node::COMMENT_NODE => {
                 if nodes_to_find.get(&cur_node_idx).is_some() {
-                    text_nodes_to_patch.insert(*cur_node_idx, node.unchecked_into());
+                    character_data_nodes_to_patch.insert(*cur_node_idx, node.unchecked_into());
                 }
 
                 *cur_node_idx += 1;
             }
-            Node::COMMENT_NODE => {
-                // At this time we do not support user entered comment nodes, so if we see a comment
-                // then it was a delimiter created by percy-dom in order to ensure that two
-                // neighboring text nodes did not get merged into one by the browser. So we skip
-                // over this percy-dom generated comment node.
-            }
             _other => {
                 // Ignoring unsupported child node type
                 // TODO: What do we do with this situation?
             }
         }
     }
+
+    Ok(())
 }
 
 fn overwrite_events(node: &VirtualNode, node_idx: &mut u32, managed_events: &mut EventsByNodeIdx) {
-    if let Some(elem) = node.as_velement_ref() {
-        for (event_name, event) in elem.events.iter() {
-            managed_events.overwrite_event_attrib_fn(*node_idx, event_name, event.clone());
+    let children = match node.rendered_node() {
+        VirtualNode::Element(elem) => {
+            for (event_name, event) in elem.events.iter() {
+                managed_events.overwrite_event_attrib_fn(*node_idx, event_name, event.clone());
+            }
+
+            elem.flat_children()
         }
+        VirtualNode::Portal(portal) => portal.flat_children(),
+        _ => return,
+    };
 
-        for child in elem.children.iter() {
-            *node_idx += 1;
+    for child in children {
+        *node_idx += 1;
 
-            overwrite_events(child, node_idx, managed_events);
-        }
+        overwrite_events(child, node_idx, managed_events);
     }
 }
 
@@ -149,33 +326,19 @@ fn apply_element_patch(
     node: &Element,
     patch: &Patch,
     managed_events: &mut EventsByNodeIdx,
-) -> Result<(), JsValue> {
+    elements: &HashMap<u32, Element>,
+) -> Result<(), PatchErrorKind> {
     match patch {
         Patch::AddAttributes(_node_idx, attributes) => {
             for (attrib_name, attrib_val) in attributes.iter() {
-                match attrib_val {
-                    AttributeValue::String(val_str) => {
-                        node.set_attribute(attrib_name, val_str)?;
-
-                        if attrib_name == &"value" {
-                            maybe_set_value_property(node, val_str)
-                        }
-                    }
-                    AttributeValue::Bool(val_bool) => {
-                        if *val_bool {
-                            node.set_attribute(attrib_name, "")?;
-                        } else {
-                            node.remove_attribute(attrib_name)?;
-                        }
-                    }
-                }
+                set_element_attribute(node, attrib_name, attrib_val)?;
             }
 
             Ok(())
         }
         Patch::RemoveAttributes(_node_idx, attributes) => {
             for attrib_name in attributes.iter() {
-                node.remove_attribute(attrib_name)?;
+                remove_element_attribute(node, attrib_name)?;
             }
 
             Ok(())
@@ -185,40 +348,65 @@ fn apply_element_patch(
             new_idx,
             new_node,
         } => {
-            let created_node = new_node.create_dom_node(*new_idx, managed_events);
+            // A replaced portal leaves its placeholder comment's spot and its mount.
+            if let Some(placeholder) = portal_placeholder(node) {
+                let created_node = new_node.try_create_dom_node_in(
+                    *new_idx,
+                    managed_events,
+                    parent_namespace(&placeholder),
+                )?;
+
+                placeholder.replace_with_with_node_1(&created_node)?;
+                remove_portal_container(node, managed_events);
+
+                return Ok(());
+            }
 
-            node.replace_with_with_node_1(&created_node)?;
+            let created_node = new_node.try_create_dom_node_in(
+                *new_idx,
+                managed_events,
+                parent_namespace(node),
+            )?;
+
+            if is_removal_deferred(node) {
+                node.before_with_node_1(&created_node)?;
+            } else {
+                node.replace_with_with_node_1(&created_node)?;
+            }
+            cancel_deferred_removal_of_sibling(&created_node)?;
+
+            Ok(())
+        }
+        Patch::RemovePortal(_node_idx) => {
+            remove_portal_container(node, managed_events);
 
             Ok(())
         }
         Patch::TruncateChildren(_node_idx, num_children_remaining) => {
+            // Collected up front since removing a child shifts the live `NodeList`.
             let children = node.child_nodes();
-            let mut child_count = children.length();
+            let children: Vec<Node> = (0..children.length())
+                .filter_map(|index| children.get(index))
+                .collect();
 
             // We skip over any separators that we placed between two text nodes
             //   -> `<!--ptns-->`
-            //  and trim all children that come after our new desired `num_children_remaining`
+            //  and any elements whose removal is deferred, and trim all children that come after
+            //  our new desired `num_children_remaining`
             let mut non_separator_children_found = 0;
 
-            for index in 0 as u32..child_count {
-                let child = children
-                    .get(min(index, child_count - 1))
-                    .expect("Potential child to truncate");
-
-                // If this is a comment node then we know that it is a `<!--ptns-->`
-                // text node separator that was created in virtual_node/mod.rs.
-                if child.node_type() == Node::COMMENT_NODE {
+            for child in children {
+                if is_text_separator(&child) || is_removal_deferred(&child) {
                     continue;
                 }
 
                 non_separator_children_found += 1;
 
-                if non_separator_children_found <= *num_children_remaining as u32 {
+                if non_separator_children_found <= *num_children_remaining {
                     continue;
                 }
 
-                node.remove_child(&child).expect("Truncated children");
-                child_count -= 1;
+                node.remove_child(&child)?;
             }
 
             Ok(())
@@ -228,21 +416,100 @@ fn apply_element_patch(
             new_nodes,
         } => {
             let parent = &node;
+            let namespace = Namespace::for_children_of(parent);
 
             for (new_idx, new_node) in new_nodes {
-                let created_node = new_node.create_dom_node(*new_idx, managed_events);
+                let created_node =
+                    new_node.try_create_dom_node_in(*new_idx, managed_events, namespace)?;
 
                 parent.append_child(&created_node)?;
+                cancel_deferred_removal_of_sibling(&created_node)?;
+            }
+
+            Ok(())
+        }
+        Patch::InsertBefore {
+            anchor_old_idx: _,
+            new_nodes,
+        } => {
+            let namespace = parent_namespace(node);
+
+            for (new_idx, new_node) in new_nodes {
+                let created_node =
+                    new_node.try_create_dom_node_in(*new_idx, managed_events, namespace)?;
+
+                node.before_with_node_1(&created_node)?;
+                cancel_deferred_removal_of_sibling(&created_node)?;
+            }
+
+            Ok(())
+        }
+        Patch::MoveNodesBefore {
+            anchor_old_idx: _,
+            to_move,
+        } => {
+            for old_idx in to_move {
+                node.before_with_node_1(found_element(elements, *old_idx)?)?;
+            }
+
+            Ok(())
+        }
+        Patch::MoveToEndOfSiblings {
+            parent_old_idx: _,
+            siblings_to_move,
+        } => {
+            for old_idx in siblings_to_move {
+                node.append_child(found_element(elements, *old_idx)?)?;
             }
 
             Ok(())
         }
-        Patch::ChangeText(_node_idx, _new_node) => {
-            unreachable!("Elements should not receive ChangeText patches.")
+        Patch::RemoveChildren {
+            parent_old_idx: _,
+            to_remove,
+        } => {
+            for old_idx in to_remove {
+                let child = found_element(elements, *old_idx)?;
+
+                if !is_removal_deferred(child) {
+                    node.remove_child(child)?;
+                }
+            }
+
+            Ok(())
         }
+        Patch::ChangeText(_node_idx, _new_node) => Err(PatchErrorKind::UnexpectedNodeType),
+        Patch::SetStyleProperties(_node_idx, styles) => {
+            for (name, value) in styles.iter() {
+                set_style_property(node, name, value)?;
+            }
+
+            Ok(())
+        }
+        Patch::RemoveStyleProperties(_node_idx, styles) => {
+            for name in styles.iter() {
+                remove_style_property(node, name)?;
+            }
+
+            Ok(())
+        }
+        Patch::SetDomProperties(_node_idx, properties) => {
+            for (name, value) in properties.iter() {
+                set_dom_property(node, name, value, managed_events)?;
+            }
+
+            Ok(())
+        }
+        Patch::RemoveDomProperties(_node_idx, properties) => {
+            for name in properties.iter() {
+                remove_dom_property(node, name)?;
+            }
+
+            Ok(())
+        }
+        #[allow(deprecated)]
         Patch::ValueAttributeUnchanged(_node_idx, value) => {
-            node.set_attribute("value", value.as_string().unwrap())?;
-            maybe_set_value_property(node, value.as_string().unwrap());
+            set_dom_property(node, "value", value, managed_events)?;
 
             Ok(())
         }
@@ -265,6 +532,46 @@ fn apply_element_patch(
 
                 Ok(())
             }
+            PatchSpecialAttribute::DeferRemoveElem(node_idx, old_node) => {
+                forget_events_ids(*node_idx, node)?;
+
+                old_node
+                    .as_velement_ref()
+                    .unwrap()
+                    .special_attributes
+                    .defer_element_removal(node)?;
+
+                Ok(())
+            }
+            PatchSpecialAttribute::CallOnUpdateElem(_node_idx, old_node, new_node) => {
+                let new_element = new_node.as_velement_ref().unwrap();
+
+                new_element.special_attributes.maybe_call_on_update_element(
+                    node,
+                    &old_node.as_velement_ref().unwrap().attrs,
+                    &new_element.attrs,
+                );
+
+                Ok(())
+            }
+            PatchSpecialAttribute::SetNodeRef(_node_idx, new_node) => {
+                new_node
+                    .as_velement_ref()
+                    .unwrap()
+                    .special_attributes
+                    .maybe_set_node_ref(node);
+
+                Ok(())
+            }
+            PatchSpecialAttribute::ClearNodeRef(_node_idx, old_node) => {
+                old_node
+                    .as_velement_ref()
+                    .unwrap()
+                    .special_attributes
+                    .maybe_clear_node_ref(node);
+
+                Ok(())
+            }
             PatchSpecialAttribute::SetDangerousInnerHtml(_node_idx, new_node) => {
                 let new_inner_html = new_node
                     .as_velement_ref()
@@ -285,22 +592,38 @@ fn apply_element_patch(
             }
         },
         Patch::RemoveEventsId(_) => {
-            js_sys::Reflect::set(node, &EVENTS_ID_PROP.into(), &JsValue::UNDEFINED).unwrap();
+            js_sys::Reflect::set(node, &EVENTS_ID_PROP.into(), &JsValue::UNDEFINED)?;
 
             Ok(())
         }
+        Patch::AddEvents(..)
+        | Patch::RemoveEvents(..)
+        | Patch::SetEventsId { .. }
+        | Patch::RemoveAllManagedEventsWithNodeIdx(..)
+        | Patch::RemoveAllManagedEventsInRange { .. } => {
+            unreachable!("Events patches are applied by apply_events_patch.")
+        }
+    }
+}
+
+fn apply_events_patch(
+    node: &Element,
+    patch: &Patch,
+    managed_events: &mut EventsByNodeIdx,
+    events_to_move: &mut Vec<(u32, u32)>,
+) -> Result<(), PatchErrorKind> {
+    match patch {
         Patch::SetEventsId { old_idx, new_idx } => {
             js_sys::Reflect::set(
                 node,
                 &EVENTS_ID_PROP.into(),
                 &format!("{}{}", managed_events.events_id_props_prefix(), new_idx).into(),
-            )
-            .unwrap();
+            )?;
 
             let move_over_old_events = old_idx != new_idx;
 
             if move_over_old_events {
-                managed_events.move_events(old_idx, *new_idx);
+                events_to_move.push((*old_idx, *new_idx));
             }
 
             Ok(())
@@ -329,8 +652,7 @@ fn apply_element_patch(
                             node.remove_event_listener_with_callback(
                                 event_name.without_on_prefix(),
                                 wrapper.as_ref().as_ref().unchecked_ref(),
-                            )
-                            .unwrap();
+                            )?;
                         }
                         _ => unreachable!(),
                     };
@@ -341,18 +663,16 @@ fn apply_element_patch(
 
             Ok(())
         }
-        Patch::RemoveAllManagedEventsWithNodeIdx(node_idx) => {
-            managed_events.remove_node(node_idx);
-            Ok(())
-        }
+        other => unreachable!("Not an events patch {:?}.", other),
     }
 }
 
-fn apply_text_patch(
-    node: &Text,
+/// Text nodes and comment nodes are both `CharacterData`.
+fn apply_character_data_patch(
+    node: &CharacterData,
     patch: &Patch,
     events: &mut EventsByNodeIdx,
-) -> Result<(), JsValue> {
+) -> Result<(), PatchErrorKind> {
     match patch {
         Patch::ChangeText(_node_idx, new_node) => {
             node.set_node_value(Some(&new_node.text));
@@ -362,22 +682,107 @@ fn apply_text_patch(
             new_idx,
             new_node,
         } => {
-            node.replace_with_with_node_1(&new_node.create_dom_node(*new_idx, events))?;
+            let created_node =
+                new_node.try_create_dom_node_in(*new_idx, events, parent_namespace(node))?;
+
+            node.replace_with_with_node_1(&created_node)?;
+            cancel_deferred_removal_of_sibling(&created_node)?;
         }
-        other => unreachable!(
-            "Text nodes should only receive ChangeText or Replace patches, not {:?}.",
-            other,
-        ),
+        // Text and comment nodes only receive ChangeText or Replace patches, so an element
+        // patch means that the element was replaced by something other than percy-dom.
+        _ => return Err(PatchErrorKind::UnexpectedNodeType),
     };
 
     Ok(())
 }
 
-// See crates/percy-dom/tests/value_attribute.rs
-fn maybe_set_value_property(node: &Element, value: &str) {
-    if let Some(input_node) = node.dyn_ref::<HtmlInputElement>() {
-        input_node.set_value(value);
-    } else if let Some(textarea_node) = node.dyn_ref::<HtmlTextAreaElement>() {
-        textarea_node.set_value(value)
+/// One of the other old elements that a patch needs, such as an element that it moves.
+fn found_element(
+    elements: &HashMap<u32, Element>,
+    node_idx: u32,
+) -> Result<&Element, PatchErrorKind> {
+    elements
+        .get(&node_idx)
+        .ok_or(PatchErrorKind::NodeNotFound(node_idx))
+}
+
+/// An element that gets created next to an element whose removal is deferred, and that has the same
+/// tag and `key` attribute, is the removed node being added back before its removal finished. So
+/// it takes the removed element's place right away.
+fn cancel_deferred_removal_of_sibling(created_node: &Node) -> Result<(), JsValue> {
+    let created: &Element = match created_node.dyn_ref() {
+        Some(created) => created,
+        None => return Ok(()),
+    };
+    // Elements without a key can't be told apart from a new element with the same tag.
+    let key = match created.get_attribute("key") {
+        Some(key) => key,
+        None => return Ok(()),
+    };
+
+    let removed = deferred_removal_siblings(created_node, Node::next_sibling)
+        .chain(deferred_removal_siblings(
+            created_node,
+            Node::previous_sibling,
+        ))
+        .find(|removed| {
+            removed.tag_name() == created.tag_name()
+                && removed.get_attribute("key").as_deref() == Some(key.as_str())
+        });
+
+    if let Some(removed) = removed {
+        cancel_deferred_removal(&removed)?;
+        removed.replace_with_with_node_1(created_node)?;
+    }
+
+    Ok(())
+}
+
+/// The run of elements whose removal is deferred that directly follow, or precede, the node.
+fn deferred_removal_siblings(
+    node: &Node,
+    next: fn(&Node) -> Option<Node>,
+) -> impl Iterator<Item = Element> {
+    std::iter::successors(next(node), next)
+        .take_while(is_removal_deferred)
+        .map(|sibling| sibling.unchecked_into())
+}
+
+/// Remove the node indices from an element whose removal was deferred, along with its descendants,
+/// since other nodes now have those indices.
+fn forget_events_ids(node_idx: u32, element: &Element) -> Result<(), PatchErrorKind> {
+    js_sys::Reflect::set(element, &EVENTS_ID_PROP.into(), &JsValue::UNDEFINED)?;
+
+    let descendants = element.query_selector_all("*")?;
+    for i in 0..descendants.length() {
+        let descendant = descendants
+            .item(i)
+            .ok_or(PatchErrorKind::NodeNotFound(node_idx))?;
+        js_sys::Reflect::set(&descendant, &EVENTS_ID_PROP.into(), &JsValue::UNDEFINED)?;
     }
+
+    Ok(())
+}
+
+/// Remove the element that holds a portal's children from the portal's mount.
+fn remove_portal_container(container: &Element, managed_events: &EventsByNodeIdx) {
+    container.remove();
+    managed_events.remove_portal_container(container);
+}
+
+/// The namespace that a node's siblings get created in.
+fn parent_namespace(node: &Node) -> Namespace {
+    node.parent_node()
+        .as_ref()
+        .map(Namespace::for_children_of)
+        .unwrap_or_default()
+}
+
+/// Whether or not the node is a `<!--ptns-->` comment that separates two neighboring text nodes.
+///
+/// See `append_children_to_dom` in virtual-node's create_element.rs.
+fn is_text_separator(node: &Node) -> bool {
+    node.dyn_ref::<Comment>()
+        .map(|comment| comment.data() == "ptns")
+        .unwrap_or(false)
 }
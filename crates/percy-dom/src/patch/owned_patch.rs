@@ -0,0 +1,1112 @@
+//! Patches that own their data, so that they can be sent to another thread or serialized.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use wasm_bindgen::JsValue;
+
+use crate::event::{EventHandler, EventName};
+use crate::{
+    AttributeValue, DeferredRemoval, EventAttribFn, NodeRef, Patch, PatchSpecialAttribute,
+};
+use crate::{SpecialAttributes, VComment, VElement, VFragment, VPortal, VText, VirtualNode};
+
+use super::NodeIdx;
+
+/// The patches that turn one virtual dom's DOM into another's, along with the events of the new
+/// virtual dom.
+///
+/// A [`Patch`] borrows from the virtual doms that were diffed, so it has to be applied on the same
+/// thread. `OwnedPatches` own all of their data, so you can diff in a Web Worker or on the server
+/// and send only the patches to the browser. With the `serde` feature they can be serialized.
+///
+/// # Events and special attributes
+///
+/// Event handlers and the `on_create_element`, `on_remove_element` and `on_update_element`
+/// functions are closures that cannot be sent between threads. So only their names and keys are
+/// kept, and they get bound on the thread that applies the patches using a [`PatchBindings`], by
+/// node index. The same goes for [`NodeRef`]s, of which only whether or not an element has one is
+/// kept.
+///
+/// After patching, every event in the new virtual dom gets re-bound, so that the handlers capture
+/// the latest state of your application just like they would when patching with a [`Patch`].
+///
+/// `dangerous_inner_html` is plain data, so it is kept as is.
+///
+/// # Example
+///
+/// ```no_run
+/// # use percy_dom::{prelude::*, OwnedPatches, PatchBindings, patch_owned};
+/// # use percy_dom::event::{EventHandler, EventName, EventsByNodeIdx};
+/// # use std::rc::Rc;
+/// // On the thread that diffs.
+/// let old = html! { <div></div> };
+/// let new = html! { <div> <button onclick=|| {}>Click</button> </div> };
+/// let patches = OwnedPatches::diff(&old, &new);
+///
+/// // On the main thread.
+/// struct Bindings;
+///
+/// impl PatchBindings for Bindings {
+///     fn event_handler(&self, node_idx: u32, event: &EventName) -> Option<EventHandler> {
+///         // Look up the handler for the node, for example by rendering the same view.
+///         None
+///     }
+/// }
+///
+/// # let root_node: web_sys::Node = unimplemented!();
+/// # let mut events: EventsByNodeIdx = unimplemented!();
+/// let bindings: Rc<dyn PatchBindings> = Rc::new(Bindings);
+/// patch_owned(root_node, &patches, &mut events, &bindings).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedPatches {
+    /// The patches, in the order that they need to be applied.
+    pub patches: Vec<OwnedPatch>,
+    /// The names of the events of every element in the new virtual dom that has events, by the
+    /// element's node index.
+    pub events: Vec<(NodeIdx, Vec<String>)>,
+}
+
+/// An owned version of a [`Patch`].
+///
+/// Events are kept by name and special attribute functions by key, see [`OwnedPatches`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
+pub enum OwnedPatch {
+    /// See [`Patch::AppendChildren`].
+    AppendChildren {
+        old_idx: NodeIdx,
+        new_nodes: Vec<(NodeIdx, OwnedNode)>,
+    },
+    /// See [`Patch::TruncateChildren`].
+    TruncateChildren(NodeIdx, usize),
+    /// See [`Patch::InsertBefore`].
+    InsertBefore {
+        anchor_old_idx: NodeIdx,
+        new_nodes: Vec<(NodeIdx, OwnedNode)>,
+    },
+    /// See [`Patch::MoveNodesBefore`].
+    MoveNodesBefore {
+        anchor_old_idx: NodeIdx,
+        to_move: Vec<NodeIdx>,
+    },
+    /// See [`Patch::MoveToEndOfSiblings`].
+    MoveToEndOfSiblings {
+        parent_old_idx: NodeIdx,
+        siblings_to_move: Vec<NodeIdx>,
+    },
+    /// See [`Patch::RemoveChildren`].
+    RemoveChildren {
+        parent_old_idx: NodeIdx,
+        to_remove: Vec<NodeIdx>,
+    },
+    /// See [`Patch::Replace`].
+    Replace {
+        old_idx: NodeIdx,
+        new_idx: NodeIdx,
+        new_node: OwnedNode,
+    },
+    /// See [`Patch::SetDomProperties`].
+    SetDomProperties(NodeIdx, Vec<(String, AttributeValue)>),
+    /// See [`Patch::RemoveDomProperties`].
+    RemoveDomProperties(NodeIdx, Vec<String>),
+    /// See [`Patch::ValueAttributeUnchanged`].
+    #[deprecated(note = "the diff now sets the value using `OwnedPatch::SetDomProperties`")]
+    ValueAttributeUnchanged(NodeIdx, AttributeValue),
+    /// See [`Patch::AddAttributes`].
+    AddAttributes(NodeIdx, Vec<(String, AttributeValue)>),
+    /// See [`Patch::RemoveAttributes`].
+    RemoveAttributes(NodeIdx, Vec<String>),
+    /// See [`Patch::SetStyleProperties`].
+    SetStyleProperties(NodeIdx, Vec<(String, String)>),
+    /// See [`Patch::RemoveStyleProperties`].
+    RemoveStyleProperties(NodeIdx, Vec<String>),
+    /// See [`Patch::ChangeText`].
+    ChangeText(NodeIdx, String),
+    /// See [`Patch::SpecialAttribute`].
+    SpecialAttribute(OwnedPatchSpecialAttribute),
+    /// See [`Patch::RemoveEventsId`].
+    RemoveEventsId(NodeIdx),
+    /// See [`Patch::SetEventsId`].
+    SetEventsId { old_idx: NodeIdx, new_idx: NodeIdx },
+    /// See [`Patch::AddEvents`]. Holds the names of the events.
+    AddEvents(NodeIdx, Vec<String>),
+    /// See [`Patch::RemoveEvents`]. Holds the names of the events.
+    RemoveEvents(NodeIdx, Vec<String>),
+    /// See [`Patch::RemoveAllManagedEventsWithNodeIdx`].
+    RemoveAllManagedEventsWithNodeIdx(NodeIdx),
+    /// See [`Patch::RemoveAllManagedEventsInRange`].
+    #[allow(missing_docs)]
+    RemoveAllManagedEventsInRange {
+        first_idx: NodeIdx,
+        last_idx: NodeIdx,
+    },
+    /// See [`Patch::RemovePortal`].
+    RemovePortal(NodeIdx),
+}
+
+/// An owned version of a [`PatchSpecialAttribute`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedPatchSpecialAttribute {
+    /// Call [`PatchBindings::on_create_element`] with the node and the function's key.
+    CallOnCreateElem(NodeIdx, String),
+    /// Call [`PatchBindings::on_remove_element`] with the node and the function's key.
+    CallOnRemoveElem(NodeIdx, String),
+    /// Call [`PatchBindings::on_remove_element_deferred`] with the node and the function's key.
+    DeferRemoveElem(NodeIdx, String),
+    /// Call [`PatchBindings::on_update_element`] with the node, the function's key and the old and
+    /// new attributes.
+    CallOnUpdateElem {
+        /// The index of the node in the old virtual dom.
+        node_idx: NodeIdx,
+        /// The on update element function's key.
+        key: String,
+        /// The old node's attributes, sorted by name.
+        old_attrs: Vec<(String, AttributeValue)>,
+        /// The new node's attributes, sorted by name.
+        new_attrs: Vec<(String, AttributeValue)>,
+    },
+    /// Set the [`PatchBindings::node_ref`] of the node to the element.
+    SetNodeRef(NodeIdx),
+    /// Clear the [`PatchBindings::node_ref`] of the node, if it still holds the element.
+    ClearNodeRef(NodeIdx),
+    /// Set the node's innerHTML.
+    SetDangerousInnerHtml(NodeIdx, String),
+    /// Set the node's innerHTML to an empty string.
+    RemoveDangerousInnerHtml(NodeIdx),
+}
+
+/// An owned version of a [`VirtualNode`] that gets created by a patch.
+///
+/// Components are replaced by the nodes that they rendered and fragments inside of elements are
+/// flattened into their children.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedNode {
+    /// An element.
+    Element(OwnedElement),
+    /// A text node.
+    Text(String),
+    /// A comment node.
+    Comment(String),
+    /// A fragment's children.
+    Fragment(Vec<OwnedNode>),
+    /// A portal.
+    #[allow(missing_docs)]
+    Portal {
+        mount_selector: String,
+        children: Vec<OwnedNode>,
+    },
+}
+
+/// An owned version of a [`VElement`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedElement {
+    /// The HTML tag, such as "div"
+    pub tag: String,
+    /// The element's attributes, sorted by name.
+    pub attrs: Vec<(String, AttributeValue)>,
+    /// See [`VElement.dom_properties`], sorted by name.
+    pub dom_properties: Vec<String>,
+    /// The element's inline style properties, in order.
+    pub styles: Vec<(String, String)>,
+    /// The names of the element's events.
+    pub events: Vec<String>,
+    /// The key of the element's on create element function.
+    pub on_create_element: Option<String>,
+    /// The key of the element's on remove element function.
+    pub on_remove_element: Option<String>,
+    /// The key of the element's on update element function.
+    pub on_update_element: Option<String>,
+    /// See [`SpecialAttributes.dangerous_inner_html`].
+    pub dangerous_inner_html: Option<String>,
+    /// Whether or not the element has a [`NodeRef`].
+    pub node_ref: bool,
+    /// The element's children.
+    pub children: Vec<OwnedNode>,
+}
+
+/// Binds the event handlers and special attribute functions that [`OwnedPatches`] only know the
+/// names and keys of.
+///
+/// The node indices are the indices of the nodes in the new virtual dom.
+pub trait PatchBindings {
+    /// The handler for a node's event, or `None` if the event should not be handled.
+    fn event_handler(&self, node_idx: NodeIdx, event: &EventName) -> Option<EventHandler>;
+
+    /// Called when the on create element function with the given key would be called.
+    fn on_create_element(&self, node_idx: NodeIdx, key: &str, element: web_sys::Element) {
+        let _ = (node_idx, key, element);
+    }
+
+    /// Called when the on remove element function with the given key would be called.
+    ///
+    /// For these the node index is the index of the node in the old virtual dom.
+    fn on_remove_element(&self, node_idx: NodeIdx, key: &str, element: web_sys::Element) {
+        let _ = (node_idx, key, element);
+    }
+
+    /// Called when the on remove element function with the given key would defer the element's
+    /// removal. The element stays in the DOM until the removal is finished.
+    ///
+    /// By default the element gets removed right away.
+    ///
+    /// For these the node index is the index of the node in the old virtual dom.
+    fn on_remove_element_deferred(
+        &self,
+        node_idx: NodeIdx,
+        key: &str,
+        element: web_sys::Element,
+        removal: DeferredRemoval,
+    ) {
+        let _ = (node_idx, key, element);
+        removal.finish();
+    }
+
+    /// Called when the on update element function with the given key would be called, with the
+    /// old and the new node's attributes.
+    ///
+    /// For these the node index is the index of the node in the old virtual dom.
+    fn on_update_element(
+        &self,
+        node_idx: NodeIdx,
+        key: &str,
+        element: web_sys::Element,
+        old_attrs: &HashMap<String, AttributeValue>,
+        new_attrs: &HashMap<String, AttributeValue>,
+    ) {
+        let _ = (node_idx, key, element, old_attrs, new_attrs);
+    }
+
+    /// The [`NodeRef`] of a node that has one, or `None` if it should not be set.
+    ///
+    /// For the `NodeRef`s that get cleared the node index is the index of the node in the old
+    /// virtual dom.
+    fn node_ref(&self, node_idx: NodeIdx) -> Option<NodeRef> {
+        let _ = node_idx;
+        None
+    }
+}
+
+impl OwnedPatches {
+    /// Diff two virtual doms, returning owned patches.
+    pub fn diff(old: &VirtualNode, new: &VirtualNode) -> Self {
+        OwnedPatches::new(new, &crate::diff(old, new))
+    }
+
+    /// Create owned patches from the patches that turn some virtual dom into the new virtual dom.
+    pub fn new(new_vdom: &VirtualNode, patches: &[Patch]) -> Self {
+        let mut events = vec![];
+        push_events(new_vdom, &mut 0, &mut events);
+
+        OwnedPatches {
+            patches: patches.iter().map(OwnedPatch::from).collect(),
+            events,
+        }
+    }
+}
+
+fn push_events(node: &VirtualNode, node_idx: &mut u32, events: &mut Vec<(NodeIdx, Vec<String>)>) {
+    let children = match node.rendered_node() {
+        VirtualNode::Element(element) => {
+            if element.events.has_events() {
+                events.push((*node_idx, event_names(element)));
+            }
+
+            element.flat_children()
+        }
+        VirtualNode::Portal(portal) => portal.flat_children(),
+        _ => return,
+    };
+
+    for child in children {
+        *node_idx += 1;
+        push_events(child, node_idx, events);
+    }
+}
+
+impl<'a> From<&Patch<'a>> for OwnedPatch {
+    fn from(patch: &Patch<'a>) -> Self {
+        match patch {
+            Patch::AppendChildren { old_idx, new_nodes } => OwnedPatch::AppendChildren {
+                old_idx: *old_idx,
+                new_nodes: owned_nodes(new_nodes),
+            },
+            Patch::TruncateChildren(node_idx, len) => OwnedPatch::TruncateChildren(*node_idx, *len),
+            Patch::InsertBefore {
+                anchor_old_idx,
+                new_nodes,
+            } => OwnedPatch::InsertBefore {
+                anchor_old_idx: *anchor_old_idx,
+                new_nodes: owned_nodes(new_nodes),
+            },
+            Patch::MoveNodesBefore {
+                anchor_old_idx,
+                to_move,
+            } => OwnedPatch::MoveNodesBefore {
+                anchor_old_idx: *anchor_old_idx,
+                to_move: to_move.clone(),
+            },
+            Patch::MoveToEndOfSiblings {
+                parent_old_idx,
+                siblings_to_move,
+            } => OwnedPatch::MoveToEndOfSiblings {
+                parent_old_idx: *parent_old_idx,
+                siblings_to_move: siblings_to_move.clone(),
+            },
+            Patch::RemoveChildren {
+                parent_old_idx,
+                to_remove,
+            } => OwnedPatch::RemoveChildren {
+                parent_old_idx: *parent_old_idx,
+                to_remove: to_remove.clone(),
+            },
+            Patch::Replace {
+                old_idx,
+                new_idx,
+                new_node,
+            } => OwnedPatch::Replace {
+                old_idx: *old_idx,
+                new_idx: *new_idx,
+                new_node: OwnedNode::from(*new_node),
+            },
+            Patch::SetDomProperties(node_idx, properties) => {
+                OwnedPatch::SetDomProperties(*node_idx, owned_attributes(properties))
+            }
+            Patch::RemoveDomProperties(node_idx, properties) => OwnedPatch::RemoveDomProperties(
+                *node_idx,
+                properties.iter().map(|name| name.to_string()).collect(),
+            ),
+            #[allow(deprecated)]
+            Patch::ValueAttributeUnchanged(node_idx, value) => {
+                OwnedPatch::ValueAttributeUnchanged(*node_idx, (*value).clone())
+            }
+            Patch::AddAttributes(node_idx, attributes) => {
+                OwnedPatch::AddAttributes(*node_idx, owned_attributes(attributes))
+            }
+            Patch::RemoveAttributes(node_idx, attributes) => OwnedPatch::RemoveAttributes(
+                *node_idx,
+                attributes.iter().map(|name| name.to_string()).collect(),
+            ),
+            Patch::SetStyleProperties(node_idx, styles) => OwnedPatch::SetStyleProperties(
+                *node_idx,
+                styles
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect(),
+            ),
+            Patch::RemoveStyleProperties(node_idx, styles) => OwnedPatch::RemoveStyleProperties(
+                *node_idx,
+                styles.iter().map(|name| name.to_string()).collect(),
+            ),
+            Patch::ChangeText(node_idx, text) => {
+                OwnedPatch::ChangeText(*node_idx, text.text.clone())
+            }
+            Patch::SpecialAttribute(special) => {
+                OwnedPatch::SpecialAttribute(OwnedPatchSpecialAttribute::from(special))
+            }
+            Patch::RemoveEventsId(node_idx) => OwnedPatch::RemoveEventsId(*node_idx),
+            Patch::SetEventsId { old_idx, new_idx } => OwnedPatch::SetEventsId {
+                old_idx: *old_idx,
+                new_idx: *new_idx,
+            },
+            Patch::AddEvents(node_idx, events) => {
+                let mut names: Vec<String> = events
+                    .keys()
+                    .map(|name| name.with_on_prefix().to_string())
+                    .collect();
+                names.sort();
+
+                OwnedPatch::AddEvents(*node_idx, names)
+            }
+            Patch::RemoveEvents(node_idx, events) => OwnedPatch::RemoveEvents(
+                *node_idx,
+                events
+                    .iter()
+                    .map(|(name, _)| name.with_on_prefix().to_string())
+                    .collect(),
+            ),
+            Patch::RemoveAllManagedEventsWithNodeIdx(node_idx) => {
+                OwnedPatch::RemoveAllManagedEventsWithNodeIdx(*node_idx)
+            }
+            Patch::RemoveAllManagedEventsInRange {
+                first_idx,
+                last_idx,
+            } => OwnedPatch::RemoveAllManagedEventsInRange {
+                first_idx: *first_idx,
+                last_idx: *last_idx,
+            },
+            Patch::RemovePortal(node_idx) => OwnedPatch::RemovePortal(*node_idx),
+        }
+    }
+}
+
+impl<'a> From<&PatchSpecialAttribute<'a>> for OwnedPatchSpecialAttribute {
+    fn from(special: &PatchSpecialAttribute<'a>) -> Self {
+        match special {
+            PatchSpecialAttribute::CallOnCreateElem(node_idx, node) => {
+                let key = special_attributes(node).on_create_element_key();
+                OwnedPatchSpecialAttribute::CallOnCreateElem(
+                    *node_idx,
+                    key.map(|key| key.to_string()).unwrap_or_default(),
+                )
+            }
+            PatchSpecialAttribute::CallOnRemoveElem(node_idx, node) => {
+                let key = special_attributes(node).on_remove_element_key();
+                OwnedPatchSpecialAttribute::CallOnRemoveElem(
+                    *node_idx,
+                    key.map(|key| key.to_string()).unwrap_or_default(),
+                )
+            }
+            PatchSpecialAttribute::DeferRemoveElem(node_idx, node) => {
+                let key = special_attributes(node).on_remove_element_key();
+                OwnedPatchSpecialAttribute::DeferRemoveElem(
+                    *node_idx,
+                    key.map(|key| key.to_string()).unwrap_or_default(),
+                )
+            }
+            PatchSpecialAttribute::CallOnUpdateElem(node_idx, old, new) => {
+                let key = special_attributes(new).on_update_element_key();
+                OwnedPatchSpecialAttribute::CallOnUpdateElem {
+                    node_idx: *node_idx,
+                    key: key.map(|key| key.to_string()).unwrap_or_default(),
+                    old_attrs: sorted_attributes(old.as_velement_ref().unwrap()),
+                    new_attrs: sorted_attributes(new.as_velement_ref().unwrap()),
+                }
+            }
+            PatchSpecialAttribute::SetNodeRef(node_idx, _) => {
+                OwnedPatchSpecialAttribute::SetNodeRef(*node_idx)
+            }
+            PatchSpecialAttribute::ClearNodeRef(node_idx, _) => {
+                OwnedPatchSpecialAttribute::ClearNodeRef(*node_idx)
+            }
+            PatchSpecialAttribute::SetDangerousInnerHtml(node_idx, node) => {
+                OwnedPatchSpecialAttribute::SetDangerousInnerHtml(
+                    *node_idx,
+                    special_attributes(node)
+                        .dangerous_inner_html
+                        .clone()
+                        .unwrap_or_default(),
+                )
+            }
+            PatchSpecialAttribute::RemoveDangerousInnerHtml(node_idx) => {
+                OwnedPatchSpecialAttribute::RemoveDangerousInnerHtml(*node_idx)
+            }
+        }
+    }
+}
+
+impl From<&VirtualNode> for OwnedNode {
+    fn from(node: &VirtualNode) -> Self {
+        match node.rendered_node() {
+            VirtualNode::Element(element) => {
+                let mut dom_properties: Vec<String> =
+                    element.dom_properties.iter().cloned().collect();
+                dom_properties.sort();
+
+                let special_attributes = &element.special_attributes;
+
+                OwnedNode::Element(OwnedElement {
+                    tag: element.tag.clone(),
+                    attrs: sorted_attributes(element),
+                    dom_properties,
+                    styles: element
+                        .styles
+                        .iter()
+                        .map(|(name, value)| (name.to_string(), value.to_string()))
+                        .collect(),
+                    events: event_names(element),
+                    on_create_element: special_attributes
+                        .on_create_element_key()
+                        .map(|key| key.to_string()),
+                    on_remove_element: special_attributes
+                        .on_remove_element_key()
+                        .map(|key| key.to_string()),
+                    on_update_element: special_attributes
+                        .on_update_element_key()
+                        .map(|key| key.to_string()),
+                    dangerous_inner_html: special_attributes.dangerous_inner_html.clone(),
+                    node_ref: special_attributes.node_ref().is_some(),
+                    children: element
+                        .flat_children()
+                        .into_iter()
+                        .map(OwnedNode::from)
+                        .collect(),
+                })
+            }
+            VirtualNode::Text(text) => OwnedNode::Text(text.text.clone()),
+            VirtualNode::Comment(comment) => OwnedNode::Comment(comment.text.clone()),
+            VirtualNode::Fragment(fragment) => OwnedNode::Fragment(
+                fragment
+                    .flat_children()
+                    .into_iter()
+                    .map(OwnedNode::from)
+                    .collect(),
+            ),
+            VirtualNode::Portal(portal) => OwnedNode::Portal {
+                mount_selector: portal.mount_selector.clone(),
+                children: portal
+                    .flat_children()
+                    .into_iter()
+                    .map(OwnedNode::from)
+                    .collect(),
+            },
+            VirtualNode::Component(_) => {
+                unreachable!("Components are replaced by their rendered nodes.")
+            }
+        }
+    }
+}
+
+impl OwnedNode {
+    /// Create the virtual node, binding its events and special attribute functions.
+    pub(crate) fn to_virtual_node(
+        &self,
+        node_idx: &mut u32,
+        bindings: &Rc<dyn PatchBindings>,
+    ) -> VirtualNode {
+        match self {
+            OwnedNode::Element(element) => {
+                let idx = *node_idx;
+                let mut velement = VElement::new(element.tag.clone());
+
+                velement.attrs = element.attrs.iter().cloned().collect();
+                velement.dom_properties = element.dom_properties.iter().cloned().collect();
+                for (name, value) in element.styles.iter() {
+                    velement.styles.set(name.as_str(), value.as_str());
+                }
+
+                for name in &element.events {
+                    let name = EventName::from(name.clone());
+                    if let Some(handler) = bindings.event_handler(idx, &name) {
+                        velement.events.insert(name, handler);
+                    }
+                }
+
+                let special_attributes = &mut velement.special_attributes;
+                if let Some(key) = &element.on_create_element {
+                    let bindings = bindings.clone();
+                    let key_clone = key.clone();
+                    special_attributes.set_on_create_element(key.clone(), move |elem| {
+                        bindings.on_create_element(idx, &key_clone, elem)
+                    });
+                }
+                special_attributes.dangerous_inner_html = element.dangerous_inner_html.clone();
+                if element.node_ref {
+                    if let Some(node_ref) = bindings.node_ref(idx) {
+                        special_attributes.set_node_ref(node_ref);
+                    }
+                }
+
+                velement.children = to_virtual_children(&element.children, node_idx, bindings);
+
+                velement.into()
+            }
+            OwnedNode::Text(text) => VText::new(text.as_str()).into(),
+            OwnedNode::Comment(text) => VComment::new(text.as_str()).into(),
+            OwnedNode::Fragment(children) => {
+                let mut fragment = VFragment::new();
+                fragment.children = to_virtual_children(children, node_idx, bindings);
+
+                fragment.into()
+            }
+            OwnedNode::Portal {
+                mount_selector,
+                children,
+            } => {
+                let mut portal = VPortal::new(mount_selector.as_str());
+                portal.children = to_virtual_children(children, node_idx, bindings);
+
+                portal.into()
+            }
+        }
+    }
+}
+
+fn to_virtual_children(
+    children: &[OwnedNode],
+    node_idx: &mut u32,
+    bindings: &Rc<dyn PatchBindings>,
+) -> Vec<VirtualNode> {
+    children
+        .iter()
+        .map(|child| {
+            *node_idx += 1;
+            child.to_virtual_node(node_idx, bindings)
+        })
+        .collect()
+}
+
+fn special_attributes(node: &VirtualNode) -> &SpecialAttributes {
+    &node.as_velement_ref().unwrap().special_attributes
+}
+
+fn owned_nodes(nodes: &[(NodeIdx, &VirtualNode)]) -> Vec<(NodeIdx, OwnedNode)> {
+    nodes
+        .iter()
+        .map(|(node_idx, node)| (*node_idx, OwnedNode::from(*node)))
+        .collect()
+}
+
+fn owned_attributes(attributes: &HashMap<&str, &AttributeValue>) -> Vec<(String, AttributeValue)> {
+    let mut attributes: Vec<(String, AttributeValue)> = attributes
+        .iter()
+        .map(|(name, value)| (name.to_string(), (*value).clone()))
+        .collect();
+    attributes.sort_by(|a, b| a.0.cmp(&b.0));
+
+    attributes
+}
+
+fn sorted_attributes(element: &VElement) -> Vec<(String, AttributeValue)> {
+    let mut attrs: Vec<(String, AttributeValue)> = element
+        .attrs
+        .iter()
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    attrs.sort_by(|a, b| a.0.cmp(&b.0));
+
+    attrs
+}
+
+fn event_names(element: &VElement) -> Vec<String> {
+    let mut names: Vec<String> = element
+        .events
+        .keys()
+        .map(|name| name.with_on_prefix().to_string())
+        .collect();
+    names.sort();
+
+    names
+}
+
+/// The virtual nodes, text and events that an [`OwnedPatch`] needs in order to be turned back into
+/// a [`Patch`] that can be applied.
+pub(crate) enum Materialized {
+    Nothing,
+    Node(Box<VirtualNode>),
+    OldAndNewNodes(Box<VirtualNode>, Box<VirtualNode>),
+    Nodes(Vec<(NodeIdx, VirtualNode)>),
+    Text(VText),
+    Events(Vec<(EventName, EventHandler)>),
+}
+
+impl Materialized {
+    pub(crate) fn new(patch: &OwnedPatch, bindings: &Rc<dyn PatchBindings>) -> Self {
+        match patch {
+            OwnedPatch::AppendChildren { new_nodes, .. }
+            | OwnedPatch::InsertBefore { new_nodes, .. } => Materialized::Nodes(
+                new_nodes
+                    .iter()
+                    .map(|(node_idx, node)| {
+                        (
+                            *node_idx,
+                            node.to_virtual_node(&mut node_idx.clone(), bindings),
+                        )
+                    })
+                    .collect(),
+            ),
+            OwnedPatch::Replace {
+                new_idx, new_node, ..
+            } => Materialized::Node(Box::new(
+                new_node.to_virtual_node(&mut new_idx.clone(), bindings),
+            )),
+            OwnedPatch::ChangeText(_, text) => Materialized::Text(VText::new(text.as_str())),
+            OwnedPatch::SpecialAttribute(OwnedPatchSpecialAttribute::CallOnUpdateElem {
+                node_idx,
+                key,
+                old_attrs,
+                new_attrs,
+            }) => {
+                let mut old = VElement::new("");
+                old.attrs = old_attrs.iter().cloned().collect();
+
+                let mut new = VElement::new("");
+                new.attrs = new_attrs.iter().cloned().collect();
+
+                let (node_idx, key_clone, bindings) = (*node_idx, key.clone(), bindings.clone());
+                new.special_attributes.set_on_update_element(
+                    key.clone(),
+                    move |elem, old_attrs, new_attrs| {
+                        bindings.on_update_element(node_idx, &key_clone, elem, old_attrs, new_attrs)
+                    },
+                );
+
+                Materialized::OldAndNewNodes(Box::new(old.into()), Box::new(new.into()))
+            }
+            OwnedPatch::SpecialAttribute(special) => {
+                let mut element = VElement::new("");
+                let special_attributes = &mut element.special_attributes;
+
+                match special {
+                    OwnedPatchSpecialAttribute::CallOnCreateElem(node_idx, key) => {
+                        let (node_idx, key_clone, bindings) =
+                            (*node_idx, key.clone(), bindings.clone());
+                        special_attributes.set_on_create_element(key.clone(), move |elem| {
+                            bindings.on_create_element(node_idx, &key_clone, elem)
+                        });
+                    }
+                    OwnedPatchSpecialAttribute::CallOnRemoveElem(node_idx, key) => {
+                        let (node_idx, key_clone, bindings) =
+                            (*node_idx, key.clone(), bindings.clone());
+                        special_attributes.set_on_remove_element(key.clone(), move |elem| {
+                            bindings.on_remove_element(node_idx, &key_clone, elem)
+                        });
+                    }
+                    OwnedPatchSpecialAttribute::DeferRemoveElem(node_idx, key) => {
+                        let (node_idx, key_clone, bindings) =
+                            (*node_idx, key.clone(), bindings.clone());
+                        special_attributes.set_on_remove_element_deferred(
+                            key.clone(),
+                            move |elem, removal| {
+                                bindings
+                                    .on_remove_element_deferred(node_idx, &key_clone, elem, removal)
+                            },
+                        );
+                    }
+                    OwnedPatchSpecialAttribute::CallOnUpdateElem { .. } => {
+                        unreachable!("Materialized along with the old node's attributes.")
+                    }
+                    OwnedPatchSpecialAttribute::SetNodeRef(node_idx)
+                    | OwnedPatchSpecialAttribute::ClearNodeRef(node_idx) => {
+                        if let Some(node_ref) = bindings.node_ref(*node_idx) {
+                            special_attributes.set_node_ref(node_ref);
+                        }
+                    }
+                    OwnedPatchSpecialAttribute::SetDangerousInnerHtml(_, html) => {
+                        special_attributes.dangerous_inner_html = Some(html.clone());
+                    }
+                    OwnedPatchSpecialAttribute::RemoveDangerousInnerHtml(_) => {}
+                }
+
+                Materialized::Node(Box::new(element.into()))
+            }
+            OwnedPatch::AddEvents(node_idx, names) => Materialized::Events(
+                names
+                    .iter()
+                    .filter_map(|name| {
+                        let name = EventName::from(name.clone());
+                        let handler = bindings.event_handler(*node_idx, &name)?;
+                        Some((name, handler))
+                    })
+                    .collect(),
+            ),
+            OwnedPatch::RemoveEvents(_, names) => Materialized::Events(
+                names
+                    .iter()
+                    .map(|name| {
+                        // Only the name is used when removing an event.
+                        let handler = EventHandler::UnsupportedSignature(EventAttribFn(Rc::new(
+                            Box::new(JsValue::NULL),
+                        )));
+                        (EventName::from(name.clone()), handler)
+                    })
+                    .collect(),
+            ),
+            _ => Materialized::Nothing,
+        }
+    }
+
+    /// The patch that borrows from the owned patch and from these materialized nodes.
+    pub(crate) fn patch<'a>(&'a self, patch: &'a OwnedPatch) -> Patch<'a> {
+        match (patch, self) {
+            (OwnedPatch::AppendChildren { old_idx, .. }, Materialized::Nodes(nodes)) => {
+                Patch::AppendChildren {
+                    old_idx: *old_idx,
+                    new_nodes: borrowed_nodes(nodes),
+                }
+            }
+            (OwnedPatch::TruncateChildren(node_idx, len), _) => {
+                Patch::TruncateChildren(*node_idx, *len)
+            }
+            (OwnedPatch::InsertBefore { anchor_old_idx, .. }, Materialized::Nodes(nodes)) => {
+                Patch::InsertBefore {
+                    anchor_old_idx: *anchor_old_idx,
+                    new_nodes: borrowed_nodes(nodes),
+                }
+            }
+            (
+                OwnedPatch::MoveNodesBefore {
+                    anchor_old_idx,
+                    to_move,
+                },
+                _,
+            ) => Patch::MoveNodesBefore {
+                anchor_old_idx: *anchor_old_idx,
+                to_move: to_move.clone(),
+            },
+            (
+                OwnedPatch::MoveToEndOfSiblings {
+                    parent_old_idx,
+                    siblings_to_move,
+                },
+                _,
+            ) => Patch::MoveToEndOfSiblings {
+                parent_old_idx: *parent_old_idx,
+                siblings_to_move: siblings_to_move.clone(),
+            },
+            (
+                OwnedPatch::RemoveChildren {
+                    parent_old_idx,
+                    to_remove,
+                },
+                _,
+            ) => Patch::RemoveChildren {
+                parent_old_idx: *parent_old_idx,
+                to_remove: to_remove.clone(),
+            },
+            (
+                OwnedPatch::Replace {
+                    old_idx, new_idx, ..
+                },
+                Materialized::Node(node),
+            ) => Patch::Replace {
+                old_idx: *old_idx,
+                new_idx: *new_idx,
+                new_node: node,
+            },
+            (OwnedPatch::SetDomProperties(node_idx, properties), _) => Patch::SetDomProperties(
+                *node_idx,
+                properties
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value))
+                    .collect(),
+            ),
+            (OwnedPatch::RemoveDomProperties(node_idx, properties), _) => {
+                Patch::RemoveDomProperties(
+                    *node_idx,
+                    properties.iter().map(|name| name.as_str()).collect(),
+                )
+            }
+            #[allow(deprecated)]
+            (OwnedPatch::ValueAttributeUnchanged(node_idx, value), _) => {
+                Patch::ValueAttributeUnchanged(*node_idx, value)
+            }
+            (OwnedPatch::AddAttributes(node_idx, attributes), _) => Patch::AddAttributes(
+                *node_idx,
+                attributes
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value))
+                    .collect(),
+            ),
+            (OwnedPatch::RemoveAttributes(node_idx, attributes), _) => Patch::RemoveAttributes(
+                *node_idx,
+                attributes.iter().map(|name| name.as_str()).collect(),
+            ),
+            (OwnedPatch::SetStyleProperties(node_idx, styles), _) => Patch::SetStyleProperties(
+                *node_idx,
+                styles
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str()))
+                    .collect(),
+            ),
+            (OwnedPatch::RemoveStyleProperties(node_idx, styles), _) => {
+                Patch::RemoveStyleProperties(
+                    *node_idx,
+                    styles.iter().map(|name| name.as_str()).collect(),
+                )
+            }
+            (OwnedPatch::ChangeText(node_idx, _), Materialized::Text(text)) => {
+                Patch::ChangeText(*node_idx, text)
+            }
+            (
+                OwnedPatch::SpecialAttribute(OwnedPatchSpecialAttribute::CallOnUpdateElem {
+                    node_idx,
+                    ..
+                }),
+                Materialized::OldAndNewNodes(old, new),
+            ) => Patch::SpecialAttribute(PatchSpecialAttribute::CallOnUpdateElem(
+                *node_idx, old, new,
+            )),
+            (OwnedPatch::SpecialAttribute(special), Materialized::Node(node)) => {
+                Patch::SpecialAttribute(match special {
+                    OwnedPatchSpecialAttribute::CallOnCreateElem(node_idx, _) => {
+                        PatchSpecialAttribute::CallOnCreateElem(*node_idx, node)
+                    }
+                    OwnedPatchSpecialAttribute::CallOnRemoveElem(node_idx, _) => {
+                        PatchSpecialAttribute::CallOnRemoveElem(*node_idx, node)
+                    }
+                    OwnedPatchSpecialAttribute::DeferRemoveElem(node_idx, _) => {
+                        PatchSpecialAttribute::DeferRemoveElem(*node_idx, node)
+                    }
+                    OwnedPatchSpecialAttribute::CallOnUpdateElem { .. } => {
+                        unreachable!("Materialized along with the old node's attributes.")
+                    }
+                    OwnedPatchSpecialAttribute::SetNodeRef(node_idx) => {
+                        PatchSpecialAttribute::SetNodeRef(*node_idx, node)
+                    }
+                    OwnedPatchSpecialAttribute::ClearNodeRef(node_idx) => {
+                        PatchSpecialAttribute::ClearNodeRef(*node_idx, node)
+                    }
+                    OwnedPatchSpecialAttribute::SetDangerousInnerHtml(node_idx, _) => {
+                        PatchSpecialAttribute::SetDangerousInnerHtml(*node_idx, node)
+                    }
+                    OwnedPatchSpecialAttribute::RemoveDangerousInnerHtml(node_idx) => {
+                        PatchSpecialAttribute::RemoveDangerousInnerHtml(*node_idx)
+                    }
+                })
+            }
+            (OwnedPatch::RemoveEventsId(node_idx), _) => Patch::RemoveEventsId(*node_idx),
+            (OwnedPatch::SetEventsId { old_idx, new_idx }, _) => Patch::SetEventsId {
+                old_idx: *old_idx,
+                new_idx: *new_idx,
+            },
+            (OwnedPatch::AddEvents(node_idx, _), Materialized::Events(events)) => Patch::AddEvents(
+                *node_idx,
+                events
+                    .iter()
+                    .map(|(name, handler)| (name, handler))
+                    .collect(),
+            ),
+            (OwnedPatch::RemoveEvents(node_idx, _), Materialized::Events(events)) => {
+                Patch::RemoveEvents(
+                    *node_idx,
+                    events
+                        .iter()
+                        .map(|(name, handler)| (name, handler))
+                        .collect(),
+                )
+            }
+            (OwnedPatch::RemoveAllManagedEventsWithNodeIdx(node_idx), _) => {
+                Patch::RemoveAllManagedEventsWithNodeIdx(*node_idx)
+            }
+            (
+                OwnedPatch::RemoveAllManagedEventsInRange {
+                    first_idx,
+                    last_idx,
+                },
+                _,
+            ) => Patch::RemoveAllManagedEventsInRange {
+                first_idx: *first_idx,
+                last_idx: *last_idx,
+            },
+            (OwnedPatch::RemovePortal(node_idx), _) => Patch::RemovePortal(*node_idx),
+            (patch, _) => unreachable!("{:?} was not materialized.", patch),
+        }
+    }
+}
+
+fn borrowed_nodes(nodes: &[(NodeIdx, VirtualNode)]) -> Vec<(NodeIdx, &VirtualNode)> {
+    nodes
+        .iter()
+        .map(|(node_idx, node)| (*node_idx, node))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::html;
+
+    /// Verify that materializing owned patches gives us back the patches that they were created
+    /// from.
+    #[test]
+    fn owned_patches_round_trip() {
+        let old = html! {
+            <div style="color: red; width: 1px">
+                <span>a</span>
+                <em></em>
+            </div>
+        };
+        let new = html! {
+            <div id="new" style="color: blue">
+                <span>b</span>
+                <em></em>
+                <strong style="margin: 0"><i></i></strong>
+            </div>
+        };
+
+        let patches = crate::diff(&old, &new);
+        let owned = OwnedPatches::new(&new, &patches);
+
+        let bindings: Rc<dyn PatchBindings> = Rc::new(NoBindings);
+        let materialized: Vec<Materialized> = owned
+            .patches
+            .iter()
+            .map(|patch| Materialized::new(patch, &bindings))
+            .collect();
+        let borrowed: Vec<Patch> = owned
+            .patches
+            .iter()
+            .zip(materialized.iter())
+            .map(|(patch, materialized)| materialized.patch(patch))
+            .collect();
+
+        assert_eq!(borrowed, patches);
+    }
+
+    /// Verify that we keep the names of the events of every element in the new virtual dom, along
+    /// with the events that get added.
+    #[test]
+    fn events_kept_by_name() {
+        let old = html! { <div> <span></span> </div> };
+        let new = html! { <div onclick=|| {}> <span oninput=|| {} onclick=|| {}></span> </div> };
+
+        let owned = OwnedPatches::diff(&old, &new);
+
+        assert_eq!(
+            owned.events,
+            vec![
+                (0, vec!["onclick".to_string()]),
+                (1, vec!["onclick".to_string(), "oninput".to_string()]),
+            ]
+        );
+        assert!(owned
+            .patches
+            .contains(&OwnedPatch::AddEvents(0, vec!["onclick".to_string()])));
+    }
+
+    /// Verify that an on update element patch keeps the function's key along with the old and new
+    /// attributes.
+    #[test]
+    fn on_update_element_keeps_attributes() {
+        let old = html! { <div key="chart" title="1" on_update_element=|| {}></div> };
+        let new = html! { <div key="chart" title="2" on_update_element=|| {}></div> };
+
+        let owned = OwnedPatches::diff(&old, &new);
+
+        let attrs = |value: &str| {
+            vec![
+                ("key".to_string(), AttributeValue::from("chart")),
+                ("title".to_string(), AttributeValue::from(value)),
+            ]
+        };
+        assert_eq!(
+            owned.patches.last(),
+            Some(&OwnedPatch::SpecialAttribute(
+                OwnedPatchSpecialAttribute::CallOnUpdateElem {
+                    node_idx: 0,
+                    key: "chart".to_string(),
+                    old_attrs: attrs("1"),
+                    new_attrs: attrs("2"),
+                }
+            ))
+        );
+    }
+
+    /// Verify that owned patches can be serialized and deserialized.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let old = html! { <div> <span>a</span> </div> };
+        let new = html! { <div> <span>b</span> <input disabled=true /> </div> };
+
+        let owned = OwnedPatches::diff(&old, &new);
+
+        let json = serde_json::to_string(&owned).unwrap();
+        assert_eq!(serde_json::from_str::<OwnedPatches>(&json).unwrap(), owned);
+    }
+
+    struct NoBindings;
+
+    impl PatchBindings for NoBindings {
+        fn event_handler(&self, _node_idx: NodeIdx, _event: &EventName) -> Option<EventHandler> {
+            None
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt;
+
+use wasm_bindgen::JsValue;
+
+use crate::patch::{NodeIdx, Patch};
+
+/// A patch that could not be applied to the DOM.
+///
+/// This typically happens when something other than percy-dom, such as a browser extension,
+/// changed the DOM nodes that percy-dom manages.
+///
+/// The patches that came before the failed patch have already been applied, so the DOM is left
+/// partially patched. See [`crate::PercyDom::recover_from_patch_errors`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatchError {
+    /// The name of the patch that failed, such as `"Replace"`.
+    pub patch: &'static str,
+    /// The depth first index of the old node that the patch applies to.
+    pub node_idx: NodeIdx,
+    /// Why the patch could not be applied.
+    pub kind: PatchErrorKind,
+}
+
+/// The reasons that a patch can fail to be applied.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchErrorKind {
+    /// There was no DOM node for one of the old nodes that the patch needed, such as the node that
+    /// it applies to or a node that it moves.
+    NodeNotFound(NodeIdx),
+    /// The DOM node was a different kind of node than the patch applies to, such as a text node
+    /// where an element was expected.
+    UnexpectedNodeType,
+    /// The browser threw an error while patching the DOM node.
+    Dom(JsValue),
+}
+
+impl PatchError {
+    pub(crate) fn new(patch: &Patch, kind: PatchErrorKind) -> Self {
+        PatchError {
+            patch: patch.name(),
+            node_idx: patch.old_node_idx(),
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Could not apply the {} patch to node {}: ",
+            self.patch, self.node_idx
+        )?;
+
+        match &self.kind {
+            PatchErrorKind::NodeNotFound(node_idx) => {
+                write!(f, "there is no DOM node for node {}.", node_idx)
+            }
+            PatchErrorKind::UnexpectedNodeType => {
+                write!(f, "the DOM node is not the kind of node that it should be.")
+            }
+            PatchErrorKind::Dom(err) => write!(f, "the browser threw {:?}.", err),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+impl From<JsValue> for PatchErrorKind {
+    fn from(err: JsValue) -> Self {
+        PatchErrorKind::Dom(err)
+    }
+}
+
+impl From<PatchError> for JsValue {
+    fn from(err: PatchError) -> Self {
+        js_sys::Error::new(&err.to_string()).into()
+    }
+}
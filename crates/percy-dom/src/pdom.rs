@@ -1,15 +1,19 @@
 //! Diff virtual-doms and patch the real DOM
 
-use crate::diff::diff;
+use crate::diff::{diff, diff_rerendered_components, rerender_changed_components};
 use crate::event::EventsByNodeIdx;
 use crate::patch::patch;
+use crate::{Namespace, Patch, PatchError};
 use std::collections::HashMap;
-use virtual_node::VirtualNode;
+use std::rc::Rc;
+use virtual_node::{HydrationMismatch, VirtualNode};
 use wasm_bindgen::JsValue;
 use web_sys::{Element, Node};
 
 mod events;
 
+type PatchErrorListener = Box<dyn Fn(&PatchError)>;
+
 /// Used for keeping a real DOM node up to date based on the current VirtualNode
 /// and a new incoming VirtualNode that represents our latest DOM state.
 ///
@@ -22,13 +26,21 @@ pub struct PercyDom {
     root_node: Node,
     // We hold onto these since if we drop the listener it can no longer be called.
     event_delegation_listeners: HashMap<&'static str, Box<dyn AsRef<JsValue>>>,
+    component_state_listener: Option<Rc<dyn Fn()>>,
+    patch_error_listener: Option<PatchErrorListener>,
 }
 
 impl PercyDom {
     /// Create a new `PercyDom`.
     ///
     /// A root `Node` will be created but not added to your DOM.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the virtual node is a fragment, since the root must be a single DOM node.
     pub fn new(current_vdom: VirtualNode) -> PercyDom {
+        assert_root_is_not_fragment(&current_vdom);
+
         let mut events = EventsByNodeIdx::new();
         let created_node = current_vdom.create_dom_node(0, &mut events);
 
@@ -37,6 +49,8 @@ impl PercyDom {
             root_node: created_node,
             events,
             event_delegation_listeners: HashMap::new(),
+            component_state_listener: None,
+            patch_error_listener: None,
         };
         pdom.attach_event_listeners();
 
@@ -71,22 +85,203 @@ impl PercyDom {
         pdom
     }
 
+    /// Create a new `PercyDom` from DOM that was already rendered from the virtual dom, typically
+    /// on the server.
+    ///
+    /// Instead of creating new DOM nodes we reuse the nodes in your passed in mount element,
+    /// attaching events and calling on create element functions.
+    ///
+    /// Any differences between the mount and the virtual dom are repaired and returned so that
+    /// you can report them. If the mount itself does not match the root virtual node it gets
+    /// replaced.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the virtual node is a fragment, since the root must be a single DOM node.
+    pub fn hydrate(
+        current_vdom: VirtualNode,
+        mount: Element,
+    ) -> (PercyDom, Vec<HydrationMismatch>) {
+        assert_root_is_not_fragment(&current_vdom);
+
+        let mut events = EventsByNodeIdx::new();
+        let mut mismatches = vec![];
+
+        let root_node =
+            current_vdom.hydrate_dom_node(mount.into(), 0, &mut events, &mut mismatches);
+
+        let mut pdom = PercyDom {
+            current_vdom,
+            root_node,
+            events,
+            event_delegation_listeners: HashMap::new(),
+            component_state_listener: None,
+            patch_error_listener: None,
+        };
+        pdom.attach_event_listeners();
+
+        (pdom, mismatches)
+    }
+
     /// Diff the current virtual dom with the new virtual dom that is being passed in.
     ///
     /// Then use that diff to patch the real DOM in the user's browser so that they are
     /// seeing the latest state of the application.
-    pub fn update(&mut self, new_vdom: VirtualNode) {
+    ///
+    /// Returns an error if one of the patches could not be applied, typically since something
+    /// other than percy-dom changed the DOM. See [`PercyDom::recover_from_patch_errors`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the virtual node is a fragment, since the root must be a single DOM node.
+    pub fn update(&mut self, new_vdom: VirtualNode) -> Result<(), PatchError> {
+        assert_root_is_not_fragment(&new_vdom);
+
         let patches = diff(&self.current_vdom, &new_vdom);
+        let patched = patch_root_node(&self.root_node, &new_vdom, &mut self.events, &patches);
+
+        self.current_vdom = new_vdom;
+
+        self.finish_patching(patched)
+    }
 
-        patch(
-            self.root_node.clone(),
-            &new_vdom,
+    /// Re-render the components whose state changed since they were last rendered, and patch the
+    /// real DOM.
+    ///
+    /// Nothing else gets rendered, and only the nodes that the re-rendered components render get
+    /// diffed.
+    ///
+    /// Returns an error if one of the patches could not be applied, just like
+    /// [`PercyDom::update`].
+    pub fn update_components(&mut self) -> Result<(), PatchError> {
+        let rerendered = rerender_changed_components(&mut self.current_vdom);
+        if rerendered.is_empty() {
+            return Ok(());
+        }
+
+        let patches = diff_rerendered_components(&self.current_vdom, &rerendered);
+        let patched = patch_root_node(
+            &self.root_node,
+            &self.current_vdom,
             &mut self.events,
             &patches,
-        )
-        .unwrap();
+        );
 
-        self.current_vdom = new_vdom;
+        self.finish_patching(patched)
+    }
+
+    /// Recover from patches that could not be applied by re-rendering the root node from scratch,
+    /// instead of returning an error from [`PercyDom::update`] and
+    /// [`PercyDom::update_components`].
+    ///
+    /// The `on_error` function gets called with the error before re-rendering, so that you can
+    /// report it.
+    ///
+    /// The re-rendered root node replaces the current root node, so any state that lives in the
+    /// DOM, such as an input's caret position, gets lost. On remove element functions do not get
+    /// called for the elements that get thrown away.
+    pub fn recover_from_patch_errors(&mut self, on_error: impl Fn(&PatchError) + 'static) {
+        self.patch_error_listener = Some(Box::new(on_error));
+    }
+
+    /// Call the listener whenever the state of one of the components in the virtual dom gets
+    /// updated.
+    ///
+    /// Typically the listener schedules a call to [`PercyDom::update_components`]. The listener
+    /// gets called while the component's state is being updated, so it should not update the
+    /// `PercyDom` right away.
+    pub fn on_component_state_change(&mut self, listener: impl Fn() + 'static) {
+        self.component_state_listener = Some(Rc::new(listener));
+        self.set_component_state_listeners();
+    }
+
+    fn set_component_state_listeners(&self) {
+        if let Some(listener) = &self.component_state_listener {
+            set_component_state_listeners(&self.current_vdom, listener);
+        }
+    }
+
+    /// Update the root node, the event delegation listeners and the component state listeners
+    /// after the patches were applied.
+    fn finish_patching(
+        &mut self,
+        patched: Result<Option<(Node, Option<Node>)>, PatchError>,
+    ) -> Result<(), PatchError> {
+        let finished = match patched {
+            Ok(replaced_root) => {
+                if let Some((parent, next_sibling)) = replaced_root {
+                    self.replace_root_node(&parent, next_sibling.as_ref());
+                }
+
+                Ok(())
+            }
+            Err(err) => self.recover_from_patch_error(err),
+        };
+
+        self.attach_event_listeners();
+        self.set_component_state_listeners();
+
+        finished
+    }
+
+    /// Re-render the root node if we are recovering from patch errors, otherwise return the
+    /// error.
+    fn recover_from_patch_error(&mut self, err: PatchError) -> Result<(), PatchError> {
+        match &self.patch_error_listener {
+            Some(on_error) => on_error(&err),
+            None => return Err(err),
+        }
+
+        if self.rerender_root_node().is_err() {
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// Replace the root node with a node that is created from the current virtual dom, along
+    /// with fresh events.
+    fn rerender_root_node(&mut self) -> Result<(), JsValue> {
+        for container in self.events.portal_containers() {
+            container.remove();
+        }
+
+        let parent = self.root_node.parent_node();
+        let namespace = parent
+            .as_ref()
+            .map(Namespace::for_children_of)
+            .unwrap_or_default();
+
+        let mut events = EventsByNodeIdx::new();
+        let created_node = self
+            .current_vdom
+            .try_create_dom_node_in(0, &mut events, namespace)?;
+
+        if let Some(parent) = parent {
+            parent.replace_child(&created_node, &self.root_node)?;
+        }
+
+        self.events = events;
+        self.root_node = created_node;
+        self.event_delegation_listeners.clear();
+        self.attach_event_listeners();
+
+        Ok(())
+    }
+
+    /// The root node was replaced by a new node that sits in the same spot in its parent.
+    ///
+    /// Our delegated event listeners live on the root node, so we attach them to the new root.
+    fn replace_root_node(&mut self, parent: &Node, next_sibling: Option<&Node>) {
+        let new_root = match next_sibling {
+            Some(next_sibling) => next_sibling.previous_sibling(),
+            None => parent.last_child(),
+        };
+
+        if let Some(new_root) = new_root {
+            self.root_node = new_root;
+            self.reattach_event_listeners();
+        }
     }
 
     /// Return the root node of your application, the highest ancestor of all other nodes in
@@ -97,3 +292,60 @@ impl PercyDom {
         self.root_node.clone()
     }
 }
+
+/// Apply the patches to the root node.
+///
+/// If the root node got replaced we return its parent and next sibling, so that we can find the
+/// new root node.
+fn patch_root_node(
+    root_node: &Node,
+    new_vdom: &VirtualNode,
+    events: &mut EventsByNodeIdx,
+    patches: &[Patch],
+) -> Result<Option<(Node, Option<Node>)>, PatchError> {
+    let root_replaced = patches
+        .iter()
+        .any(|p| matches!(p, Patch::Replace { old_idx: 0, .. }));
+    let root_parent = root_node.parent_node();
+    let root_next_sibling = root_node.next_sibling();
+
+    patch(root_node.clone(), new_vdom, events, patches)?;
+
+    if root_replaced {
+        Ok(root_parent.map(|parent| (parent, root_next_sibling)))
+    } else {
+        Ok(None)
+    }
+}
+
+fn set_component_state_listeners(node: &VirtualNode, listener: &Rc<dyn Fn()>) {
+    match node {
+        VirtualNode::Element(element) => {
+            for child in element.children.iter() {
+                set_component_state_listeners(child, listener);
+            }
+        }
+        VirtualNode::Fragment(fragment) => {
+            for child in fragment.children.iter() {
+                set_component_state_listeners(child, listener);
+            }
+        }
+        VirtualNode::Portal(portal) => {
+            for child in portal.children.iter() {
+                set_component_state_listeners(child, listener);
+            }
+        }
+        VirtualNode::Component(component) => {
+            component.set_state_listener(listener.clone());
+            set_component_state_listeners(component.rendered(), listener);
+        }
+        VirtualNode::Text(_) | VirtualNode::Comment(_) => {}
+    }
+}
+
+fn assert_root_is_not_fragment(vdom: &VirtualNode) {
+    assert!(
+        vdom.as_vfragment_ref().is_none(),
+        "The root virtual node of a PercyDom cannot be a fragment. Try wrapping it in an element."
+    );
+}
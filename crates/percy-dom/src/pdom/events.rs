@@ -1,66 +1,259 @@
-use crate::event::{EventHandler, EventName, EventsByNodeIdx, MouseEvent, EVENTS_ID_PROP};
-use crate::{Closure, PercyDom};
+use crate::event::{
+    add_delegated_listener, DelegatedEvent, EventName, EventsByNodeIdx, EVENTS_ID_PROP,
+};
+use crate::{portal_placeholder, Closure, PercyDom, CONTROLLED_VALUE_PROP};
 use js_sys::Reflect;
-use wasm_bindgen::JsCast;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Element, HtmlInputElement, HtmlTextAreaElement, Node};
 
 impl PercyDom {
-    /// Attach all of the event listeners that handle event delegation.
+    /// Attach the event delegation listeners that are missing.
+    ///
+    /// A DOM event gets a single listener on the root node, as well as on the element that holds
+    /// each portal's children, once one of its delegated events has a handler.
     pub(super) fn attach_event_listeners(&mut self) {
-        self.attach_onclick_listener();
+        for (listen_for, passive) in self.events.missing_delegated_listeners() {
+            self.attach_delegated_listener(listen_for, passive);
+        }
     }
 
-    fn attach_onclick_listener(&mut self) {
-        let event = "click";
-        debug_assert!(EventName::new(format!("on{}", event).into()).is_delegated());
+    /// Attach all of the event delegation listeners again, since the root node was replaced and
+    /// the listeners stop bubbling events at the root node.
+    pub(super) fn reattach_event_listeners(&mut self) {
+        for (listen_for, passive) in self.events.delegated_listeners() {
+            self.attach_delegated_listener(listen_for, passive);
+        }
+    }
 
+    fn attach_delegated_listener(&mut self, listen_for: &'static str, passive: bool) {
         let events = self.events.clone();
+        let root_node = self.root_node.clone();
+
+        let delegated_events: Vec<&'static DelegatedEvent> = EventName::delegated_events()
+            .iter()
+            .filter(|delegated| delegated.listen_for == listen_for)
+            .collect();
+        let events_id_prop: JsValue = EVENTS_ID_PROP.into();
+        let events_id_prefix = events.events_id_props_prefix().to_string();
+        let handled_prop: JsValue = format!("__percy_handled_{}__", events_id_prefix).into();
 
-        let callback = move |event: web_sys::MouseEvent| {
-            let target = event.target().unwrap();
+        let callback = move |event: web_sys::Event| {
+            if already_handled(&event, &handled_prop) {
+                return;
+            }
 
-            bubble_event(target.dyn_into().unwrap(), MouseEvent::new(event), &events);
+            let target: Node = match event.target() {
+                Some(target) => target.unchecked_into(),
+                None => return,
+            };
+
+            // Events can target text nodes, in which case we start from the text's parent.
+            let target = match target.dyn_into::<Element>() {
+                Ok(element) => Some(element),
+                Err(node) => node.parent_element(),
+            };
+
+            if let Some(target) = target {
+                let delegation = Delegation {
+                    delegated_events: &delegated_events,
+                    events_id_prop: &events_id_prop,
+                    events_id_prefix: &events_id_prefix,
+                    events: &events,
+                };
+                bubble_event(&delegation, &event, target.clone(), &root_node);
+
+                // After the handlers, so that renders that they schedule come first.
+                if listen_for == "input" {
+                    restore_controlled_value_after_render(&event, &target);
+                }
+            }
         };
         let callback = Box::new(callback) as Box<dyn FnMut(_)>;
         let callback = Closure::wrap(callback);
+        let listener = callback.as_ref().unchecked_ref::<js_sys::Function>();
 
-        self.root_node
-            .add_event_listener_with_callback(event, callback.as_ref().unchecked_ref())
-            .unwrap();
+        // A passive listener gets replaced once a handler that can cancel the event is inserted.
+        if let Some(previous) = self.event_delegation_listeners.get(listen_for) {
+            self.root_node
+                .remove_event_listener_with_callback(
+                    listen_for,
+                    previous.as_ref().as_ref().unchecked_ref(),
+                )
+                .unwrap();
+        }
+
+        add_delegated_listener(&self.root_node, listen_for, listener, passive);
+        self.events
+            .set_delegated_listener(listen_for, listener.clone(), passive);
 
         self.event_delegation_listeners
-            .insert(event, Box::new(callback));
+            .insert(listen_for, Box::new(callback));
     }
 }
 
-// Call the event, then call it on its parent, etc
-fn bubble_event(elem: web_sys::Element, mouse_event: MouseEvent, events: &EventsByNodeIdx) {
-    let events_id = Reflect::get(&elem, &EVENTS_ID_PROP.into()).unwrap();
-    let events_id = events_id.as_string();
+/// What a delegation listener needs in order to find the handlers for its DOM event.
+struct Delegation<'a> {
+    /// The delegated events that are powered by the DOM event.
+    delegated_events: &'a [&'static DelegatedEvent],
+    events_id_prop: &'a JsValue,
+    events_id_prefix: &'a str,
+    events: &'a EventsByNodeIdx,
+}
 
-    if let Some(events_id) = events_id {
-        let events_id = events_id.trim_start_matches(&events.events_id_props_prefix().to_string());
-        let node_id: u32 = events_id.parse().unwrap();
+// An event inside of a portal whose mount is inside of the root node reaches both the portal's
+// listener and the root node's listener, so we mark the event the first time that we see it.
+fn already_handled(event: &web_sys::Event, handled_prop: &JsValue) -> bool {
+    if Reflect::has(event, handled_prop).unwrap_or(false) {
+        return true;
+    }
+    Reflect::set(event, handled_prop, &JsValue::TRUE).unwrap();
 
-        let cb = events.get_event_handler(&node_id, &EventName::ONCLICK);
+    false
+}
 
-        if let Some(cb) = cb {
-            match cb {
-                EventHandler::NoArgs(no_args) => (no_args.borrow_mut())(),
-                EventHandler::MouseEvent(mouse) => {
-                    (mouse.borrow_mut())(mouse_event.clone());
-                }
-                _ => panic!(),
-            };
-        }
+// A form control that was rendered with a `value` is controlled by the application, so after the
+// user changes it we put back the rendered value, unless the application rendered a new one.
+//
+// This happens on the next animation frame, which comes after any render that the event's handlers
+// scheduled using a `RenderScheduler`, so a render of the value that the user typed leaves the
+// value and the caret alone.
+fn restore_controlled_value_after_render(event: &web_sys::Event, target: &Element) {
+    // Changing the value while an input method editor is composing text would cancel the
+    // composition.
+    let is_composing = Reflect::get(event, &"isComposing".into())
+        .map(|is_composing| is_composing.is_truthy())
+        .unwrap_or(false);
+    if is_composing || !Reflect::has(target, &CONTROLLED_VALUE_PROP.into()).unwrap_or(false) {
+        return;
     }
 
-    if !mouse_event.should_propagate().get() {
+    let target = target.clone();
+    let restore = Closure::once_into_js(move || restore_controlled_value(&target));
+
+    web_sys::window()
+        .unwrap()
+        .request_animation_frame(restore.unchecked_ref())
+        .unwrap();
+}
+
+// Put back the value that the form control was last rendered with.
+//
+// The caret keeps its distance from the end of the value, so when a typed character gets rejected
+// the caret ends up where the character would have been.
+fn restore_controlled_value(element: &Element) {
+    let rendered = match Reflect::get(element, &CONTROLLED_VALUE_PROP.into()) {
+        Ok(rendered) if rendered.is_string() => rendered,
+        _ => return,
+    };
+    let current = Reflect::get(element, &"value".into()).unwrap_or(JsValue::UNDEFINED);
+    if current == rendered {
         return;
     }
 
-    if let Some(parent) = elem.parent_node() {
-        if let Ok(parent) = parent.dyn_into() {
-            bubble_event(parent, mouse_event, events);
+    let current_len = current.as_string().unwrap_or_default().chars().count() as u32;
+    let rendered_len = rendered.as_string().unwrap_or_default().chars().count() as u32;
+    let selection = selection_range(element);
+
+    Reflect::set(element, &"value".into(), &rendered).unwrap();
+
+    if let Some((start, end)) = selection {
+        let from_end =
+            |position: u32| rendered_len.saturating_sub(current_len.saturating_sub(position));
+        set_selection_range(element, from_end(start), from_end(end));
+    }
+}
+
+// The selection of a focused text input or textarea.
+fn selection_range(element: &Element) -> Option<(u32, u32)> {
+    let document = element.owner_document()?;
+    if !document.active_element()?.is_same_node(Some(element)) {
+        return None;
+    }
+
+    // Inputs whose type has no caret, such as checkboxes, have no selection.
+    if let Some(input) = element.dyn_ref::<HtmlInputElement>() {
+        return Some((input.selection_start().ok()??, input.selection_end().ok()??));
+    }
+    if let Some(textarea) = element.dyn_ref::<HtmlTextAreaElement>() {
+        return Some((
+            textarea.selection_start().ok()??,
+            textarea.selection_end().ok()??,
+        ));
+    }
+
+    None
+}
+
+fn set_selection_range(element: &Element, start: u32, end: u32) {
+    if let Some(input) = element.dyn_ref::<HtmlInputElement>() {
+        let _ = input.set_selection_range(start, end);
+    } else if let Some(textarea) = element.dyn_ref::<HtmlTextAreaElement>() {
+        let _ = textarea.set_selection_range(start, end);
+    }
+}
+
+// Call the event on the target, then on its parent, etc, until we reach the root node or an event
+// handler stops the event from propagating.
+//
+// Events inside of a portal continue from the portal's placeholder comment, so they bubble up to the
+// portal's parent even though the portal's children are somewhere else in the DOM.
+//
+// An event that we listen for can power more than one delegated event. For example, the `focusin`
+// event powers both `onfocusin` and `onfocus`. Delegated events that don't bubble, such as
+// `onfocus`, only get called for the target element.
+fn bubble_event(
+    delegation: &Delegation,
+    event: &web_sys::Event,
+    target: Element,
+    root_node: &Node,
+) {
+    let mut current = Some(target.clone());
+
+    while let Some(elem) = current {
+        if let Some(node_idx) = node_idx(&elem, delegation) {
+            let is_target = elem.is_same_node(Some(&target));
+
+            for delegated in delegation.delegated_events {
+                if !(delegated.bubbles || is_target) {
+                    continue;
+                }
+
+                let handler = delegation
+                    .events
+                    .get_event_handler(&node_idx, &delegated.event_name);
+                if let Some(cb) = handler {
+                    cb.call_with_event(event);
+                }
+            }
+        }
+
+        // `cancelBubble` is set when an event handler calls `event.stop_propagation()`.
+        if event.cancel_bubble() {
+            return;
+        }
+
+        if root_node.is_same_node(Some(&elem)) {
+            return;
         }
+
+        current = match portal_placeholder(&elem) {
+            Some(placeholder) => placeholder.parent_element(),
+            None => elem.parent_element(),
+        };
     }
 }
+
+// Elements that have events store their node index, so that we can look up their event handlers.
+//
+// Elements that belong to a different PercyDom, such as when one PercyDom is nested inside of
+// another, have a different prefix so we ignore them.
+fn node_idx(elem: &Element, delegation: &Delegation) -> Option<u32> {
+    let events_id = Reflect::get(elem, delegation.events_id_prop)
+        .ok()?
+        .as_string()?;
+
+    events_id
+        .strip_prefix(delegation.events_id_prefix)?
+        .parse()
+        .ok()
+}
@@ -1,10 +1,15 @@
 //! Utilities to help with rendering.
 
 use crate::{PercyDom, VirtualNode};
-use std::{cell::Cell, rc::Rc};
+use std::cell::{Cell, OnceCell, RefCell};
+use std::rc::{Rc, Weak};
 use wasm_bindgen::prelude::Closure;
 use wasm_bindgen::JsCast;
 
+pub use self::manual_clock::ManualClock;
+
+mod manual_clock;
+
 /// Given a [`PercyDom`] and a function that renders a [`VirtualNode`],
 /// return a function that can call that render function up to once per browser
 /// animation frame.
@@ -18,6 +23,9 @@ use wasm_bindgen::JsCast;
 /// This is useful for when your application state changes and you want to schedule
 /// a re-render to occur on the next animation frame.
 ///
+/// If you need to flush or cancel a scheduled render, or get your `PercyDom` back, use a
+/// [`RenderScheduler`] instead.
+///
 /// # Example
 ///
 /// ```
@@ -55,34 +63,452 @@ use wasm_bindgen::JsCast;
 /// # fn make_percy_dom_somehow() -> PercyDom { unimplemented!() }
 /// ```
 pub fn create_render_scheduler<F: FnMut() -> VirtualNode + 'static>(
-    mut percy_dom: PercyDom,
-    mut render: F,
+    percy_dom: PercyDom,
+    render: F,
 ) -> Box<dyn FnMut()> {
-    let render_is_scheduled = Rc::new(Cell::new(false));
-    let render_is_scheduled_clone = render_is_scheduled.clone();
+    let scheduler = RenderScheduler::new(percy_dom, render);
+
+    Box::new(move || scheduler.schedule()) as Box<dyn FnMut() -> ()>
+}
+
+/// Schedules renders of a [`PercyDom`], rendering at most once per animation frame.
+///
+/// Scheduling a render when one is already scheduled does nothing. The scheduled render calls your
+/// render function and updates the `PercyDom` with the `VirtualNode` that it returns.
+///
+/// When the state of one of the `PercyDom`'s components changes, only the components whose state
+/// changed are re-rendered (see [`PercyDom::update_components`]), unless a full render is also
+/// scheduled.
+///
+/// Cloning a `RenderScheduler` gives you another handle to the same scheduler, so you can move it
+/// into your event handlers.
+///
+/// # Example
+///
+/// ```
+/// # use percy_dom::{prelude::*, render::RenderScheduler};
+/// fn start(pdom: PercyDom) {
+///     let scheduler = RenderScheduler::new(pdom, || html! { <div>Hello</div> });
+///
+///     // Renders on the next animation frame.
+///     scheduler.schedule();
+///
+///     // Or render right away, for example before measuring the layout.
+///     scheduler.flush();
+/// }
+/// ```
+///
+/// In tests you can use a [`ManualClock`] instead of the browser's animation frames, so that you
+/// decide when the next frame happens.
+///
+/// # Panics
+///
+/// A render panics if the `PercyDom` could not be patched, unless you used
+/// [`PercyDom::recover_from_patch_errors`] before creating the scheduler.
+#[derive(Clone)]
+pub struct RenderScheduler {
+    scheduler: Rc<Scheduler<App>>,
+}
+
+impl RenderScheduler {
+    /// Create a `RenderScheduler` that renders on the browser's next animation frame.
+    ///
+    /// Nothing gets rendered until you schedule a render.
+    pub fn new<F: FnMut() -> VirtualNode + 'static>(percy_dom: PercyDom, render: F) -> Self {
+        Self::with_clock(percy_dom, render, Clock::AnimationFrame)
+    }
+
+    /// Create a `RenderScheduler` whose scheduled renders happen when you call
+    /// [`ManualClock::next_frame`].
+    ///
+    /// Useful in tests, since you do not have to wait for a real animation frame.
+    pub fn with_manual_clock<F: FnMut() -> VirtualNode + 'static>(
+        percy_dom: PercyDom,
+        render: F,
+        clock: &ManualClock,
+    ) -> Self {
+        Self::with_clock(percy_dom, render, Clock::Manual(clock.clone()))
+    }
 
-    let render = move || {
-        render_is_scheduled.set(false);
+    fn with_clock<F: FnMut() -> VirtualNode + 'static>(
+        percy_dom: PercyDom,
+        render: F,
+        clock: Clock,
+    ) -> Self {
+        let app = App {
+            percy_dom,
+            render: Box::new(render),
+        };
+        let scheduler = Scheduler::new(app, clock);
 
-        let vdom = render();
-        percy_dom.update(vdom);
-    };
+        let weak = Rc::downgrade(&scheduler);
+        scheduler
+            .app
+            .borrow_mut()
+            .as_mut()
+            .unwrap()
+            .percy_dom
+            .on_component_state_change(move || {
+                if let Some(scheduler) = weak.upgrade() {
+                    scheduler.schedule(Pending::Components);
+                }
+            });
 
-    let render = Box::new(render) as Box<dyn FnMut()>;
-    let render = Closure::wrap(render);
+        RenderScheduler { scheduler }
+    }
 
-    let window = web_sys::window().unwrap();
-    let render_raf = move || {
-        if render_is_scheduled_clone.get() {
+    /// Schedule a render for the next frame, unless one is already scheduled.
+    pub fn schedule(&self) {
+        self.scheduler.schedule(Pending::Everything);
+    }
+
+    /// Whether or not a render is scheduled.
+    pub fn is_scheduled(&self) -> bool {
+        self.scheduler.pending.get() != Pending::Nothing
+    }
+
+    /// Render right away if a render is scheduled, instead of waiting for the next frame.
+    ///
+    /// Returns whether or not anything was rendered.
+    pub fn flush(&self) -> bool {
+        self.scheduler.flush()
+    }
+
+    /// Cancel the scheduled render, if there is one.
+    pub fn cancel(&self) {
+        self.scheduler.cancel();
+    }
+
+    /// Stop scheduling renders and return the `PercyDom`.
+    ///
+    /// Any scheduled render is canceled, and scheduling a render using this or any other handle to
+    /// the scheduler does nothing from now on.
+    ///
+    /// The `PercyDom`'s component state change listener is left in place but no longer does
+    /// anything, so you may want to set a new one with [`PercyDom::on_component_state_change`].
+    ///
+    /// Returns `None` if the `PercyDom` was already taken, or if it is being rendered.
+    pub fn take_percy_dom(&self) -> Option<PercyDom> {
+        self.scheduler.take_app().map(|app| app.percy_dom)
+    }
+}
+
+/// Something that a [`Scheduler`] renders.
+trait Render {
+    fn render(&mut self, pending: Pending);
+}
+
+struct App {
+    percy_dom: PercyDom,
+    render: Box<dyn FnMut() -> VirtualNode>,
+}
+
+impl Render for App {
+    fn render(&mut self, pending: Pending) {
+        let patched = match pending {
+            Pending::Everything => {
+                let vdom = (self.render)();
+                self.percy_dom.update(vdom)
+            }
+            Pending::Components => self.percy_dom.update_components(),
+            Pending::Nothing => Ok(()),
+        };
+
+        if let Err(err) = patched {
+            panic!("{}", err);
+        }
+    }
+}
+
+/// What needs to be rendered on the next frame.
+///
+/// Ordered so that a bigger render includes all of the smaller ones.
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+enum Pending {
+    Nothing,
+    Components,
+    Everything,
+}
+
+enum Clock {
+    AnimationFrame,
+    Manual(ManualClock),
+}
+
+struct Scheduler<R: Render> {
+    app: RefCell<Option<R>>,
+    app_taken: Cell<bool>,
+    pending: Cell<Pending>,
+    /// The ID of the requested frame, if one was requested.
+    frame_id: Cell<Option<i32>>,
+    clock: Clock,
+    /// Created the first time that we request an animation frame.
+    animation_frame_callback: OnceCell<Closure<dyn FnMut()>>,
+    this: Weak<Scheduler<R>>,
+}
+
+impl<R: Render + 'static> Scheduler<R> {
+    fn new(app: R, clock: Clock) -> Rc<Self> {
+        Rc::new_cyclic(|this| Scheduler {
+            app: RefCell::new(Some(app)),
+            app_taken: Cell::new(false),
+            pending: Cell::new(Pending::Nothing),
+            frame_id: Cell::new(None),
+            clock,
+            animation_frame_callback: OnceCell::new(),
+            this: this.clone(),
+        })
+    }
+
+    fn schedule(&self, render: Pending) {
+        if self.app_taken.get() || render <= self.pending.get() {
             return;
         }
 
-        render_is_scheduled_clone.set(true);
+        let already_requested = self.pending.get() != Pending::Nothing;
+        self.pending.set(render);
+
+        if !already_requested {
+            self.frame_id.set(Some(self.request_frame()));
+        }
+    }
+
+    fn request_frame(&self) -> i32 {
+        match &self.clock {
+            Clock::AnimationFrame => {
+                let callback = self.animation_frame_callback.get_or_init(|| {
+                    let this = self.this.clone();
+                    Closure::wrap(Box::new(move || {
+                        if let Some(scheduler) = this.upgrade() {
+                            scheduler.on_frame();
+                        }
+                    }) as Box<dyn FnMut()>)
+                });
+
+                web_sys::window()
+                    .unwrap()
+                    .request_animation_frame(callback.as_ref().unchecked_ref())
+                    .unwrap()
+            }
+            Clock::Manual(clock) => {
+                let this = self.this.clone();
+                clock.request_frame(move || {
+                    if let Some(scheduler) = this.upgrade() {
+                        scheduler.on_frame();
+                    }
+                })
+            }
+        }
+    }
+
+    fn on_frame(&self) {
+        self.frame_id.set(None);
+
+        // If we're already rendering, for example when the render function advances a manual
+        // clock, the pending render happens on the next frame.
+        let mut app = match self.app.try_borrow_mut() {
+            Ok(app) => app,
+            Err(_) => {
+                if self.pending.get() != Pending::Nothing {
+                    self.frame_id.set(Some(self.request_frame()));
+                }
+                return;
+            }
+        };
+
+        let pending = self.pending.replace(Pending::Nothing);
+        Self::render(&mut app, pending);
+    }
+
+    fn flush(&self) -> bool {
+        // If we're already rendering, for example when the render function flushes, the pending
+        // render stays scheduled for the next frame.
+        let mut app = match self.app.try_borrow_mut() {
+            Ok(app) => app,
+            Err(_) => return false,
+        };
+
+        let pending = self.pending.get();
+        self.cancel();
+
+        Self::render(&mut app, pending)
+    }
+
+    fn cancel(&self) {
+        self.pending.set(Pending::Nothing);
+
+        if let Some(frame_id) = self.frame_id.take() {
+            match &self.clock {
+                Clock::AnimationFrame => {
+                    web_sys::window()
+                        .unwrap()
+                        .cancel_animation_frame(frame_id)
+                        .unwrap();
+                }
+                Clock::Manual(clock) => clock.cancel_frame(frame_id),
+            }
+        }
+    }
+
+    /// Returns whether or not anything was rendered.
+    fn render(app: &mut Option<R>, pending: Pending) -> bool {
+        if pending == Pending::Nothing {
+            return false;
+        }
+
+        match app.as_mut() {
+            Some(app) => {
+                app.render(pending);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn take_app(&self) -> Option<R> {
+        let app = self.app.try_borrow_mut().ok()?.take()?;
+        self.cancel();
+        self.app_taken.set(true);
+
+        Some(app)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that scheduling more renders before the next frame only renders once.
+    #[test]
+    fn renders_once_per_frame() {
+        let clock = ManualClock::new();
+        let (scheduler, renders) = test_scheduler(&clock);
+
+        scheduler.schedule(Pending::Everything);
+        scheduler.schedule(Pending::Everything);
+        assert_eq!(*renders.borrow(), vec![]);
+
+        assert_eq!(clock.next_frame(), 1);
+        assert_eq!(*renders.borrow(), vec![Pending::Everything]);
+
+        assert_eq!(clock.next_frame(), 0);
+        assert_eq!(*renders.borrow(), vec![Pending::Everything]);
+    }
+
+    /// Verify that a full render that gets scheduled after a components render replaces it.
+    #[test]
+    fn full_render_replaces_components_render() {
+        let clock = ManualClock::new();
+        let (scheduler, renders) = test_scheduler(&clock);
+
+        scheduler.schedule(Pending::Components);
+        scheduler.schedule(Pending::Everything);
+        scheduler.schedule(Pending::Components);
+
+        clock.next_frame();
+        assert_eq!(*renders.borrow(), vec![Pending::Everything]);
+    }
+
+    /// Verify that flushing renders right away and cancels the requested frame.
+    #[test]
+    fn flush() {
+        let clock = ManualClock::new();
+        let (scheduler, renders) = test_scheduler(&clock);
+
+        assert!(!scheduler.flush());
+
+        scheduler.schedule(Pending::Everything);
+        assert!(scheduler.flush());
+        assert_eq!(*renders.borrow(), vec![Pending::Everything]);
+
+        assert!(!clock.has_pending_frame());
+    }
+
+    /// Verify that flushing while rendering keeps the render that was scheduled during the render.
+    #[test]
+    fn flush_while_rendering() {
+        let clock = ManualClock::new();
+        let renders = Rc::new(RefCell::new(vec![]));
+        // Lets the app use the scheduler while it renders.
+        let handle: Rc<RefCell<Weak<Scheduler<TestApp>>>> = Rc::new(RefCell::new(Weak::new()));
+
+        let handle_clone = handle.clone();
+        let app = TestApp {
+            renders: renders.clone(),
+            on_render: Box::new(move || {
+                let scheduler = handle_clone.borrow().upgrade();
+
+                if let Some(scheduler) = scheduler {
+                    scheduler.schedule(Pending::Components);
+                    assert!(!scheduler.flush());
+                    assert!(scheduler.take_app().is_none());
+                }
+            }),
+        };
+        let scheduler = Scheduler::new(app, Clock::Manual(clock.clone()));
+        *handle.borrow_mut() = Rc::downgrade(&scheduler);
+
+        scheduler.schedule(Pending::Everything);
+        assert!(scheduler.flush());
+        assert_eq!(*renders.borrow(), vec![Pending::Everything]);
+        assert!(clock.has_pending_frame());
+
+        *handle.borrow_mut() = Weak::new();
+        clock.next_frame();
+        assert_eq!(
+            *renders.borrow(),
+            vec![Pending::Everything, Pending::Components]
+        );
+    }
+
+    /// Verify that a canceled render does not happen.
+    #[test]
+    fn cancel() {
+        let clock = ManualClock::new();
+        let (scheduler, renders) = test_scheduler(&clock);
+
+        scheduler.schedule(Pending::Everything);
+        scheduler.cancel();
+
+        assert!(!clock.has_pending_frame());
+        assert!(!scheduler.flush());
+        assert_eq!(*renders.borrow(), vec![]);
+    }
+
+    /// Verify that once the app was taken we no longer schedule renders.
+    #[test]
+    fn take_app() {
+        let clock = ManualClock::new();
+        let (scheduler, renders) = test_scheduler(&clock);
+
+        scheduler.schedule(Pending::Everything);
+        assert!(scheduler.take_app().is_some());
+        assert!(scheduler.take_app().is_none());
+
+        scheduler.schedule(Pending::Everything);
+        assert!(!clock.has_pending_frame());
+        assert_eq!(*renders.borrow(), vec![]);
+    }
+
+    struct TestApp {
+        renders: Rc<RefCell<Vec<Pending>>>,
+        on_render: Box<dyn FnMut()>,
+    }
+
+    impl Render for TestApp {
+        fn render(&mut self, pending: Pending) {
+            self.renders.borrow_mut().push(pending);
+            (self.on_render)();
+        }
+    }
 
-        window
-            .request_animation_frame(render.as_ref().as_ref().unchecked_ref())
-            .unwrap();
-    };
+    fn test_scheduler(clock: &ManualClock) -> (Rc<Scheduler<TestApp>>, Rc<RefCell<Vec<Pending>>>) {
+        let renders = Rc::new(RefCell::new(vec![]));
+        let app = TestApp {
+            renders: renders.clone(),
+            on_render: Box::new(|| {}),
+        };
 
-    Box::new(render_raf) as Box<dyn FnMut() -> ()>
+        (Scheduler::new(app, Clock::Manual(clock.clone())), renders)
+    }
 }
@@ -0,0 +1,70 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// A clock whose frames only happen when you say so, typically used in tests instead of the
+/// browser's animation frames.
+///
+/// Cloning a `ManualClock` gives you another handle to the same clock.
+///
+/// ```
+/// # use percy_dom::render::ManualClock;
+/// let clock = ManualClock::new();
+/// assert_eq!(clock.next_frame(), 0);
+/// ```
+#[derive(Clone, Default)]
+pub struct ManualClock {
+    frames: Rc<RefCell<Frames>>,
+}
+
+#[derive(Default)]
+struct Frames {
+    next_id: i32,
+    /// The callbacks for the next frame, along with their IDs.
+    callbacks: Vec<(i32, Box<dyn FnOnce()>)>,
+}
+
+impl ManualClock {
+    /// Create a new `ManualClock`.
+    pub fn new() -> Self {
+        ManualClock::default()
+    }
+
+    /// Run the callbacks that were requested for the next frame.
+    ///
+    /// Callbacks that get requested while the frame is running run on the frame after it.
+    ///
+    /// Returns the number of callbacks that were run.
+    pub fn next_frame(&self) -> usize {
+        let callbacks = std::mem::take(&mut self.frames.borrow_mut().callbacks);
+        let count = callbacks.len();
+
+        for (_, callback) in callbacks {
+            callback();
+        }
+
+        count
+    }
+
+    /// Whether or not any callbacks were requested for the next frame.
+    pub fn has_pending_frame(&self) -> bool {
+        !self.frames.borrow().callbacks.is_empty()
+    }
+
+    /// Call the callback on the next frame, returning an ID that can be used to cancel it.
+    pub(crate) fn request_frame(&self, callback: impl FnOnce() + 'static) -> i32 {
+        let mut frames = self.frames.borrow_mut();
+
+        frames.next_id += 1;
+        let id = frames.next_id;
+        frames.callbacks.push((id, Box::new(callback)));
+
+        id
+    }
+
+    pub(crate) fn cancel_frame(&self, id: i32) {
+        self.frames
+            .borrow_mut()
+            .callbacks
+            .retain(|(callback_id, _)| *callback_id != id);
+    }
+}
@@ -0,0 +1,102 @@
+//! Verify that `bind:value`, `bind:checked` and `bind:group` store the state of the form control
+//! that the user changed.
+//!
+//! To run all tests in this file:
+//!
+//! wasm-pack test --chrome --headless crates/percy-dom --test bind
+
+use crate::testing_utilities::create_mount;
+use percy_dom::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+use web_sys::{HtmlInputElement, InputEvent};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+mod testing_utilities;
+
+/// Verify that `bind:value` stores the value that the user typed.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test bind -- bind_value
+#[wasm_bindgen_test]
+fn bind_value() {
+    let name = Rc::new(RefCell::new("Alice".to_string()));
+
+    let mount = create_mount();
+    let _pdom =
+        PercyDom::new_append_to_mount(html! { <div><input bind:value=name /></div> }, &mount);
+
+    let input = query_input(&mount, "input");
+    assert_eq!(input.value(), "Alice");
+
+    input.set_value("Bob");
+    dispatch_input(&input);
+
+    assert_eq!(&*name.borrow(), "Bob");
+}
+
+/// Verify that `bind:checked` stores whether or not the user checked the checkbox.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test bind -- bind_checked
+#[wasm_bindgen_test]
+fn bind_checked() {
+    let agreed = Rc::new(RefCell::new(false));
+
+    let mount = create_mount();
+    let _pdom = PercyDom::new_append_to_mount(
+        html! { <div><input type="checkbox" bind:checked=agreed /></div> },
+        &mount,
+    );
+
+    let checkbox = query_input(&mount, "input");
+    assert!(!checkbox.checked());
+
+    checkbox.set_checked(true);
+    dispatch_input(&checkbox);
+
+    assert!(*agreed.borrow());
+}
+
+/// Verify that `bind:group` stores the value of the radio button that the user checked.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test bind -- bind_group
+#[wasm_bindgen_test]
+fn bind_group() {
+    let color = Rc::new(RefCell::new("red".to_string()));
+
+    let mount = create_mount();
+    let _pdom = PercyDom::new_append_to_mount(
+        html! {
+            <div>
+              <input id="red" type="radio" name="color" value="red" bind:group=color />
+              <input id="blue" type="radio" name="color" value="blue" bind:group=color />
+            </div>
+        },
+        &mount,
+    );
+
+    assert!(query_input(&mount, "#red").checked());
+
+    let blue = query_input(&mount, "#blue");
+    blue.set_checked(true);
+    dispatch_input(&blue);
+
+    assert_eq!(&*color.borrow(), "blue");
+}
+
+fn query_input(mount: &web_sys::Element, selector: &str) -> HtmlInputElement {
+    mount
+        .query_selector(selector)
+        .unwrap()
+        .unwrap()
+        .dyn_into()
+        .unwrap()
+}
+
+fn dispatch_input(input: &HtmlInputElement) {
+    let event = InputEvent::new("input").unwrap();
+    event.init_event_with_bubbles("input", true);
+    input.dispatch_event(&event).unwrap();
+}
@@ -0,0 +1,123 @@
+//! Tests that ensure that components keep their state and only re-render when they need to.
+//!
+//! To run all tests in this file:
+//!
+//! wasm-pack test --chrome --headless crates/percy-dom --test components
+
+use crate::testing_utilities::{create_mount, get_element_by_id, random_id};
+use percy_dom::prelude::*;
+use percy_dom::PercyDom;
+use std::cell::Cell;
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+mod testing_utilities;
+
+/// Verify that updating a component's state calls the state change listener, and that updating
+/// the components re-renders the component and keeps its events working.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test components -- state_change_rerenders_component
+#[wasm_bindgen_test]
+fn state_change_rerenders_component() {
+    let id = random_id();
+    let renders = Rc::new(Cell::new(0));
+    let state_changes = Rc::new(Cell::new(0));
+
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(
+        html! { <div> <Counter id=id renders=renders.clone() /> </div> },
+        &mount,
+    );
+
+    let state_changes_clone = state_changes.clone();
+    pdom.on_component_state_change(move || state_changes_clone.set(state_changes_clone.get() + 1));
+
+    assert_eq!(button_text(id), "0");
+
+    click(id);
+    assert_eq!(state_changes.get(), 1);
+    assert_eq!(button_text(id), "0");
+
+    pdom.update_components().unwrap();
+    assert_eq!(button_text(id), "1");
+    assert_eq!(renders.get(), 2);
+
+    click(id);
+    assert_eq!(state_changes.get(), 2);
+
+    pdom.update_components().unwrap();
+    assert_eq!(button_text(id), "2");
+}
+
+/// Verify that a component whose props did not change keeps its state and is not rendered again
+/// when we update the `PercyDom`.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test components -- equal_props_not_rerendered
+#[wasm_bindgen_test]
+fn equal_props_not_rerendered() {
+    let id = random_id();
+    let renders = Rc::new(Cell::new(0));
+
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(
+        html! { <div> <Counter id=id renders=renders.clone() /> </div> },
+        &mount,
+    );
+
+    click(id);
+    pdom.update_components().unwrap();
+    assert_eq!(renders.get(), 2);
+
+    pdom.update(html! { <div> <em></em> <Counter id=id renders=renders.clone() /> </div> })
+        .unwrap();
+    assert_eq!(renders.get(), 2);
+    assert_eq!(button_text(id), "1");
+
+    // The button's events were moved since it now comes after the <em>.
+    click(id);
+    pdom.update_components().unwrap();
+    assert_eq!(button_text(id), "2");
+}
+
+/// A button that counts how many times it was clicked.
+struct Counter {
+    id: &'static str,
+    renders: Rc<Cell<u32>>,
+}
+
+impl PartialEq for Counter {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Component for Counter {
+    type State = u32;
+
+    fn render(&self, state: &ComponentState<u32>) -> VirtualNode {
+        self.renders.set(self.renders.get() + 1);
+
+        let count = state.get().to_string();
+        let state = state.clone();
+
+        html! {
+            <button id=self.id onclick=move || state.update(|count| *count += 1)>
+                {count}
+            </button>
+        }
+    }
+}
+
+fn button_text(id: &str) -> String {
+    get_element_by_id(id).text_content().unwrap()
+}
+
+fn click(id: &str) {
+    get_element_by_id(id)
+        .dyn_into::<web_sys::HtmlElement>()
+        .unwrap()
+        .click();
+}
@@ -0,0 +1,200 @@
+//! Verify that form controls that get rendered with a `value` are controlled by the application,
+//! and that patching them leaves the caret and selection alone.
+//!
+//! To run all tests in this file:
+//!
+//! wasm-pack test --chrome --headless crates/percy-dom --test controlled_input
+
+use std::cell::{OnceCell, RefCell};
+use std::rc::Rc;
+
+use crate::testing_utilities::{create_mount, next_animation_frame};
+use percy_dom::prelude::*;
+use percy_dom::render::RenderScheduler;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+use web_sys::{Element, HtmlInputElement, HtmlSelectElement, HtmlTextAreaElement, InputEvent};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+mod testing_utilities;
+
+/// Verify that when the user changes an input's value and the application does not render the new
+/// value, the rendered value gets put back.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test controlled_input -- restores_rendered_value
+#[wasm_bindgen_test]
+async fn restores_rendered_value() {
+    let mount = create_mount();
+    let _pdom = PercyDom::new_append_to_mount(html! { <input value="Hello" /> }, &mount);
+
+    let input: HtmlInputElement = query(&mount, "input");
+    input.set_value("Hello!");
+    dispatch_input(&input);
+
+    next_animation_frame().await;
+    assert_eq!(input.value(), "Hello");
+}
+
+/// Verify that a value that the user typed is kept when the application renders it, along with the
+/// caret's position.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test controlled_input -- keeps_rendered_input
+#[wasm_bindgen_test]
+async fn keeps_rendered_input() {
+    let text = Rc::new(RefCell::new("Hello".to_string()));
+    let scheduler: Rc<OnceCell<RenderScheduler>> = Rc::new(OnceCell::new());
+
+    let render = {
+        let (text, scheduler) = (text.clone(), scheduler.clone());
+        move || text_input(text.clone(), scheduler.clone())
+    };
+
+    let mount = create_mount();
+    let pdom = PercyDom::new_append_to_mount(render(), &mount);
+    let _ = scheduler.set(RenderScheduler::new(pdom, render));
+
+    let input: HtmlInputElement = query(&mount, "input");
+    input.focus().unwrap();
+    input.set_value("Hel!lo");
+    input.set_selection_range(4, 4).unwrap();
+    dispatch_input(&input);
+
+    next_animation_frame().await;
+    next_animation_frame().await;
+
+    assert_eq!(&*text.borrow(), "Hel!lo");
+    assert_eq!(input.value(), "Hel!lo");
+    assert_eq!(input.selection_start().unwrap(), Some(4));
+}
+
+/// Verify that when a typed character gets rejected, the caret ends up where the character would
+/// have been.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test controlled_input -- rejected_character_keeps_caret
+#[wasm_bindgen_test]
+async fn rejected_character_keeps_caret() {
+    let mount = create_mount();
+    let _pdom = PercyDom::new_append_to_mount(html! { <input value="abc" /> }, &mount);
+
+    let input: HtmlInputElement = query(&mount, "input");
+    input.focus().unwrap();
+    input.set_value("abXc");
+    input.set_selection_range(3, 3).unwrap();
+    dispatch_input(&input);
+
+    next_animation_frame().await;
+    assert_eq!(input.value(), "abc");
+    assert_eq!(input.selection_start().unwrap(), Some(2));
+}
+
+/// Verify that patching an input with the value that it already has keeps its selection.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test controlled_input -- same_value_keeps_selection
+#[wasm_bindgen_test]
+fn same_value_keeps_selection() {
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(
+        html! { <input value="Hello World" class="before" /> },
+        &mount,
+    );
+
+    let input: HtmlInputElement = query(&mount, "input");
+    input.focus().unwrap();
+    input.set_selection_range(2, 5).unwrap();
+
+    pdom.update(html! { <input value="Hello World" class="after" /> })
+        .unwrap();
+
+    assert_eq!(input.selection_start().unwrap(), Some(2));
+    assert_eq!(input.selection_end().unwrap(), Some(5));
+}
+
+/// Verify that a textarea's rendered value gets put back.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test controlled_input -- restores_textarea_value
+#[wasm_bindgen_test]
+async fn restores_textarea_value() {
+    let mount = create_mount();
+    let _pdom =
+        PercyDom::new_append_to_mount(html! { <textarea value="Hello"></textarea> }, &mount);
+
+    let textarea: HtmlTextAreaElement = query(&mount, "textarea");
+    textarea.set_value("Hello!");
+    dispatch_input(&textarea);
+
+    next_animation_frame().await;
+    assert_eq!(textarea.value(), "Hello");
+}
+
+/// Verify that a select's rendered value gets put back.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test controlled_input -- restores_select_value
+#[wasm_bindgen_test]
+async fn restores_select_value() {
+    let mount = create_mount();
+    let _pdom = PercyDom::new_append_to_mount(
+        html! {
+            <select value="a">
+              <option value="a">A</option>
+              <option value="b">B</option>
+            </select>
+        },
+        &mount,
+    );
+
+    let select: HtmlSelectElement = query(&mount, "select");
+    select.set_value("b");
+    dispatch_input(&select);
+
+    next_animation_frame().await;
+    assert_eq!(select.value(), "a");
+}
+
+/// Verify that an input that was not rendered with a value keeps whatever the user typed.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test controlled_input -- uncontrolled_input
+#[wasm_bindgen_test]
+async fn uncontrolled_input() {
+    let mount = create_mount();
+    let _pdom = PercyDom::new_append_to_mount(html! { <input /> }, &mount);
+
+    let input: HtmlInputElement = query(&mount, "input");
+    input.set_value("Typed");
+    dispatch_input(&input);
+
+    next_animation_frame().await;
+    assert_eq!(input.value(), "Typed");
+}
+
+/// An input whose value comes from the text, which gets updated and re-rendered on input.
+fn text_input(text: Rc<RefCell<String>>, scheduler: Rc<OnceCell<RenderScheduler>>) -> VirtualNode {
+    let value = text.borrow().clone();
+
+    html! {
+        <input
+          value=value
+          oninput=move |event: InputEvent| {
+              let input: HtmlInputElement = event.target().unwrap().dyn_into().unwrap();
+              *text.borrow_mut() = input.value();
+              scheduler.get().unwrap().schedule();
+          }
+        />
+    }
+}
+
+fn query<T: JsCast>(parent: &Element, selector: &str) -> T {
+    parent
+        .query_selector(selector)
+        .unwrap()
+        .unwrap()
+        .dyn_into()
+        .unwrap()
+}
+
+fn dispatch_input(target: &web_sys::EventTarget) {
+    let event = InputEvent::new("input").unwrap();
+    event.init_event_with_bubbles("input", true);
+
+    target.dispatch_event(&event).unwrap();
+}
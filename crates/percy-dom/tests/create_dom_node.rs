@@ -57,3 +57,64 @@ fn div_with_attributes() {
 
     assert_eq!(div.class_list().length(), 2);
 }
+
+/// Verify that the escaped HTML string that we generate on the server matches the HTML that the
+/// browser generates for the same element.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test create_dom_node -- escaped_string_matches_outer_html
+#[wasm_bindgen_test]
+fn escaped_string_matches_outer_html() {
+    let text = "</div><script>alert('hi')</script> & \"quotes\" \u{a0} héllo 🦀";
+    let title = "\" onmouseover=\"alert(1) & 'single' \u{a0} wörld";
+
+    let vdiv = html! { <div title=title>{text}</div> };
+    let div: Element = vdiv
+        .create_dom_node(0, &mut EventsByNodeIdx::new())
+        .unchecked_into();
+
+    assert_eq!(div.text_content().unwrap(), text);
+    assert_eq!(div.get_attribute("title").unwrap(), title);
+    assert_eq!(div.outer_html(), vdiv.to_string());
+}
+
+/// Verify that a fragment's children are placed directly into the fragment's parent, with text
+/// nodes on either side of the fragment's boundary kept separate.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test create_dom_node -- fragment_children
+#[wasm_bindgen_test]
+fn fragment_children() {
+    let vdiv = html! { <div>{"a"}<>{"b"}<em></em></><br /></div> };
+    let div: Element = vdiv
+        .create_dom_node(0, &mut EventsByNodeIdx::new())
+        .unchecked_into();
+
+    assert_eq!(&div.inner_html(), "a<!--ptns-->b<em></em><br>");
+}
+
+/// Verify that a root fragment gets created as a `DocumentFragment`.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test create_dom_node -- fragment_root
+#[wasm_bindgen_test]
+fn fragment_root() {
+    let vfragment = html! { <><td></td><td></td></> };
+    let fragment: web_sys::DocumentFragment = vfragment
+        .create_dom_node(0, &mut EventsByNodeIdx::new())
+        .unchecked_into();
+
+    assert_eq!(fragment.child_element_count(), 2);
+}
+
+/// Verify that a placeholder becomes a comment node, which does not need a separator between it
+/// and neighboring text nodes.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test create_dom_node -- placeholder_comment
+#[wasm_bindgen_test]
+fn placeholder_comment() {
+    let vdiv = html! { <div>{"a"}{None::<VirtualNode>}{"b"}</div> };
+    let div: Element = vdiv
+        .create_dom_node(0, &mut EventsByNodeIdx::new())
+        .unchecked_into();
+
+    assert_eq!(&div.inner_html(), "a<!-- -->b");
+    assert_eq!(div.outer_html(), vdiv.to_string());
+}
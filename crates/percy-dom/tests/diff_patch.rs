@@ -193,3 +193,174 @@ fn replace_text_with_element() {
     }
     .test();
 }
+
+#[wasm_bindgen_test]
+fn keyed_children() {
+    DiffPatchTest {
+        desc: "Insert a keyed child at the beginning",
+        old: html! { <ul> <li key="a">A</li> <li key="b">B</li> </ul> },
+        new: html! { <ul> <li key="z">Z</li> <li key="a">A</li> <li key="b">B</li> </ul> },
+        override_expected: None,
+    }
+    .test();
+
+    DiffPatchTest {
+        desc: "Remove a keyed child from the middle",
+        old: html! { <ul> <li key="a">A</li> <li key="b">B</li> <li key="c">C</li> </ul> },
+        new: html! { <ul> <li key="a">A</li> <li key="c">C</li> </ul> },
+        override_expected: None,
+    }
+    .test();
+
+    DiffPatchTest {
+        desc: "Reverse keyed children",
+        old: html! { <ul> <li key="a">A</li> <li key="b">B</li> <li key="c">C</li> </ul> },
+        new: html! { <ul> <li key="c">C</li> <li key="b">B</li> <li key="a">A</li> </ul> },
+        override_expected: None,
+    }
+    .test();
+
+    DiffPatchTest {
+        desc: "Move a keyed child to the end",
+        old: html! { <ul> <li key="a">A</li> <li key="b">B</li> <li key="c">C</li> </ul> },
+        new: html! { <ul> <li key="b">B</li> <li key="c">C</li> <li key="a">A</li> </ul> },
+        override_expected: None,
+    }
+    .test();
+
+    DiffPatchTest {
+        desc: "Insert, remove, move and patch keyed children",
+        old: html! {
+          <ul>
+            <li key="a">A <em>1</em></li>
+            <li key="b">B</li>
+            <li key="c">C</li>
+            <li key="d">D</li>
+          </ul>
+        },
+        new: html! {
+          <ul>
+            <li key="d">D2</li>
+            <li key="x">X</li>
+            <li key="a">A <strong>1</strong></li>
+            <li key="c">C</li>
+            <li key="y">Y</li>
+          </ul>
+        },
+        override_expected: None,
+    }
+    .test();
+}
+
+/// Verify that a fragment's children are patched as if they were the children of the fragment's
+/// parent.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test diff_patch -- fragments
+#[wasm_bindgen_test]
+fn fragments() {
+    DiffPatchTest {
+        desc: "Patch the children of a fragment",
+        old: html! { <table> <> <tr><td>A</td></tr> <tr><td>B</td></tr> </> </table> },
+        new: html! { <table> <> <tr><td>A</td></tr> <tr><td>C</td></tr> </> </table> },
+        override_expected: None,
+    }
+    .test();
+
+    DiffPatchTest {
+        desc: "Append to a fragment that is followed by a sibling",
+        old: html! { <div> <> <b>1</b> </> <em>2</em> </div> },
+        new: html! { <div> <> <b>1</b> <i>3</i> </> <em>2</em> <span>4</span> </div> },
+        override_expected: None,
+    }
+    .test();
+
+    DiffPatchTest {
+        desc: "Remove a fragment",
+        old: html! { <div> <b>1</b> <> <i>2</i> <em>3</em> </> </div> },
+        new: html! { <div> <b>1</b> </div> },
+        override_expected: None,
+    }
+    .test();
+}
+
+/// Verify that placeholder comments are patched like any other node, and that the siblings after
+/// them keep their positions.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test diff_patch -- placeholders
+#[wasm_bindgen_test]
+fn placeholders() {
+    DiffPatchTest {
+        desc: "Replace a placeholder with an element",
+        old: html! { <div> {None::<VirtualNode>} <span>1</span> </div> },
+        new: html! { <div> <em>0</em> <span>1</span> </div> },
+        override_expected: None,
+    }
+    .test();
+
+    DiffPatchTest {
+        desc: "Replace an element with a placeholder",
+        old: html! { <div> <em>0</em> <span>1</span> </div> },
+        new: html! { <div> {None::<VirtualNode>} <span>2</span> </div> },
+        override_expected: None,
+    }
+    .test();
+
+    DiffPatchTest {
+        desc: "Change text that comes after a placeholder",
+        old: html! { <div> {"a"} {None::<VirtualNode>} {"b"} </div> },
+        new: html! { <div> {"a"} {None::<VirtualNode>} {"c"} </div> },
+        override_expected: None,
+    }
+    .test();
+
+    DiffPatchTest {
+        desc: "Truncate the children after a placeholder",
+        old: html! { <div> <b></b> {None::<VirtualNode>} <i></i> </div> },
+        new: html! { <div> <b></b> {None::<VirtualNode>} </div> },
+        override_expected: None,
+    }
+    .test();
+}
+
+/// Verify that children that are truncated from one element and appended to another element get
+/// moved there.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test diff_patch -- truncated_children_moved
+#[wasm_bindgen_test]
+fn truncated_children_moved() {
+    DiffPatchTest {
+        desc: "Move the last item of one list to the end of another list",
+        old: html! {
+          <div>
+            <ul> <li>A</li> <li>B</li> </ul>
+            <ul> <li>C</li> </ul>
+          </div>
+        },
+        new: html! {
+          <div>
+            <ul> <li>A</li> </ul>
+            <ul> <li>C</li> <li>B</li> </ul>
+          </div>
+        },
+        override_expected: None,
+    }
+    .test();
+
+    DiffPatchTest {
+        desc: "Move a truncated item in between appended items",
+        old: html! {
+          <div>
+            <ul> <li>A</li> <li>B</li> </ul>
+            <ul></ul>
+          </div>
+        },
+        new: html! {
+          <div>
+            <ul> <li>A</li> </ul>
+            <ul> <li>D</li> <li>B</li> <li>E</li> </ul>
+          </div>
+        },
+        override_expected: None,
+    }
+    .test();
+}
@@ -0,0 +1,158 @@
+//! Verify that attributes that reflect state that the user can change, such as whether or not a
+//! checkbox is checked, get set as DOM properties.
+//!
+//! To run all tests in this file:
+//!
+//! wasm-pack test --chrome --headless crates/percy-dom --test dom_property
+
+use crate::testing_utilities::create_mount;
+use percy_dom::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+use web_sys::{Element, HtmlInputElement, HtmlMediaElement, HtmlSelectElement};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+mod testing_utilities;
+
+/// Verify that we uncheck a checkbox that the user checked, even though the `checked` attribute
+/// did not change.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test dom_property -- checked_reasserted_after_click
+#[wasm_bindgen_test]
+fn checked_reasserted_after_click() {
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(checkbox(false), &mount);
+
+    let input: HtmlInputElement = query(&mount, "input");
+    input.click();
+    assert!(input.checked());
+
+    pdom.update(checkbox(false)).unwrap();
+    assert!(!input.checked());
+
+    pdom.update(checkbox(true)).unwrap();
+    assert!(input.checked());
+}
+
+/// Verify that we uncheck a checkbox when the `checked` attribute gets removed.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test dom_property -- checked_removed
+#[wasm_bindgen_test]
+fn checked_removed() {
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(checkbox(true), &mount);
+
+    let input: HtmlInputElement = query(&mount, "input");
+    assert!(input.checked());
+
+    pdom.update(html! { <input type="checkbox" /> }).unwrap();
+    assert!(!input.checked());
+}
+
+/// Verify that we set `indeterminate`, which has no HTML attribute, as a DOM property.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test dom_property -- indeterminate
+#[wasm_bindgen_test]
+fn indeterminate() {
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(
+        html! { <input type="checkbox" indeterminate=true /> },
+        &mount,
+    );
+
+    let input: HtmlInputElement = query(&mount, "input");
+    assert!(input.indeterminate());
+    assert!(!input.has_attribute("indeterminate"));
+
+    pdom.update(html! { <input type="checkbox" /> }).unwrap();
+    assert!(!input.indeterminate());
+}
+
+/// Verify that we set a select's value after creating its options.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test dom_property -- select_value
+#[wasm_bindgen_test]
+fn select_value() {
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(
+        html! {
+            <select value="b">
+                <option value="a">A</option>
+                <option value="b">B</option>
+            </select>
+        },
+        &mount,
+    );
+
+    let select: HtmlSelectElement = query(&mount, "select");
+    assert_eq!(select.value(), "b");
+
+    select.set_value("a");
+    pdom.update(html! {
+        <select value="c">
+            <option value="a">A</option>
+            <option value="b">B</option>
+            <option value="c">C</option>
+        </select>
+    })
+    .unwrap();
+    assert_eq!(select.value(), "c");
+}
+
+/// Verify that we set `muted`, which the user can toggle using the media controls.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test dom_property -- muted
+#[wasm_bindgen_test]
+fn muted() {
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(html! { <video muted=true></video> }, &mount);
+
+    let video: HtmlMediaElement = query(&mount, "video");
+    assert!(video.muted());
+
+    video.set_muted(false);
+    pdom.update(html! { <video muted=true></video> }).unwrap();
+    assert!(video.muted());
+}
+
+/// Verify that we set `scrollTop`, which has no HTML attribute, as a DOM property.
+///
+/// An element can only be scrolled once it is in the document, so we only check patching it.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test dom_property -- scroll_top
+#[wasm_bindgen_test]
+fn scroll_top() {
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(scrollable(0), &mount);
+
+    let div: Element = query(&mount, "div");
+
+    pdom.update(scrollable(20)).unwrap();
+    assert_eq!(div.scroll_top(), 20);
+    assert!(!div.has_attribute("scrollTop"));
+
+    div.set_scroll_top(0);
+    pdom.update(scrollable(20)).unwrap();
+    assert_eq!(div.scroll_top(), 20);
+}
+
+fn checkbox(checked: bool) -> VirtualNode {
+    html! { <input type="checkbox" checked=checked /> }
+}
+
+fn scrollable(scroll_top: u32) -> VirtualNode {
+    html! {
+        <div style="height: 10px; overflow: scroll;" scrollTop=scroll_top>
+            <span style="display: block; height: 100px;"></span>
+        </div>
+    }
+}
+
+fn query<T: JsCast>(mount: &Element, selector: &str) -> T {
+    mount
+        .query_selector(selector)
+        .unwrap()
+        .unwrap()
+        .unchecked_into()
+}
@@ -64,7 +64,7 @@ fn click_event_works() {
 /// wasm-pack test --chrome --headless crates/percy-dom --test events -- updated_non_delegated_event_handler
 #[wasm_bindgen_test]
 fn updated_non_delegated_event_handler() {
-    let event = EventName::ONINPUT;
+    let event = EventName::new("onfoobar".into());
     assert_eq!(event.is_delegated(), false);
 
     let id = random_id();
@@ -80,10 +80,11 @@ fn updated_non_delegated_event_handler() {
         vec![event.clone()],
         text.clone(),
         APPEND_TEXT_TWO,
-    ));
+    ))
+    .unwrap();
 
     assert_text_unmodified(&text);
-    send_input_event(id);
+    send_foobar_event(id);
     assert_text_appended(&text, APPEND_TEXT_TWO);
 }
 
@@ -109,7 +110,8 @@ fn updated_delegated_event_handler() {
         vec![event.clone()],
         text.clone(),
         APPEND_TEXT_TWO,
-    ));
+    ))
+    .unwrap();
 
     assert_text_unmodified(&text);
     send_click_event(id);
@@ -141,8 +143,8 @@ fn removes_old_non_delegated_event_listeners() {
     // We hold onto the old closures so that they don't get invalidated.
     let old_event = pdom.events.__get_event_wrapper_clone(&0, &event);
 
-    pdom.update(two);
-    pdom.update(three);
+    pdom.update(two).unwrap();
+    pdom.update(three).unwrap();
 
     assert_text_unmodified(&text);
     send_foobar_event(id);
@@ -399,7 +401,7 @@ fn patch_remove_events_id() {
 /// wasm-pack test --chrome --headless crates/percy-dom --test events -- patch_add_non_delegated_event_listener
 #[wasm_bindgen_test]
 fn patch_add_non_delegated_event_listener() {
-    let event = EventName::ONINPUT;
+    let event = EventName::new("onfoobar".into());
     assert_eq!(event.is_delegated(), false);
 
     let id = random_id();
@@ -412,10 +414,11 @@ fn patch_add_non_delegated_event_listener() {
         vec![event.clone()],
         text.clone(),
         APPEND_TEXT_TWO,
-    ));
+    ))
+    .unwrap();
 
     assert_text_unmodified(&text);
-    send_input_event(id);
+    send_foobar_event(id);
     assert_text_appended(&text, APPEND_TEXT_TWO);
 }
 
@@ -425,7 +428,7 @@ fn patch_add_non_delegated_event_listener() {
 /// wasm-pack test --chrome --headless crates/percy-dom --test events -- patch_remove_non_delegated_event_listener
 #[wasm_bindgen_test]
 fn patch_remove_non_delegated_event_listener() {
-    let event = EventName::ONINPUT;
+    let event = EventName::new("onfoobar".into());
     assert_eq!(event.is_delegated(), false);
 
     let id = random_id();
@@ -438,11 +441,11 @@ fn patch_remove_non_delegated_event_listener() {
     );
 
     assert!(pdom.events.get_event_handler(&0, &event).is_some());
-    pdom.update(html! { <input id=id onclick=|| {} />});
+    pdom.update(html! { <input id=id onclick=|| {} />}).unwrap();
     assert!(pdom.events.get_event_handler(&0, &event).is_none());
 
     assert_text_unmodified(&text);
-    send_input_event(id);
+    send_foobar_event(id);
     assert_text_unmodified(&text);
 }
 
@@ -465,7 +468,8 @@ fn patch_add_delegated_event_listener() {
         vec![event.clone()],
         text.clone(),
         APPEND_TEXT_TWO,
-    ));
+    ))
+    .unwrap();
 
     assert_text_unmodified(&text);
     send_click_event(id);
@@ -491,7 +495,8 @@ fn patch_remove_delegated_event_listener() {
     );
 
     assert!(pdom.events.get_event_handler(&0, &event).is_some());
-    pdom.update(html! { <div id=id oninput=||{}></div>});
+    pdom.update(html! { <div id=id oninput=||{}></div>})
+        .unwrap();
     assert!(pdom.events.get_event_handler(&0, &event).is_none());
 
     assert_text_unmodified(&text);
@@ -522,6 +527,33 @@ fn patch_remove_all_events_with_node_idx() {
         .is_none());
 }
 
+/// Verify that our patch for removing all managed events for a range of node indices works.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test events -- patch_remove_all_events_in_range
+#[wasm_bindgen_test]
+fn patch_remove_all_events_in_range() {
+    let mut events = EventsByNodeIdx::new();
+    for node_idx in [0, 2] {
+        events.insert_managed_event(
+            node_idx,
+            EventName::ONCLICK,
+            ManagedEvent::Delegated(EventHandler::UnsupportedSignature(EventAttribFn(Rc::new(
+                JsValue::NULL,
+            )))),
+        );
+    }
+    let patch = Patch::RemoveAllManagedEventsInRange {
+        first_idx: 0,
+        last_idx: 2,
+    };
+
+    let node = VirtualNode::element("div").create_dom_node(0, &mut events);
+    percy_dom::patch(node, &VirtualNode::text("..."), &mut events, &[patch]).unwrap();
+
+    assert!(events.get_event_handler(&0, &EventName::ONCLICK).is_none());
+    assert!(events.get_event_handler(&2, &EventName::ONCLICK).is_none());
+}
+
 /// Verify that we can create a closure that does not have any arguments.
 ///
 /// wasm-pack test --chrome --headless crates/percy-dom --test events -- closure_with_no_arguments
@@ -626,6 +658,229 @@ fn stop_propagation_on_delegated_event() {
     assert_eq!(called.get(), false);
 }
 
+/// Verify that when keyed children swap places their events move along with them.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test events -- keyed_children_events_follow_moved_nodes
+#[wasm_bindgen_test]
+fn keyed_children_events_follow_moved_nodes() {
+    let a_id = random_id();
+    let b_id = random_id();
+    let a_text = start_text();
+    let b_text = start_text();
+
+    let list = |first: VirtualNode, second: VirtualNode| {
+        let mut ul = VElement::new("ul");
+        ul.children.push(first);
+        ul.children.push(second);
+        VirtualNode::Element(ul)
+    };
+    let keyed = |key: &str, id: &str, text: Rc<RefCell<String>>| {
+        let mut node = div_node_with_event(id, vec![EventName::ONCLICK], text, APPEND_TEXT_ONE);
+        node.as_velement_mut()
+            .unwrap()
+            .attrs
+            .insert("key".to_string(), key.into());
+        node
+    };
+
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_replace_mount(
+        list(
+            keyed("a", a_id, a_text.clone()),
+            keyed("b", b_id, b_text.clone()),
+        ),
+        mount,
+    );
+    pdom.update(list(
+        keyed("b", b_id, b_text.clone()),
+        keyed("a", a_id, a_text.clone()),
+    ))
+    .unwrap();
+
+    send_click_event(a_id);
+    assert_text_appended(&a_text, APPEND_TEXT_ONE);
+    assert_text_unmodified(&b_text);
+
+    send_click_event(b_id);
+    assert_text_appended(&b_text, APPEND_TEXT_ONE);
+}
+
+/// Verify that the standard bubbling events are delegated, and that events that don't bubble and
+/// don't have a bubbling counterpart are not.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test events -- delegated_event_names
+#[wasm_bindgen_test]
+fn delegated_event_names() {
+    for event in [
+        "onclick",
+        "oninput",
+        "onkeydown",
+        "onmouseover",
+        "onsubmit",
+        "onfocus",
+    ]
+    .iter()
+    {
+        assert!(EventName::from(*event).is_delegated(), "{}", event);
+    }
+
+    for event in [
+        "onmouseenter",
+        "onmouseleave",
+        "onscroll",
+        "onload",
+        "onfoobar",
+    ]
+    .iter()
+    {
+        assert!(!EventName::from(*event).is_delegated(), "{}", event);
+    }
+}
+
+/// Verify that stop propagation works on delegated events that are not mouse events.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test events -- stop_propagation_on_delegated_input_event
+#[wasm_bindgen_test]
+fn stop_propagation_on_delegated_input_event() {
+    assert!(EventName::ONINPUT.is_delegated());
+
+    let parent_called = Rc::new(Cell::new(false));
+    let parent_called_clone = parent_called.clone();
+    let child_called = Rc::new(Cell::new(false));
+    let child_called_clone = child_called.clone();
+
+    let id = random_id();
+
+    let node: VirtualNode = html! {
+        <div oninput=move || { parent_called_clone.set(true); }>
+          <input
+            id=id
+            oninput=move |event: web_sys::InputEvent| {
+              child_called_clone.set(true);
+              event.stop_propagation();
+            }
+          />
+        </div>
+    };
+    let mount = create_mount();
+    let _pdom = PercyDom::new_replace_mount(node, mount);
+
+    send_input_event(id);
+    assert_eq!(child_called.get(), true);
+    assert_eq!(parent_called.get(), false);
+}
+
+/// Verify that the `onfocus` event only gets called for the element that received focus, since
+/// the focus event does not bubble, while `onfocusin` gets called for its ancestors.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test events -- focus_does_not_bubble
+#[wasm_bindgen_test]
+fn focus_does_not_bubble() {
+    let parent_focus = Rc::new(Cell::new(false));
+    let parent_focus_clone = parent_focus.clone();
+    let parent_focusin = Rc::new(Cell::new(false));
+    let parent_focusin_clone = parent_focusin.clone();
+    let child_focus = Rc::new(Cell::new(false));
+    let child_focus_clone = child_focus.clone();
+
+    let id = random_id();
+
+    let node: VirtualNode = html! {
+        <div
+          onfocus=move || { parent_focus_clone.set(true); }
+          onfocusin=move || { parent_focusin_clone.set(true); }
+        >
+          <input id=id onfocus=move || { child_focus_clone.set(true); } />
+        </div>
+    };
+    let mount = create_mount();
+    let _pdom = PercyDom::new_replace_mount(node, mount);
+
+    let focusin = web_sys::Event::new("focusin").unwrap();
+    focusin.init_event_with_bubbles("focusin", true);
+    send_event::<web_sys::Element>(id, &focusin);
+
+    assert_eq!(child_focus.get(), true);
+    assert_eq!(parent_focusin.get(), true);
+    assert_eq!(parent_focus.get(), false);
+}
+
+/// Verify that events that don't bubble and aren't delegated, such as `onmouseenter`, are attached
+/// directly to the element.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test events -- non_bubbling_event_attached_to_element
+#[wasm_bindgen_test]
+fn non_bubbling_event_attached_to_element() {
+    let called = Rc::new(Cell::new(false));
+    let called_clone = called.clone();
+
+    let id = random_id();
+
+    let node: VirtualNode = html! {
+        <div>
+          <span id=id onmouseenter=move || { called_clone.set(true); }></span>
+        </div>
+    };
+    let mount = create_mount();
+    let _pdom = PercyDom::new_replace_mount(node, mount);
+
+    send_event::<web_sys::Element>(id, &web_sys::Event::new("mouseenter").unwrap());
+    assert_eq!(called.get(), true);
+}
+
+/// Verify that a delegated listener only gets attached once one of its events has a handler.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test events -- delegated_listener_attached_for_first_handler
+#[wasm_bindgen_test]
+fn delegated_listener_attached_for_first_handler() {
+    let id = random_id();
+    let text = start_text();
+
+    let mount = create_mount();
+    let mut pdom =
+        PercyDom::new_replace_mount(div_node_with_event(id, vec![], text.clone(), ""), mount);
+    let handled_prop: JsValue =
+        format!("__percy_handled_{}__", pdom.events.events_id_props_prefix()).into();
+
+    let click = web_sys::Event::new("click").unwrap();
+    click.init_event_with_bubbles("click", true);
+    send_event::<web_sys::Element>(id, &click);
+    assert!(!js_sys::Reflect::has(&click, &handled_prop).unwrap());
+
+    pdom.update(div_node_with_event(
+        id,
+        vec![EventName::ONCLICK],
+        text.clone(),
+        APPEND_TEXT_ONE,
+    ))
+    .unwrap();
+
+    send_click_event(id);
+    assert_text_appended(&text, APPEND_TEXT_ONE);
+}
+
+/// Verify that a passive wheel listener gets replaced once a wheel handler that can prevent the
+/// default action is inserted.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test events -- wheel_handler_can_prevent_default
+#[wasm_bindgen_test]
+fn wheel_handler_can_prevent_default() {
+    let id = random_id();
+
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_replace_mount(html! { <div id=id onwheel=|| {}></div> }, mount);
+    pdom.update(html! {
+        <div id=id onwheel=|event: web_sys::WheelEvent| { event.prevent_default(); }></div>
+    })
+    .unwrap();
+
+    let wheel = web_sys::Event::new("wheel").unwrap();
+    wheel.init_event_with_bubbles_and_cancelable("wheel", true, true);
+    send_event::<web_sys::Element>(id, &wheel);
+
+    assert!(wheel.default_prevented());
+}
+
 fn input_node_with_events(
     id: &str,
     events: Vec<EventName>,
@@ -696,7 +951,10 @@ fn assert_text_appended(text: &Rc<RefCell<String>>, append: &str) {
 }
 
 fn send_input_event(id: &str) {
-    send_event::<web_sys::HtmlInputElement>(id, &web_sys::InputEvent::new("input").unwrap());
+    let input_event = web_sys::InputEvent::new("input").unwrap();
+    input_event.init_event_with_bubbles("input", true);
+
+    send_event::<web_sys::HtmlInputElement>(id, &input_event);
 }
 
 fn send_foobar_event(id: &str) {
@@ -0,0 +1,235 @@
+//! Tests that ensure that we can hydrate DOM that was rendered from an HTML string.
+//!
+//! To run all tests in this file:
+//!
+//! wasm-pack test --chrome --headless crates/percy-dom --test hydrate
+
+use crate::testing_utilities::{create_mount, get_element_by_id, random_id};
+use percy_dom::prelude::*;
+use percy_dom::{HydrationMismatch, HydrationMismatchKind};
+use std::cell::Cell;
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+use web_sys::{Element, HtmlElement};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+mod testing_utilities;
+
+/// Verify that we reuse the DOM nodes that are already in the page instead of creating new ones.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test hydrate -- hydrate_reuses_dom_nodes
+#[wasm_bindgen_test]
+fn hydrate_reuses_dom_nodes() {
+    let id = random_id();
+    let vdom = || html! { <div> <span id=id>Hello</span> <em>world</em> </div> };
+
+    let mount = server_render(&vdom());
+    let span = get_element_by_id(id);
+
+    let (pdom, mismatches) = PercyDom::hydrate(vdom(), mount.clone());
+
+    assert_eq!(mismatches, vec![]);
+    assert!(pdom.root_node().is_same_node(Some(&mount)));
+    assert!(get_element_by_id(id).is_same_node(Some(&span)));
+}
+
+/// Verify that we reuse the placeholder comments that were rendered on the server.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test hydrate -- hydrate_placeholder
+#[wasm_bindgen_test]
+fn hydrate_placeholder() {
+    let id = random_id();
+    let vdom = || html! { <div> {None::<VirtualNode>} <span id=id></span> </div> };
+
+    let mount = server_render(&vdom());
+    let placeholder = mount.first_child().unwrap();
+
+    let (_pdom, mismatches) = PercyDom::hydrate(vdom(), mount.clone());
+
+    assert_eq!(mismatches, vec![]);
+    assert!(mount
+        .first_child()
+        .unwrap()
+        .is_same_node(Some(&placeholder)));
+    assert_eq!(placeholder.node_type(), web_sys::Node::COMMENT_NODE);
+}
+
+/// Verify that we attach events to hydrated elements.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test hydrate -- hydrate_attaches_events
+#[wasm_bindgen_test]
+fn hydrate_attaches_events() {
+    let id = random_id();
+    let clicked = Rc::new(Cell::new(false));
+
+    let vdom = {
+        let clicked = clicked.clone();
+        html! { <div> <button id=id onclick=move || { clicked.set(true) }></button> </div> }
+    };
+
+    let mount = server_render(&vdom);
+    let (_pdom, _) = PercyDom::hydrate(vdom, mount);
+
+    get_element_by_id(id)
+        .unchecked_into::<HtmlElement>()
+        .click();
+    assert!(clicked.get());
+}
+
+/// Verify that we call the on create element functions of hydrated elements.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test hydrate -- hydrate_calls_on_create_element
+#[wasm_bindgen_test]
+fn hydrate_calls_on_create_element() {
+    let id = random_id();
+
+    let mut vdom = html! { <div> <span id=id></span> </div> };
+    vdom.as_velement_mut().unwrap().children[0]
+        .as_velement_mut()
+        .unwrap()
+        .special_attributes
+        .set_on_create_element("key", |elem: Element| {
+            elem.set_attribute("data-created", "").unwrap();
+        });
+
+    let mount = server_render(&vdom);
+    let (_pdom, _) = PercyDom::hydrate(vdom, mount);
+
+    assert!(get_element_by_id(id).has_attribute("data-created"));
+}
+
+/// Verify that we split up neighboring text nodes that the browser merged when parsing the HTML
+/// string, so that they can be patched later.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test hydrate -- hydrate_text_siblings
+#[wasm_bindgen_test]
+fn hydrate_text_siblings() {
+    let first = "First";
+    let second = "Second";
+    let vdom = html! { <div>{first}{second}<em>Third</em></div> };
+
+    let mount = server_render(&vdom);
+    assert_eq!(mount.child_nodes().length(), 2);
+
+    let (mut pdom, mismatches) = PercyDom::hydrate(vdom, mount.clone());
+    assert_eq!(mismatches, vec![]);
+
+    let updated = "Updated";
+    pdom.update(html! { <div>{first}{updated}<em>Third</em></div> })
+        .unwrap();
+
+    assert_eq!(mount.inner_html(), "First<!--ptns-->Updated<em>Third</em>");
+}
+
+/// Verify that we repair and report differences between the DOM and the virtual dom.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test hydrate -- hydrate_repairs_mismatches
+#[wasm_bindgen_test]
+fn hydrate_repairs_mismatches() {
+    let server = html! {
+        <div class="server">
+          <span>Server text</span>
+          <em></em>
+          <strong></strong>
+        </div>
+    };
+    let client = html! {
+        <div class="client">
+          <span>Client text</span>
+          <b></b>
+        </div>
+    };
+    let expected_html = client.to_string();
+
+    let mount = server_render(&server);
+    let (_pdom, mismatches) = PercyDom::hydrate(client, mount.clone());
+
+    assert_eq!(mount.outer_html(), expected_html);
+    assert_eq!(
+        mismatches,
+        vec![
+            HydrationMismatch {
+                node_idx: 0,
+                kind: HydrationMismatchKind::Attribute {
+                    name: "class".to_string(),
+                    expected: Some("client".to_string()),
+                    found: Some("server".to_string()),
+                },
+            },
+            HydrationMismatch {
+                node_idx: 2,
+                kind: HydrationMismatchKind::Text {
+                    expected: "Client text".to_string(),
+                    found: "Server text".to_string(),
+                },
+            },
+            HydrationMismatch {
+                node_idx: 3,
+                kind: HydrationMismatchKind::WrongNode {
+                    expected: "<b>".to_string(),
+                    found: "<em>".to_string(),
+                },
+            },
+            HydrationMismatch {
+                node_idx: 0,
+                kind: HydrationMismatchKind::ExtraNode {
+                    found: "<strong>".to_string(),
+                },
+            },
+        ]
+    );
+}
+
+/// Verify that we compare the inline styles property by property.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test hydrate -- hydrate_styles
+#[wasm_bindgen_test]
+fn hydrate_styles() {
+    let mount = server_render(&html! { <div style="color: red; width: 5px"></div> });
+    let (_pdom, mismatches) = PercyDom::hydrate(
+        html! { <div style="color:red;width:5px;"></div> },
+        mount.clone(),
+    );
+    assert_eq!(mismatches, vec![]);
+
+    let mount = server_render(&html! { <div style="color: red"></div> });
+    let (_pdom, mismatches) = PercyDom::hydrate(html! { <div style="color: blue"></div> }, mount);
+    assert_eq!(
+        mismatches,
+        vec![HydrationMismatch {
+            node_idx: 0,
+            kind: HydrationMismatchKind::Attribute {
+                name: "style".to_string(),
+                expected: Some("color: blue".to_string()),
+                found: Some("color: red".to_string()),
+            },
+        }]
+    );
+}
+
+/// Verify that if the mount does not match the root virtual node we replace it.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test hydrate -- hydrate_replaces_mismatched_root
+#[wasm_bindgen_test]
+fn hydrate_replaces_mismatched_root() {
+    let id = random_id();
+
+    let mount = server_render(&html! { <div></div> });
+    let (pdom, mismatches) = PercyDom::hydrate(html! { <section id=id></section> }, mount);
+
+    assert_eq!(mismatches.len(), 1);
+    assert!(pdom.root_node().is_same_node(Some(&get_element_by_id(id))));
+}
+
+/// Render the virtual node to a string and parse it into the page, similar to a browser loading
+/// server rendered HTML.
+///
+/// Returns the root element.
+fn server_render(vdom: &VirtualNode) -> Element {
+    let container = create_mount();
+    container.set_inner_html(&vdom.to_string());
+
+    container.first_element_child().unwrap()
+}
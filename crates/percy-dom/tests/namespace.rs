@@ -0,0 +1,142 @@
+//! Verify that elements get created in the namespace that they inherit from their parent.
+//!
+//! To run all tests in this file:
+//!
+//! wasm-pack test --chrome --headless crates/percy-dom --test namespace
+
+use crate::testing_utilities::create_mount;
+use percy_dom::prelude::*;
+use wasm_bindgen_test::*;
+use web_sys::Element;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+mod testing_utilities;
+
+const HTML: &str = "http://www.w3.org/1999/xhtml";
+const SVG: &str = "http://www.w3.org/2000/svg";
+const MATHML: &str = "http://www.w3.org/1998/Math/MathML";
+
+/// Verify that elements such as `<a>` and `<title>`, which exist in both HTML and SVG, are SVG
+/// elements when they are inside of an `<svg>`.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test namespace -- inherit_svg_namespace
+#[wasm_bindgen_test]
+fn inherit_svg_namespace() {
+    let mount = create_mount();
+    PercyDom::new_append_to_mount(
+        html! {
+          <div>
+            <a></a>
+            <svg>
+              <a><circle /></a>
+              <title>Circle</title>
+            </svg>
+          </div>
+        },
+        &mount,
+    );
+
+    assert_eq!(namespace(&mount, "div > a"), HTML);
+    assert_eq!(namespace(&mount, "svg"), SVG);
+    assert_eq!(namespace(&mount, "svg > a"), SVG);
+    assert_eq!(namespace(&mount, "circle"), SVG);
+    assert_eq!(namespace(&mount, "title"), SVG);
+}
+
+/// Verify that the children of a `<foreignObject>` are HTML elements.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test namespace -- foreign_object
+#[wasm_bindgen_test]
+fn foreign_object() {
+    let mount = create_mount();
+    PercyDom::new_append_to_mount(
+        html! { <svg><foreignObject><div></div></foreignObject></svg> },
+        &mount,
+    );
+
+    assert_eq!(namespace(&mount, "foreignObject"), SVG);
+    assert_eq!(namespace(&mount, "div"), HTML);
+}
+
+/// Verify that `<math>` and its children are MathML elements.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test namespace -- mathml
+#[wasm_bindgen_test]
+fn mathml() {
+    let mount = create_mount();
+    PercyDom::new_append_to_mount(html! { <math><mi>x</mi></math> }, &mount);
+
+    assert_eq!(namespace(&mount, "math"), MATHML);
+    assert_eq!(namespace(&mount, "mi"), MATHML);
+}
+
+/// Verify that an `xmlns` attribute overrides the namespace that an element would inherit.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test namespace -- xmlns_override
+#[wasm_bindgen_test]
+fn xmlns_override() {
+    let mount = create_mount();
+    PercyDom::new_append_to_mount(
+        html! { <div><g xmlns="http://www.w3.org/2000/svg"><a></a></g></div> },
+        &mount,
+    );
+
+    assert_eq!(namespace(&mount, "g"), SVG);
+    assert_eq!(namespace(&mount, "g > a"), SVG);
+}
+
+/// Verify that `xlink:` attributes are set and removed in the xlink namespace.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test namespace -- xlink_attribute
+#[wasm_bindgen_test]
+fn xlink_attribute() {
+    let xlink = Some("http://www.w3.org/1999/xlink");
+
+    let mount = create_mount();
+    let mut pdom =
+        PercyDom::new_append_to_mount(html! { <svg><image xlink:href="#first" /></svg> }, &mount);
+
+    let image = query(&mount, "image");
+    assert_eq!(
+        image.get_attribute_ns(xlink, "href"),
+        Some("#first".to_string())
+    );
+
+    pdom.update(html! { <svg><image xlink:href="#second" /></svg> })
+        .unwrap();
+    assert_eq!(
+        image.get_attribute_ns(xlink, "href"),
+        Some("#second".to_string())
+    );
+
+    pdom.update(html! { <svg><image /></svg> }).unwrap();
+    assert_eq!(image.get_attribute_ns(xlink, "href"), None);
+}
+
+/// Verify that nodes that get appended to or replaced inside of an `<svg>` inherit its namespace.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test namespace -- patch_inside_svg
+#[wasm_bindgen_test]
+fn patch_inside_svg() {
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(html! { <svg><g></g></svg> }, &mount);
+
+    pdom.update(html! { <svg><a></a><title>Appended</title></svg> })
+        .unwrap();
+    assert_eq!(namespace(&mount, "a"), SVG);
+    assert_eq!(namespace(&mount, "title"), SVG);
+
+    pdom.update(html! { <svg><g><a></a></g><title>Appended</title></svg> })
+        .unwrap();
+    assert_eq!(namespace(&mount, "g"), SVG);
+    assert_eq!(namespace(&mount, "g > a"), SVG);
+}
+
+fn query(mount: &Element, selector: &str) -> Element {
+    mount.query_selector(selector).unwrap().unwrap()
+}
+
+fn namespace(mount: &Element, selector: &str) -> String {
+    query(mount, selector).namespace_uri().unwrap()
+}
@@ -0,0 +1,87 @@
+//! Verify that a NodeRef holds the DOM element that its virtual element was rendered into.
+//!
+//! To run all tests in this file:
+//!
+//! wasm-pack test --chrome --headless crates/percy-dom --test node_ref
+
+use crate::testing_utilities::create_mount;
+use percy_dom::prelude::*;
+use virtual_node::NodeRef;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+mod testing_utilities;
+
+/// Verify that a NodeRef gets set to the element when the element is created.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test node_ref -- set_on_create
+#[wasm_bindgen_test]
+fn set_on_create() {
+    let node_ref = NodeRef::new();
+
+    let mount = create_mount();
+    let _pdom = PercyDom::new_append_to_mount(
+        html! { <div> <input ref=node_ref.clone() /> </div> },
+        &mount,
+    );
+
+    let input = mount.query_selector("input").unwrap().unwrap();
+    assert_eq!(node_ref.get().unwrap(), input);
+    assert!(node_ref.cast::<web_sys::HtmlInputElement>().is_some());
+}
+
+/// Verify that a NodeRef gets set to the new element when its element gets replaced.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test node_ref -- updated_on_replace
+#[wasm_bindgen_test]
+fn updated_on_replace() {
+    let node_ref = NodeRef::new();
+
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(
+        html! { <div> <span ref=node_ref.clone()></span> </div> },
+        &mount,
+    );
+
+    pdom.update(html! { <div> <em ref=node_ref.clone()></em> </div> })
+        .unwrap();
+
+    assert_eq!(node_ref.get().unwrap().tag_name(), "EM");
+}
+
+/// Verify that a NodeRef gets set when it is given to an element that already exists.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test node_ref -- set_on_existing_element
+#[wasm_bindgen_test]
+fn set_on_existing_element() {
+    let node_ref = NodeRef::new();
+
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(html! { <div> <span></span> </div> }, &mount);
+    assert!(node_ref.get().is_none());
+
+    pdom.update(html! { <div> <span ref=node_ref.clone()></span> </div> })
+        .unwrap();
+
+    assert_eq!(node_ref.get().unwrap().tag_name(), "SPAN");
+}
+
+/// Verify that a NodeRef gets cleared when its element gets removed.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test node_ref -- cleared_on_remove
+#[wasm_bindgen_test]
+fn cleared_on_remove() {
+    let node_ref = NodeRef::new();
+
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(
+        html! { <div> <span ref=node_ref.clone()></span> </div> },
+        &mount,
+    );
+    assert!(node_ref.get().is_some());
+
+    pdom.update(html! { <div></div> }).unwrap();
+
+    assert!(node_ref.get().is_none());
+}
@@ -7,12 +7,13 @@
 extern crate wasm_bindgen_test;
 extern crate web_sys;
 
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use wasm_bindgen_test::*;
 
 use crate::testing_utilities::{create_mount, get_element_by_id, random_id};
 use percy_dom::prelude::*;
+use percy_dom::DeferredRemoval;
 
 mod testing_utilities;
 
@@ -41,7 +42,7 @@ fn remove_node() {
 
     let mount = create_mount();
     let mut pdom = PercyDom::new_append_to_mount(old, &mount);
-    pdom.update(new);
+    pdom.update(new).unwrap();
 
     assert!(called.get());
 }
@@ -78,6 +79,131 @@ fn on_remove_elem_called_on_children() {
     let mut pdom = PercyDom::new_append_to_mount(old, &mount);
 
     assert_eq!(called.get(), false);
-    pdom.update(new);
+    pdom.update(new).unwrap();
     assert_eq!(called.get(), true);
 }
+
+/// Verify that an element whose on remove element function defers its removal stays in the DOM
+/// until the removal is finished.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test on_remove_element -- deferred_removal
+#[wasm_bindgen_test]
+fn deferred_removal() {
+    let removal = Rc::new(RefCell::new(None));
+
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(
+        html! { <div> <span></span> {deferred_em(&removal)} </div> },
+        &mount,
+    );
+
+    pdom.update(html! { <div> <span></span> </div> }).unwrap();
+    assert_eq!(mount.query_selector_all("em").unwrap().length(), 1);
+
+    removal.borrow_mut().take().unwrap().finish();
+    assert_eq!(mount.query_selector_all("em").unwrap().length(), 0);
+}
+
+/// Verify that the nodes after an element whose removal is deferred still get patched.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test on_remove_element -- deferred_removal_keeps_sibling_indices
+#[wasm_bindgen_test]
+fn deferred_removal_keeps_sibling_indices() {
+    let removal = Rc::new(RefCell::new(None));
+
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(
+        html! { <div> {deferred_em(&removal)} <b>Before</b> </div> },
+        &mount,
+    );
+
+    pdom.update(html! { <div> <b>Before</b> </div> }).unwrap();
+    pdom.update(html! { <div> <b>After</b> <i></i> </div> })
+        .unwrap();
+
+    assert_eq!(
+        mount.first_element_child().unwrap().inner_html(),
+        "<b>After</b><em key=\"deferred\">Leaving</em><i></i>"
+    );
+}
+
+/// Verify that an element that gets added back before the deferred removal of the element that it
+/// replaces finishes takes that element's place, and is not removed when the removal finishes.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test on_remove_element -- deferred_removal_cancelled
+#[wasm_bindgen_test]
+fn deferred_removal_cancelled() {
+    let removal = Rc::new(RefCell::new(None));
+
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(
+        html! { <div> <span></span> {deferred_em(&removal)} </div> },
+        &mount,
+    );
+
+    pdom.update(html! { <div> <span></span> </div> }).unwrap();
+    let old_removal = removal.borrow_mut().take().unwrap();
+
+    pdom.update(html! { <div> <span></span> {deferred_em(&removal)} </div> })
+        .unwrap();
+    assert_eq!(mount.query_selector_all("em").unwrap().length(), 1);
+
+    old_removal.finish();
+    assert_eq!(mount.query_selector_all("em").unwrap().length(), 1);
+
+    pdom.update(html! { <div> <span></span> </div> }).unwrap();
+    removal.borrow_mut().take().unwrap().finish();
+    assert_eq!(mount.query_selector_all("em").unwrap().length(), 0);
+}
+
+/// Verify that an element without a key that gets created next to an element whose removal is
+/// deferred does not take the removed element's place.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test on_remove_element -- deferred_removal_not_cancelled_by_unkeyed_sibling
+#[wasm_bindgen_test]
+fn deferred_removal_not_cancelled_by_unkeyed_sibling() {
+    let removal = Rc::new(RefCell::new(None));
+    let removal_clone = removal.clone();
+
+    let mut leaving = html! { <em>Leaving</em> };
+    leaving
+        .as_velement_mut()
+        .unwrap()
+        .special_attributes
+        .set_on_remove_element_deferred("fade-out", move |_elem, finish| {
+            *removal_clone.borrow_mut() = Some(finish);
+        });
+
+    let mount = create_mount();
+    let mut pdom =
+        PercyDom::new_append_to_mount(html! { <div> <span></span> {leaving} </div> }, &mount);
+
+    pdom.update(html! { <div> <span></span> </div> }).unwrap();
+    pdom.update(html! { <div> <span></span> <em>Entering</em> </div> })
+        .unwrap();
+    assert_eq!(
+        mount.first_element_child().unwrap().inner_html(),
+        "<span></span><em>Leaving</em><em>Entering</em>"
+    );
+
+    removal.borrow_mut().take().unwrap().finish();
+    assert_eq!(
+        mount.first_element_child().unwrap().inner_html(),
+        "<span></span><em>Entering</em>"
+    );
+}
+
+fn deferred_em(removal: &Rc<RefCell<Option<DeferredRemoval>>>) -> VirtualNode {
+    let removal = removal.clone();
+
+    html! {
+        <em
+          key="deferred"
+          on_remove_element=move |_elem, finish| {
+              *removal.borrow_mut() = Some(finish);
+          }
+        >
+          Leaving
+        </em>
+    }
+}
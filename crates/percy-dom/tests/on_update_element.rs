@@ -0,0 +1,77 @@
+//! Test the on update element special attribute.
+//!
+//! To run all tests in this file:
+//!
+//! wasm-pack test --chrome --headless crates/percy-dom --test on_update_element
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::testing_utilities::create_mount;
+use percy_dom::prelude::*;
+use wasm_bindgen_test::*;
+use web_sys::Element;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+mod testing_utilities;
+
+/// Verify that the on update element function gets called with the old and new attributes once
+/// the element and its children have been patched.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test on_update_element -- called_after_patching
+#[wasm_bindgen_test]
+fn called_after_patching() {
+    let updates = Rc::new(RefCell::new(vec![]));
+
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(chart("1", "Old", updates.clone()), &mount);
+    assert!(updates.borrow().is_empty());
+
+    pdom.update(chart("2", "New", updates.clone())).unwrap();
+
+    assert_eq!(
+        &*updates.borrow(),
+        &[("1".to_string(), "2".to_string(), "<em>New</em>".to_string())]
+    );
+}
+
+/// Verify that the on update element function does not get called when patching does not change
+/// the element.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test on_update_element -- not_called_if_unchanged
+#[wasm_bindgen_test]
+fn not_called_if_unchanged() {
+    let updates = Rc::new(RefCell::new(vec![]));
+
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(chart("1", "Same", updates.clone()), &mount);
+
+    pdom.update(chart("1", "Same", updates.clone())).unwrap();
+
+    assert!(updates.borrow().is_empty());
+}
+
+/// A div that records its old and new `title` attributes, along with its inner HTML, whenever it
+/// gets updated.
+fn chart(
+    title: &'static str,
+    text: &'static str,
+    updates: Rc<RefCell<Vec<(String, String, String)>>>,
+) -> VirtualNode {
+    html! {
+        <div
+          key="chart"
+          title=title
+          on_update_element=move |elem: Element, old_attrs, new_attrs| {
+              updates.borrow_mut().push((
+                  old_attrs["title"].to_string(),
+                  new_attrs["title"].to_string(),
+                  elem.inner_html(),
+              ));
+          }
+        >
+          <em>{text}</em>
+        </div>
+    }
+}
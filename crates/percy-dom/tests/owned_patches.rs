@@ -0,0 +1,100 @@
+//! Tests that ensure that owned patches, which could have been created on another thread, get
+//! applied properly.
+//!
+//! To run all tests in this file:
+//!
+//! wasm-pack test --chrome --headless crates/percy-dom --test owned_patches
+
+use percy_dom::event::{EventHandler, EventName, EventsByNodeIdx};
+use percy_dom::prelude::*;
+use percy_dom::{patch_owned, OwnedPatches, PatchBindings};
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+use web_sys::Element;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+/// Verify that applying owned patches turns the old DOM into the new DOM.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test owned_patches -- patches_dom
+#[wasm_bindgen_test]
+fn patches_dom() {
+    let old = html! {
+        <div>
+            <span>Old</span>
+            <em></em>
+            <ul> <li key="a">a</li> <li key="b">b</li> </ul>
+        </div>
+    };
+    let new = html! {
+        <div id="new">
+            <span>New</span>
+            <strong>Replaced</strong>
+            <ul> <li key="b">b</li> <li key="c">c</li> <li key="a">a</li> </ul>
+            <input disabled=true />
+        </div>
+    };
+
+    let mut events = EventsByNodeIdx::new();
+    let root_node = old.create_dom_node(0, &mut events);
+
+    let patches = OwnedPatches::diff(&old, &new);
+    let bindings: Rc<dyn PatchBindings> = Rc::new(ClickCounter::default());
+    patch_owned(root_node.clone(), &patches, &mut events, &bindings).unwrap();
+
+    assert_eq!(
+        root_node.unchecked_into::<Element>().outer_html(),
+        new.to_string()
+    );
+}
+
+/// Verify that the events of created nodes, and the events of existing nodes, are bound on the
+/// thread that applies the patches.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test owned_patches -- events_bound_by_index
+#[wasm_bindgen_test]
+fn events_bound_by_index() {
+    let old = html! { <div> <button></button> </div> };
+    let new = html! { <div> <button onclick=|| {}></button> <a onclick=|| {}></a> </div> };
+
+    let mut events = EventsByNodeIdx::new();
+    let root_node = old.create_dom_node(0, &mut events);
+
+    let counter = Rc::new(ClickCounter::default());
+    let bindings: Rc<dyn PatchBindings> = counter.clone();
+    patch_owned(
+        root_node,
+        &OwnedPatches::diff(&old, &new),
+        &mut events,
+        &bindings,
+    )
+    .unwrap();
+
+    // Event delegation happens in the PercyDom, so we call the handlers that were bound directly.
+    for node_idx in [1, 2] {
+        let handler = events.get_event_handler(&node_idx, &EventName::ONCLICK);
+        match handler {
+            Some(EventHandler::NoArgs(handler)) => (handler.borrow_mut())(),
+            _ => panic!("The onclick handler for {} was not bound.", node_idx),
+        }
+    }
+
+    assert_eq!(*counter.clicked.borrow(), vec![1, 2]);
+}
+
+/// Binds an onclick handler for every node, which records that the node was clicked.
+#[derive(Default)]
+struct ClickCounter {
+    clicked: Rc<RefCell<Vec<u32>>>,
+}
+
+impl PatchBindings for ClickCounter {
+    fn event_handler(&self, node_idx: u32, _event: &EventName) -> Option<EventHandler> {
+        let clicked = self.clicked.clone();
+        Some(EventHandler::NoArgs(Rc::new(RefCell::new(move || {
+            clicked.borrow_mut().push(node_idx)
+        }))))
+    }
+}
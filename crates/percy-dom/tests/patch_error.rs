@@ -0,0 +1,149 @@
+//! Verify that patches that can't be applied, typically since something other than percy-dom
+//! changed the DOM, return an error instead of panicking, and that a PercyDom can recover from
+//! them by re-rendering.
+//!
+//! To run all tests in this file:
+//!
+//! wasm-pack test --chrome --headless crates/percy-dom --test patch_error
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::testing_utilities::{create_mount, get_element_by_id, random_id};
+use percy_dom::event::EventsByNodeIdx;
+use percy_dom::prelude::*;
+use percy_dom::{PatchError, PatchErrorKind};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+use web_sys::Element;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+mod testing_utilities;
+
+/// Verify that patching a node that was removed by something other than percy-dom returns an
+/// error that describes the patch.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test patch_error -- node_removed_from_dom
+#[wasm_bindgen_test]
+fn node_removed_from_dom() {
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(
+        html! { <div> <span>First</span> <em>Second</em> </div> },
+        &mount,
+    );
+
+    remove_em(&mount);
+
+    let err = pdom
+        .update(html! { <div> <span>First</span> <em>Changed</em> </div> })
+        .unwrap_err();
+
+    assert_eq!(
+        err,
+        PatchError {
+            patch: "ChangeText",
+            node_idx: 4,
+            kind: PatchErrorKind::NodeNotFound(4),
+        }
+    );
+}
+
+/// Verify that a PercyDom that recovers from patch errors re-renders its root node and reports
+/// the error.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test patch_error -- recover_by_rerendering
+#[wasm_bindgen_test]
+fn recover_by_rerendering() {
+    let errors = Rc::new(RefCell::new(vec![]));
+    let errors_clone = errors.clone();
+
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(
+        html! { <div> <span>First</span> <em>Second</em> </div> },
+        &mount,
+    );
+    pdom.recover_from_patch_errors(move |err: &PatchError| {
+        errors_clone.borrow_mut().push(err.patch);
+    });
+
+    remove_em(&mount);
+
+    pdom.update(html! { <div> <span>First</span> <em>Changed</em> </div> })
+        .unwrap();
+
+    assert_eq!(
+        mount.inner_html(),
+        "<div><span>First</span><em>Changed</em></div>"
+    );
+    assert_eq!(&*errors.borrow(), &["ChangeText"]);
+}
+
+/// Verify that events are handled by the root node that replaced the root node that could not be
+/// patched.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test patch_error -- events_after_recovering
+#[wasm_bindgen_test]
+fn events_after_recovering() {
+    let id = random_id();
+
+    let clicked = Rc::new(Cell::new(false));
+    let clicked_clone = clicked.clone();
+
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(
+        html! { <div> <span>First</span> <em>Second</em> </div> },
+        &mount,
+    );
+    pdom.recover_from_patch_errors(|_| {});
+
+    remove_em(&mount);
+
+    pdom.update(html! {
+        <div>
+          <span>First</span>
+          <em>Changed</em>
+          <button id=id onclick=move || clicked_clone.set(true)></button>
+        </div>
+    })
+    .unwrap();
+
+    get_element_by_id(id)
+        .dyn_into::<web_sys::HtmlElement>()
+        .unwrap()
+        .click();
+
+    assert!(clicked.get());
+}
+
+/// Verify that patching a root node that is neither an element nor text returns an error instead
+/// of panicking.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test patch_error -- unsupported_root_node_type
+#[wasm_bindgen_test]
+fn unsupported_root_node_type() {
+    let old = html! { <div></div> };
+    let new = html! { <div id="changed"></div> };
+    let patches = percy_dom::diff(&old, &new);
+
+    let fragment = web_sys::window()
+        .unwrap()
+        .document()
+        .unwrap()
+        .create_document_fragment();
+
+    let err = percy_dom::patch(fragment, &new, &mut EventsByNodeIdx::new(), &patches).unwrap_err();
+
+    assert_eq!(
+        err,
+        PatchError {
+            patch: "AddAttributes",
+            node_idx: 0,
+            kind: PatchErrorKind::UnexpectedNodeType,
+        }
+    );
+}
+
+fn remove_em(mount: &Element) {
+    mount.query_selector("em").unwrap().unwrap().remove();
+}
@@ -35,7 +35,7 @@ fn patches_dom() {
     let mut pdom = PercyDom::new(vdom);
 
     let new_vdom = html! { <div id="patched"></div> };
-    pdom.update(new_vdom);
+    pdom.update(new_vdom).unwrap();
 
     document
         .body()
@@ -82,10 +82,11 @@ fn append_element_with_closure() {
         // New node gets appended into the DOM.
         // We are testing that we've stored this new node's closures even though `new` will be dropped
         // at the end of this block.
-        pdom.update(append_node);
+        pdom.update(append_node).unwrap();
     }
 
     let input_event = InputEvent::new("input").unwrap();
+    input_event.init_event_with_bubbles("input", true);
 
     assert_eq!(&*text.borrow(), "Start Text");
 
@@ -134,10 +135,11 @@ fn updates_active_closure_on_replace() {
         // New node replaces old node.
         // We are testing that we've stored this new node's closures even though `new` will be dropped
         // at the end of this block.
-        pdom.update(replace_node);
+        pdom.update(replace_node).unwrap();
     }
 
     let input_event = InputEvent::new("input").unwrap();
+    input_event.init_event_with_bubbles("input", true);
 
     assert_eq!(&*text.borrow(), "Start Text");
 
@@ -0,0 +1,209 @@
+//! Verify that portals render their children into a different mount, while being patched and
+//! handling events as part of the PercyDom that they were rendered in.
+//!
+//! To run all tests in this file:
+//!
+//! wasm-pack test --chrome --headless crates/percy-dom --test portal
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::testing_utilities::{append_to_document, create_mount, document, random_id};
+use percy_dom::prelude::*;
+use percy_dom::VPortal;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+use web_sys::Element;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+mod testing_utilities;
+
+/// Verify that a portal's children get placed into the portal's mount, and that the portal leaves
+/// a placeholder comment in its spot.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test portal -- renders_into_mount
+#[wasm_bindgen_test]
+fn renders_into_mount() {
+    let (portal_mount, selector) = create_portal_mount();
+
+    let mount = create_mount();
+    PercyDom::new_append_to_mount(
+        html! { <div> <span></span> {portal(&selector, html! { <em>Modal</em> })} </div> },
+        &mount,
+    );
+
+    assert!(mount.query_selector("em").unwrap().is_none());
+    assert_eq!(portal_mount.text_content().unwrap(), "Modal");
+
+    let div = mount.first_element_child().unwrap();
+    assert_eq!(div.inner_html(), "<span></span><!--portal-->");
+}
+
+/// Verify that a portal's children get patched.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test portal -- patch_portal_children
+#[wasm_bindgen_test]
+fn patch_portal_children() {
+    let (portal_mount, selector) = create_portal_mount();
+
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(
+        html! { <div> {portal(&selector, html! { <em>Before</em> })} <span></span> </div> },
+        &mount,
+    );
+
+    pdom.update(html! {
+        <div> {portal(&selector, html! { <em>After</em> })} <strong></strong> </div>
+    })
+    .unwrap();
+
+    assert_eq!(portal_mount.inner_html(), "<div><em>After</em></div>");
+    assert!(mount.query_selector("strong").unwrap().is_some());
+}
+
+/// Verify that an event inside of a portal calls the handlers of the portal's children and then
+/// bubbles up to the portal's parent.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test portal -- event_bubbles_to_portal_parent
+#[wasm_bindgen_test]
+fn event_bubbles_to_portal_parent() {
+    let (portal_mount, selector) = create_portal_mount();
+
+    let clicked = Rc::new(RefCell::new(vec![]));
+    let clicked_button = clicked.clone();
+    let clicked_div = clicked.clone();
+
+    let button = html! {
+        <button onclick=move || clicked_button.borrow_mut().push("button")></button>
+    };
+
+    let mount = create_mount();
+    let _pdom = PercyDom::new_append_to_mount(
+        html! {
+            <div onclick=move || clicked_div.borrow_mut().push("div")>
+              {portal(&selector, button)}
+            </div>
+        },
+        &mount,
+    );
+
+    query_html_element(&portal_mount, "button").click();
+
+    assert_eq!(&*clicked.borrow(), &["button", "div"]);
+}
+
+/// Verify that an event inside of a portal whose mount is inside of the PercyDom's root node only
+/// gets handled once.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test portal -- mount_inside_of_root
+#[wasm_bindgen_test]
+fn mount_inside_of_root() {
+    let mount_id = format!("portal-mount-{}", random_id().replace('.', ""));
+    let selector = format!("#{}", mount_id);
+
+    let clicked = Rc::new(RefCell::new(0));
+    let clicked_clone = clicked.clone();
+
+    let button = html! {
+        <button onclick=move || *clicked_clone.borrow_mut() += 1></button>
+    };
+
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(
+        html! { <div> <section id=mount_id.clone()></section> </div> },
+        &mount,
+    );
+    pdom.update(html! {
+        <div> <section id=mount_id></section> {portal(&selector, button)} </div>
+    })
+    .unwrap();
+
+    query_html_element(&mount, "button").click();
+
+    assert_eq!(*clicked.borrow(), 1);
+}
+
+/// Verify that when a portal gets removed its children get removed from its mount and their on
+/// remove element functions get called.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test portal -- remove_portal
+#[wasm_bindgen_test]
+fn remove_portal() {
+    let (portal_mount, selector) = create_portal_mount();
+
+    let removed = Rc::new(RefCell::new(None));
+    let removed_clone = removed.clone();
+
+    let mut child = html! { <em>Modal</em> };
+    child
+        .as_velement_mut()
+        .unwrap()
+        .special_attributes
+        .set_on_remove_element("key", move |elem: Element| {
+            *removed_clone.borrow_mut() = Some(elem.tag_name());
+        });
+
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(
+        html! { <div> <span></span> {portal(&selector, child)} </div> },
+        &mount,
+    );
+
+    pdom.update(html! { <div> <span></span> </div> }).unwrap();
+
+    assert_eq!(portal_mount.inner_html(), "");
+    assert_eq!(removed.borrow().as_deref(), Some("EM"));
+}
+
+/// Verify that a replaced portal removes its children from its mount, and that a portal can
+/// replace another node.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test portal -- replace_portal
+#[wasm_bindgen_test]
+fn replace_portal() {
+    let (portal_mount, selector) = create_portal_mount();
+
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(
+        html! { <div> {portal(&selector, html! { <em>Modal</em> })} </div> },
+        &mount,
+    );
+
+    pdom.update(html! { <div> <span>Inline</span> </div> })
+        .unwrap();
+    assert_eq!(portal_mount.inner_html(), "");
+    assert_eq!(mount.text_content().unwrap(), "Inline");
+
+    pdom.update(html! { <div> {portal(&selector, html! { <em>Again</em> })} </div> })
+        .unwrap();
+    assert_eq!(portal_mount.inner_html(), "<div><em>Again</em></div>");
+    assert_eq!(mount.text_content().unwrap(), "");
+}
+
+fn portal(mount_selector: &str, child: VirtualNode) -> VirtualNode {
+    let mut portal = VPortal::new(mount_selector);
+    portal.children.push(child);
+    portal.into()
+}
+
+/// Create an element for portals to place their children into, returning the element and a
+/// selector for it.
+fn create_portal_mount() -> (Element, String) {
+    let id = format!("portal-mount-{}", random_id().replace('.', ""));
+
+    let portal_mount = document().create_element("div").unwrap();
+    portal_mount.set_id(&id);
+    append_to_document(&portal_mount);
+
+    (portal_mount, format!("#{}", id))
+}
+
+fn query_html_element(parent: &Element, selector: &str) -> web_sys::HtmlElement {
+    parent
+        .query_selector(selector)
+        .unwrap()
+        .unwrap()
+        .dyn_into()
+        .unwrap()
+}
@@ -0,0 +1,166 @@
+//! Tests that ensure that the render scheduler renders when it should.
+//!
+//! To run all tests in this file:
+//!
+//! wasm-pack test --chrome --headless crates/percy-dom --test render_scheduler
+
+use crate::testing_utilities::create_mount;
+use percy_dom::prelude::*;
+use percy_dom::render::{ManualClock, RenderScheduler};
+use percy_dom::PercyDom;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+mod testing_utilities;
+
+/// Verify that a scheduled render happens on the next frame, and that flushing renders right away.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test render_scheduler -- scheduled_render_and_flush
+#[wasm_bindgen_test]
+fn scheduled_render_and_flush() {
+    let count = Rc::new(Cell::new(0));
+
+    let mount = create_mount();
+    let pdom = PercyDom::new_append_to_mount(html! { <div>0</div> }, &mount);
+
+    let clock = ManualClock::new();
+    let count_clone = count.clone();
+    let scheduler = RenderScheduler::with_manual_clock(
+        pdom,
+        move || html! { <div>{count_clone.get().to_string()}</div> },
+        &clock,
+    );
+
+    count.set(1);
+    scheduler.schedule();
+    assert_eq!(mount.text_content().unwrap(), "0");
+
+    clock.next_frame();
+    assert_eq!(mount.text_content().unwrap(), "1");
+
+    count.set(2);
+    scheduler.schedule();
+    assert!(scheduler.flush());
+    assert_eq!(mount.text_content().unwrap(), "2");
+    assert!(!clock.has_pending_frame());
+}
+
+/// Verify that a render that gets scheduled and flushed by the render function stays scheduled for
+/// the next frame.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test render_scheduler -- flush_inside_render
+#[wasm_bindgen_test]
+fn flush_inside_render() {
+    let count = Rc::new(Cell::new(0));
+    let handle: Rc<RefCell<Option<RenderScheduler>>> = Rc::new(RefCell::new(None));
+
+    let mount = create_mount();
+    let pdom = PercyDom::new_append_to_mount(html! { <div>0</div> }, &mount);
+
+    let clock = ManualClock::new();
+    let count_clone = count.clone();
+    let handle_clone = handle.clone();
+    let scheduler = RenderScheduler::with_manual_clock(
+        pdom,
+        move || {
+            count_clone.set(count_clone.get() + 1);
+
+            if count_clone.get() == 1 {
+                let scheduler = handle_clone.borrow().clone().unwrap();
+                scheduler.schedule();
+                assert!(!scheduler.flush());
+            }
+
+            html! { <div>{count_clone.get().to_string()}</div> }
+        },
+        &clock,
+    );
+    *handle.borrow_mut() = Some(scheduler.clone());
+
+    scheduler.schedule();
+    assert!(scheduler.flush());
+    assert_eq!(mount.text_content().unwrap(), "1");
+    assert!(scheduler.is_scheduled());
+
+    clock.next_frame();
+    assert_eq!(mount.text_content().unwrap(), "2");
+
+    handle.borrow_mut().take();
+}
+
+/// Verify that we get the `PercyDom` back without rendering the canceled render.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test render_scheduler -- take_percy_dom
+#[wasm_bindgen_test]
+fn take_percy_dom() {
+    let mount = create_mount();
+    let pdom = PercyDom::new_append_to_mount(html! { <div>Before</div> }, &mount);
+
+    let clock = ManualClock::new();
+    let scheduler = RenderScheduler::with_manual_clock(pdom, || html! { <div>After</div> }, &clock);
+
+    scheduler.schedule();
+    let mut pdom = scheduler.take_percy_dom().unwrap();
+    assert!(scheduler.take_percy_dom().is_none());
+
+    clock.next_frame();
+    assert_eq!(mount.text_content().unwrap(), "Before");
+
+    pdom.update(html! { <div>Updated</div> }).unwrap();
+    assert_eq!(mount.text_content().unwrap(), "Updated");
+}
+
+/// Verify that changing a component's state schedules a render of only that component.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test render_scheduler -- component_state_change_schedules_render
+#[wasm_bindgen_test]
+fn component_state_change_schedules_render() {
+    let app_renders = Rc::new(Cell::new(0));
+
+    let mount = create_mount();
+    let pdom = PercyDom::new_append_to_mount(html! { <div> <Toggle /> </div> }, &mount);
+
+    let clock = ManualClock::new();
+    let app_renders_clone = app_renders.clone();
+    let scheduler = RenderScheduler::with_manual_clock(
+        pdom,
+        move || {
+            app_renders_clone.set(app_renders_clone.get() + 1);
+            html! { <div> <Toggle /> </div> }
+        },
+        &clock,
+    );
+
+    let button: web_sys::HtmlElement = mount
+        .query_selector("button")
+        .unwrap()
+        .unwrap()
+        .unchecked_into();
+    button.click();
+    assert!(scheduler.is_scheduled());
+    assert_eq!(mount.text_content().unwrap(), "off");
+
+    clock.next_frame();
+    assert_eq!(mount.text_content().unwrap(), "on");
+    assert_eq!(app_renders.get(), 0);
+}
+
+#[derive(PartialEq)]
+struct Toggle {}
+
+impl Component for Toggle {
+    type State = bool;
+
+    fn render(&self, state: &ComponentState<bool>) -> VirtualNode {
+        let text = if *state.get() { "on" } else { "off" };
+        let state = state.clone();
+
+        html! {
+            <button onclick=move || state.update(|on| *on = !*on)>{text}</button>
+        }
+    }
+}
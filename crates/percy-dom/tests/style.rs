@@ -0,0 +1,64 @@
+//! Verify that inline styles get patched one property at a time.
+//!
+//! To run all tests in this file:
+//!
+//! wasm-pack test --chrome --headless crates/percy-dom --test style
+
+use crate::testing_utilities::create_mount;
+use percy_dom::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_test::*;
+use web_sys::HtmlElement;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+mod testing_utilities;
+
+/// Verify that we set the inline styles when creating an element.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test style -- create_with_styles
+#[wasm_bindgen_test]
+fn create_with_styles() {
+    let mount = create_mount();
+    PercyDom::new_append_to_mount(
+        html! { <div style="color: red; margin-top: 5px !important"></div> },
+        &mount,
+    );
+
+    let style = div(&mount).style();
+    assert_eq!(style.get_property_value("color").unwrap(), "red");
+    assert_eq!(style.get_property_value("margin-top").unwrap(), "5px");
+    assert_eq!(style.get_property_priority("margin-top"), "important");
+}
+
+/// Verify that patching one property leaves the other properties alone, including ones that were
+/// set outside of percy, such as by an animation library.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test style -- patch_one_property
+#[wasm_bindgen_test]
+fn patch_one_property() {
+    let mount = create_mount();
+    let mut pdom = PercyDom::new_append_to_mount(
+        html! { <div style="color: red; width: 5px; height: 5px"></div> },
+        &mount,
+    );
+
+    let style = div(&mount).style();
+    style.set_property("opacity", "0.5").unwrap();
+
+    pdom.update(html! { <div style="color: blue; width: 5px"></div> })
+        .unwrap();
+
+    assert_eq!(style.get_property_value("color").unwrap(), "blue");
+    assert_eq!(style.get_property_value("width").unwrap(), "5px");
+    assert_eq!(style.get_property_value("height").unwrap(), "");
+    assert_eq!(style.get_property_value("opacity").unwrap(), "0.5");
+}
+
+fn div(mount: &web_sys::Element) -> HtmlElement {
+    mount
+        .query_selector("div")
+        .unwrap()
+        .unwrap()
+        .unchecked_into()
+}
@@ -1,5 +1,8 @@
 //! Various helper functions and types for writing tests.
 
+// Not every test file uses every helper.
+#![allow(dead_code)]
+
 // Tests share the same DOM, so IDs need to be unique across tests.
 pub fn random_id() -> &'static str {
     Box::leak(Box::new(js_sys::Math::random().to_string()))
@@ -23,3 +26,15 @@ pub fn create_mount() -> web_sys::Element {
 
     mount
 }
+
+/// Wait until the browser's next animation frame has run.
+pub async fn next_animation_frame() {
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        web_sys::window()
+            .unwrap()
+            .request_animation_frame(&resolve)
+            .unwrap();
+    });
+
+    wasm_bindgen_futures::JsFuture::from(promise).await.unwrap();
+}
@@ -90,3 +90,34 @@ fn set_textarea_elem_value_property() {
         );
     }
 }
+
+/// Verify that the deprecated `ValueAttributeUnchanged` patch still overwrites the value of an
+/// input field.
+///
+/// wasm-pack test --chrome --headless crates/percy-dom --test value_attribute -- value_attribute_unchanged_patch
+#[wasm_bindgen_test]
+#[allow(deprecated)]
+fn value_attribute_unchanged_patch() {
+    let input = html! {<input value="Rendered">};
+    let input_node = input.create_dom_node(0, &mut EventsByNodeIdx::new());
+
+    input_node
+        .clone()
+        .dyn_into::<HtmlInputElement>()
+        .unwrap()
+        .set_value("Should Be Replaced");
+
+    let value = "Rendered".into();
+    percy_dom::patch(
+        input_node.clone(),
+        &input,
+        &mut EventsByNodeIdx::new(),
+        &[percy_dom::Patch::ValueAttributeUnchanged(0, &value)],
+    )
+    .unwrap();
+
+    assert_eq!(
+        input_node.dyn_into::<HtmlInputElement>().unwrap().value(),
+        "Rendered"
+    );
+}
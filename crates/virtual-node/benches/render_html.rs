@@ -0,0 +1,128 @@
+//! Benchmarks for rendering virtual nodes into HTML strings.
+//!
+//! cargo bench -p virtual-node --bench render_html
+
+// Criterion can't be compiled to wasm32, so there is nothing to benchmark there.
+#![cfg_attr(target_arch = "wasm32", no_main)]
+#![cfg(not(target_arch = "wasm32"))]
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::fmt::Write;
+use virtual_node::{AttributeValue, VElement, VirtualNode};
+
+fn render_html(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render_html");
+
+    for rows in [10, 100, 1_000].iter() {
+        let table = table(*rows);
+        let len = table.to_string().len();
+        group.throughput(Throughput::Bytes(len as u64));
+
+        group.bench_with_input(
+            BenchmarkId::new("recursive_to_string", rows),
+            &table,
+            |b, table| b.iter(|| recursive_to_string(black_box(table))),
+        );
+
+        group.bench_with_input(BenchmarkId::new("to_string", rows), &table, |b, table| {
+            b.iter(|| black_box(table).to_string())
+        });
+
+        group.bench_with_input(
+            BenchmarkId::new("write_html_preallocated", rows),
+            &table,
+            |b, table| {
+                let mut html = String::with_capacity(len);
+                b.iter(|| {
+                    html.clear();
+                    black_box(table).write_html(&mut html).unwrap();
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("write_html_io", rows),
+            &table,
+            |b, table| {
+                let mut bytes = Vec::with_capacity(len);
+                b.iter(|| {
+                    bytes.clear();
+                    black_box(table).write_html_io(&mut bytes).unwrap();
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("html_chunks_8kb", rows),
+            &table,
+            |b, table| b.iter(|| black_box(table).html_chunks(8 * 1024).count()),
+        );
+    }
+
+    group.finish();
+}
+
+/// A table with a header and many rows of cells, similar to a large page.
+fn table(rows: usize) -> VirtualNode {
+    let mut table = VElement::new("table");
+    table.attrs.insert("class".into(), "data-table".into());
+
+    for row in 0..rows {
+        let mut tr = VElement::new("tr");
+        tr.attrs.insert("id".into(), format!("row-{}", row).into());
+
+        for col in 0..10 {
+            let mut td = VElement::new("td");
+            td.attrs.insert("class".into(), "cell".into());
+            td.children
+                .push(VirtualNode::text(format!("Row {} & column {}", row, col)));
+            tr.children.push(td.into());
+        }
+
+        table.children.push(tr.into());
+    }
+
+    table.into()
+}
+
+/// How rendering worked before the streaming renderer, where every element rendered each of its
+/// children into their own `String`.
+fn recursive_to_string(node: &VirtualNode) -> String {
+    match node {
+        VirtualNode::Text(text) => text.to_string(),
+        VirtualNode::Comment(comment) => comment.to_string(),
+        VirtualNode::Portal(portal) => portal.to_string(),
+        VirtualNode::Component(component) => recursive_to_string(component.rendered()),
+        VirtualNode::Element(element) => {
+            let mut html = String::new();
+            write!(html, "<{}", element.tag).unwrap();
+
+            for (attr, value) in element.attrs.iter() {
+                match value {
+                    AttributeValue::String(value) => {
+                        write!(html, r#" {}="{}""#, attr, value).unwrap()
+                    }
+                    AttributeValue::Bool(true) => write!(html, " {}", attr).unwrap(),
+                    AttributeValue::Bool(false) => {}
+                    value => write!(html, r#" {}="{}""#, attr, value).unwrap(),
+                }
+            }
+
+            html += ">";
+
+            for child in element.children.iter() {
+                html += &recursive_to_string(child);
+            }
+
+            write!(html, "</{}>", element.tag).unwrap();
+
+            html
+        }
+        VirtualNode::Fragment(fragment) => {
+            fragment.children.iter().map(recursive_to_string).collect()
+        }
+    }
+}
+
+criterion_group!(benches, render_html);
+criterion_main!(benches);
@@ -1,45 +1,61 @@
-use web_sys::{Document, Element};
+use js_sys::Reflect;
+use wasm_bindgen::JsValue;
+use web_sys::{Comment, Document, DocumentFragment, Element, Node};
 
 use crate::event::EventsByNodeIdx;
-use crate::{AttributeValue, VElement, VirtualNode};
+use crate::{
+    set_dom_property, set_element_attribute, set_style_property, AttributeTarget, Namespace,
+    VElement, VFragment, VPortal, VirtualNode, PORTAL_CONTAINER_PROP, PORTAL_PLACEHOLDER_PROP,
+};
 
 mod add_events;
 
 impl VElement {
     /// Build a DOM element by recursively creating DOM nodes for this element and it's
     /// children, it's children's children, etc.
+    ///
+    /// The element is created in the namespace that it inherits from its parent, see
+    /// [`Namespace`].
     pub(crate) fn create_element_node(
         &self,
         node_idx: &mut u32,
         events: &mut EventsByNodeIdx,
-    ) -> Element {
+        parent_namespace: Namespace,
+    ) -> Result<Element, JsValue> {
         let document = web_sys::window().unwrap().document().unwrap();
 
-        let element = if html_validation::is_svg_namespace(&self.tag) {
-            document
-                .create_element_ns(Some("http://www.w3.org/2000/svg"), &self.tag)
-                .unwrap()
-        } else {
-            document.create_element(&self.tag).unwrap()
-        };
-
-        self.attrs.iter().for_each(|(name, value)| {
-            match value {
-                AttributeValue::String(s) => {
-                    element.set_attribute(name, s).unwrap();
-                }
-                AttributeValue::Bool(b) => {
-                    if *b {
-                        element.set_attribute(name, "").unwrap();
-                    }
-                }
-            };
-        });
+        let namespace = parent_namespace.of_child(self);
+        let element = namespace.create_element(&document, &self.tag)?;
+
+        for (name, value) in self.attrs.iter() {
+            if self.attribute_target(name) == AttributeTarget::Property {
+                continue;
+            }
+
+            set_element_attribute(&element, name, value)?;
+        }
+
+        for (name, value) in self.styles.iter() {
+            set_style_property(&element, name, value)?;
+        }
 
         self.add_events(&element, events, *node_idx);
 
-        self.append_children_to_dom(&element, &document, node_idx, events);
+        append_children_to_dom(
+            &element,
+            &self.flat_children(),
+            &document,
+            node_idx,
+            events,
+            namespace.of_children(self),
+        )?;
+
+        // Set after the children were created, since a `<select>`'s value depends on its options.
+        for (name, value) in self.dom_property_attributes() {
+            set_dom_property(&element, name, value, events)?;
+        }
 
+        self.special_attributes.maybe_set_node_ref(&element);
         self.special_attributes
             .maybe_call_on_create_element(&element);
 
@@ -47,55 +63,152 @@ impl VElement {
             element.set_inner_html(inner_html);
         }
 
-        element
+        Ok(element)
     }
 }
 
-impl VElement {
-    fn append_children_to_dom(
+impl VFragment {
+    /// Build a DOM fragment by recursively creating DOM nodes for this fragment's children.
+    pub(crate) fn create_document_fragment(
         &self,
-        element: &Element,
-        document: &Document,
         node_idx: &mut u32,
         events: &mut EventsByNodeIdx,
-    ) {
-        let mut previous_node_was_text = false;
-
-        self.children.iter().for_each(|child| {
-            *node_idx += 1;
-
-            match child {
-                VirtualNode::Text(text_node) => {
-                    let current_node = element.as_ref() as &web_sys::Node;
-
-                    // We ensure that the text siblings are patched by preventing the browser from merging
-                    // neighboring text nodes. Originally inspired by some of React's work from 2016.
-                    //  -> https://reactjs.org/blog/2016/04/07/react-v15.html#major-changes
-                    //  -> https://github.com/facebook/react/pull/5753
-                    //
-                    // `ptns` = Percy text node separator
-                    if previous_node_was_text {
-                        let separator = document.create_comment("ptns");
-                        current_node
-                            .append_child(separator.as_ref() as &web_sys::Node)
-                            .unwrap();
-                    }
-
-                    current_node
-                        .append_child(&text_node.create_text_node())
-                        .unwrap();
-
-                    previous_node_was_text = true;
-                }
-                VirtualNode::Element(element_node) => {
-                    previous_node_was_text = false;
+        parent_namespace: Namespace,
+    ) -> Result<DocumentFragment, JsValue> {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let fragment = document.create_document_fragment();
+
+        append_children_to_dom(
+            &fragment,
+            &self.flat_children(),
+            &document,
+            node_idx,
+            events,
+            parent_namespace,
+        )?;
+
+        Ok(fragment)
+    }
+}
+
+impl VPortal {
+    /// Create the portal's placeholder comment, along with the element that holds the portal's
+    /// children, which gets appended to the portal's mount.
+    pub(crate) fn create_portal_placeholder(
+        &self,
+        node_idx: &mut u32,
+        events: &mut EventsByNodeIdx,
+    ) -> Result<Comment, JsValue> {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let placeholder = document.create_comment("portal");
+
+        self.create_portal_container(&placeholder, node_idx, events)?;
+
+        Ok(placeholder)
+    }
 
-                    let child = element_node.create_element_node(node_idx, events);
-                    let child_elem: Element = child;
+    /// Create the element that holds the portal's children, append it to the portal's mount and
+    /// link it to the portal's placeholder comment.
+    ///
+    /// Returns an error if there is no element that matches the portal's mount selector.
+    pub(crate) fn create_portal_container(
+        &self,
+        placeholder: &Comment,
+        node_idx: &mut u32,
+        events: &mut EventsByNodeIdx,
+    ) -> Result<(), JsValue> {
+        let document = web_sys::window().unwrap().document().unwrap();
 
-                    element.append_child(&child_elem).unwrap();
+        let mount = document
+            .query_selector(&self.mount_selector)?
+            .ok_or_else(|| {
+                js_sys::Error::new(&format!(
+                    "There is no element that matches the portal's mount selector {:?}.",
+                    self.mount_selector
+                ))
+            })?;
+
+        let container = document.create_element("div")?;
+
+        append_children_to_dom(
+            &container,
+            &self.flat_children(),
+            &document,
+            node_idx,
+            events,
+            Namespace::Html,
+        )?;
+
+        Reflect::set(placeholder, &PORTAL_CONTAINER_PROP.into(), &container)?;
+        Reflect::set(&container, &PORTAL_PLACEHOLDER_PROP.into(), placeholder)?;
+        events.add_portal_container(&container);
+
+        mount.append_child(&container)?;
+
+        Ok(())
+    }
+}
+
+/// Create and append DOM nodes for the children of an element or fragment.
+///
+/// The children must already have had any fragments flattened.
+fn append_children_to_dom(
+    parent: &Node,
+    children: &[&VirtualNode],
+    document: &Document,
+    node_idx: &mut u32,
+    events: &mut EventsByNodeIdx,
+    namespace: Namespace,
+) -> Result<(), JsValue> {
+    let mut previous_node_was_text = false;
+
+    for child in children {
+        *node_idx += 1;
+
+        match child.rendered_node() {
+            VirtualNode::Text(text_node) => {
+                // We ensure that the text siblings are patched by preventing the browser from merging
+                // neighboring text nodes. Originally inspired by some of React's work from 2016.
+                //  -> https://reactjs.org/blog/2016/04/07/react-v15.html#major-changes
+                //  -> https://github.com/facebook/react/pull/5753
+                //
+                // `ptns` = Percy text node separator
+                if previous_node_was_text {
+                    let separator = document.create_comment("ptns");
+                    parent.append_child(separator.as_ref() as &web_sys::Node)?;
                 }
+
+                parent.append_child(&text_node.create_text_node())?;
+
+                previous_node_was_text = true;
             }
-        });
+            VirtualNode::Element(element_node) => {
+                previous_node_was_text = false;
+
+                let child = element_node.create_element_node(node_idx, events, namespace)?;
+                let child_elem: Element = child;
+
+                parent.append_child(&child_elem)?;
+            }
+            VirtualNode::Comment(comment) => {
+                previous_node_was_text = false;
+
+                parent.append_child(&comment.create_comment_node())?;
+            }
+            VirtualNode::Portal(portal) => {
+                previous_node_was_text = false;
+
+                let placeholder = portal.create_portal_placeholder(node_idx, events)?;
+                parent.append_child(&placeholder)?;
+            }
+            VirtualNode::Fragment(_) => {
+                unreachable!("Fragments should have been flattened into their children.")
+            }
+            VirtualNode::Component(_) => {
+                unreachable!("Components should have been replaced by their rendered nodes.")
+            }
+        }
     }
+
+    Ok(())
 }
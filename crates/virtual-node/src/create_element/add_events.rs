@@ -7,7 +7,7 @@ use crate::event::EVENTS_ID_PROP;
 use js_sys::Reflect;
 
 impl VElement {
-    pub(super) fn add_events(&self, element: &Element, events: &EventsByNodeIdx, node_idx: u32) {
+    pub(crate) fn add_events(&self, element: &Element, events: &EventsByNodeIdx, node_idx: u32) {
         let needs_create_closures = self.events.has_events();
         if needs_create_closures {
             for (onevent, callback) in self.events.events() {
@@ -0,0 +1,237 @@
+//! Escaping text and attribute values when turning virtual nodes into HTML strings.
+//!
+//! We follow the HTML fragment serialization algorithm, so the strings that we generate match what
+//! a browser would give you for an element's `outerHTML`. The one exception is the text of a
+//! `<script>` or `<style>` that contains its own end tag, which a browser would write as is even
+//! though parsing the HTML again would close the element early.
+//!
+//! https://html.spec.whatwg.org/multipage/parsing.html#escapingString
+
+use std::fmt;
+
+/// Writes text content with `&`, `<`, `>` and non-breaking spaces escaped.
+pub(crate) struct EscapeText<'a>(pub &'a str);
+
+/// Writes a double quoted attribute value with `&`, `"`, `<`, `>` and non-breaking spaces escaped.
+pub(crate) struct EscapeAttribute<'a>(pub &'a str);
+
+/// A writer that escapes everything that gets written into it like [`EscapeAttribute`] does, so
+/// that values such as numbers and styles can be written into a double quoted attribute value using
+/// their `Display` implementation without first collecting them into a `String`.
+pub(crate) struct AttributeWriter<'a, W>(pub &'a mut W);
+
+/// Writes the text of a comment with `<` and `>` escaped, so that the text cannot end the comment
+/// early.
+///
+/// Browsers do not decode character references inside of comments, so the escaped characters show
+/// up as is in the comment's data.
+pub(crate) struct EscapeComment<'a>(pub &'a str);
+
+/// Writes the text content of a raw text element such as `<script>` or `<style>`.
+///
+/// Browsers do not decode character references inside of raw text elements, so the text is written
+/// as is, except for the `</` of the element's own end tag, such as `</script`, which gets written
+/// as `<\/` so that the text cannot close the element early. The end tag is matched ignoring case,
+/// just like browsers do.
+pub(crate) struct RawText<'a> {
+    /// The tag of the raw text element, such as `"script"`.
+    pub tag: &'a str,
+    pub text: &'a str,
+}
+
+/// Elements whose text content is not parsed as HTML.
+pub(crate) fn is_raw_text_element(tag: &str) -> bool {
+    tag.eq_ignore_ascii_case("script") || tag.eq_ignore_ascii_case("style")
+}
+
+impl fmt::Display for EscapeText<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_escaped(f, self.0, |c| match c {
+            '&' => Some("&amp;"),
+            '\u{a0}' => Some("&nbsp;"),
+            '<' => Some("&lt;"),
+            '>' => Some("&gt;"),
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for EscapeAttribute<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_escaped(f, self.0, escaped_attribute_char)
+    }
+}
+
+impl<W: fmt::Write> fmt::Write for AttributeWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        write_escaped(self.0, s, escaped_attribute_char)
+    }
+}
+
+fn escaped_attribute_char(c: char) -> Option<&'static str> {
+    match c {
+        '&' => Some("&amp;"),
+        '\u{a0}' => Some("&nbsp;"),
+        '"' => Some("&quot;"),
+        '<' => Some("&lt;"),
+        '>' => Some("&gt;"),
+        _ => None,
+    }
+}
+
+impl fmt::Display for EscapeComment<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_escaped(f, self.0, |c| match c {
+            '<' => Some("&lt;"),
+            '>' => Some("&gt;"),
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for RawText<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut rest = self.text;
+
+        while let Some(pos) = rest.find("</") {
+            let after = &rest[pos + 2..];
+            let closes_element = after
+                .get(..self.tag.len())
+                .is_some_and(|name| name.eq_ignore_ascii_case(self.tag));
+
+            f.write_str(&rest[..pos])?;
+            f.write_str(if closes_element { "<\\/" } else { "</" })?;
+            rest = after;
+        }
+
+        f.write_str(rest)
+    }
+}
+
+/// Write the string, replacing every character that has an escaped form.
+///
+/// Unescaped runs of characters are written all at once.
+fn write_escaped<W: fmt::Write + ?Sized>(
+    f: &mut W,
+    text: &str,
+    escaped: impl Fn(char) -> Option<&'static str>,
+) -> fmt::Result {
+    let mut unescaped_start = 0;
+
+    for (idx, c) in text.char_indices() {
+        if let Some(escaped) = escaped(c) {
+            f.write_str(&text[unescaped_start..idx])?;
+            f.write_str(escaped)?;
+            unescaped_start = idx + c.len_utf8();
+        }
+    }
+
+    f.write_str(&text[unescaped_start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that we escape text content.
+    #[test]
+    fn escape_text() {
+        let tests = [
+            ("", ""),
+            ("plain text", "plain text"),
+            ("Tom & Jerry", "Tom &amp; Jerry"),
+            ("&amp;", "&amp;amp;"),
+            ("1 < 2 > 0", "1 &lt; 2 &gt; 0"),
+            (
+                "<script>alert('hi')</script>",
+                "&lt;script&gt;alert('hi')&lt;/script&gt;",
+            ),
+            (r#"Quotes "stay" 'as is'"#, r#"Quotes "stay" 'as is'"#),
+            ("non\u{a0}breaking", "non&nbsp;breaking"),
+            ("héllo wörld 你好 🦀", "héllo wörld 你好 🦀"),
+            ("🦀<🦀>🦀", "🦀&lt;🦀&gt;🦀"),
+            ("<!-- comment -->", "&lt;!-- comment --&gt;"),
+        ];
+
+        for (text, expected) in tests.iter() {
+            assert_eq!(&EscapeText(text).to_string(), expected, "{}", text);
+        }
+    }
+
+    /// Verify that we escape attribute values.
+    #[test]
+    fn escape_attribute() {
+        let tests = [
+            ("", ""),
+            ("some-id", "some-id"),
+            (r#"" onclick="alert(1)"#, "&quot; onclick=&quot;alert(1)"),
+            ("'single'", "'single'"),
+            ("a&b", "a&amp;b"),
+            ("/search?a=1&b=2", "/search?a=1&amp;b=2"),
+            ("<script></script>", "&lt;script&gt;&lt;/script&gt;"),
+            ("non\u{a0}breaking", "non&nbsp;breaking"),
+            ("日本語 ✓", "日本語 ✓"),
+        ];
+
+        for (value, expected) in tests.iter() {
+            assert_eq!(&EscapeAttribute(value).to_string(), expected, "{}", value);
+        }
+    }
+
+    /// Verify that values written using their `Display` implementation get escaped.
+    #[test]
+    fn attribute_writer() {
+        use std::fmt::Write;
+
+        let mut escaped = String::new();
+        write!(AttributeWriter(&mut escaped), "\"a\"&{}", 1.5).unwrap();
+
+        assert_eq!(escaped, "&quot;a&quot;&amp;1.5");
+    }
+
+    /// Verify that comment text cannot end the comment.
+    #[test]
+    fn escape_comment() {
+        let tests = [
+            (" ", " "),
+            ("a & b", "a & b"),
+            (
+                "--><script></script>",
+                "--&gt;&lt;script&gt;&lt;/script&gt;",
+            ),
+            ("--!>", "--!&gt;"),
+            ("<!-", "&lt;!-"),
+        ];
+
+        for (text, expected) in tests.iter() {
+            assert_eq!(&EscapeComment(text).to_string(), expected, "{}", text);
+        }
+    }
+
+    /// Verify that raw text cannot close its element.
+    #[test]
+    fn raw_text() {
+        let tests = [
+            ("script", "if (a < b && c > d) {}", "if (a < b && c > d) {}"),
+            (
+                "script",
+                "let s = \"</script>\";",
+                "let s = \"<\\/script>\";",
+            ),
+            ("script", "</SCRIPT></style>", "<\\/SCRIPT></style>"),
+            ("style", "</SCRIPT></Style >", "</SCRIPT><\\/Style >"),
+            ("script", "a </ b", "a </ b"),
+            (
+                "script",
+                "let html = \"<b>x</b>\";",
+                "let html = \"<b>x</b>\";",
+            ),
+            ("script", "</scrip", "</scrip"),
+            ("script", "ünïcödé </ü", "ünïcödé </ü"),
+        ];
+
+        for (tag, text, expected) in tests.iter() {
+            assert_eq!(&RawText { tag, text }.to_string(), expected, "{}", text);
+        }
+    }
+}
@@ -6,8 +6,10 @@ use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 
 pub use self::event_handlers::*;
-pub use self::event_name::EventName;
-pub use self::events_by_node_idx::{EventsByNodeIdx, ManagedEvent, EVENTS_ID_PROP};
+pub use self::event_name::{DelegatedEvent, EventName};
+pub use self::events_by_node_idx::{
+    add_delegated_listener, EventsByNodeIdx, ManagedEvent, EVENTS_ID_PROP,
+};
 pub use self::non_delegated_event_wrapper::insert_non_delegated_event;
 
 mod event_handlers;
@@ -64,6 +66,86 @@ impl Events {
         self.events
             .insert(event_name, EventHandler::MouseEvent(event));
     }
+
+    /// Insert an input event handler.
+    pub fn insert_input_event(
+        &mut self,
+        event_name: EventName,
+        event: Rc<RefCell<dyn FnMut(web_sys::InputEvent)>>,
+    ) {
+        self.events
+            .insert(event_name, EventHandler::InputEvent(event));
+    }
+
+    /// Insert a keyboard event handler.
+    pub fn insert_keyboard_event(
+        &mut self,
+        event_name: EventName,
+        event: Rc<RefCell<dyn FnMut(web_sys::KeyboardEvent)>>,
+    ) {
+        self.events
+            .insert(event_name, EventHandler::KeyboardEvent(event));
+    }
+
+    /// Insert a submit event handler.
+    pub fn insert_submit_event(
+        &mut self,
+        event_name: EventName,
+        event: Rc<RefCell<dyn FnMut(web_sys::SubmitEvent)>>,
+    ) {
+        self.events
+            .insert(event_name, EventHandler::SubmitEvent(event));
+    }
+
+    /// Insert a wheel event handler.
+    pub fn insert_wheel_event(
+        &mut self,
+        event_name: EventName,
+        event: Rc<RefCell<dyn FnMut(web_sys::WheelEvent)>>,
+    ) {
+        self.events
+            .insert(event_name, EventHandler::WheelEvent(event));
+    }
+
+    /// Insert a drag event handler.
+    pub fn insert_drag_event(
+        &mut self,
+        event_name: EventName,
+        event: Rc<RefCell<dyn FnMut(web_sys::DragEvent)>>,
+    ) {
+        self.events
+            .insert(event_name, EventHandler::DragEvent(event));
+    }
+
+    /// Insert a pointer event handler.
+    pub fn insert_pointer_event(
+        &mut self,
+        event_name: EventName,
+        event: Rc<RefCell<dyn FnMut(web_sys::PointerEvent)>>,
+    ) {
+        self.events
+            .insert(event_name, EventHandler::PointerEvent(event));
+    }
+
+    /// Insert a touch event handler.
+    pub fn insert_touch_event(
+        &mut self,
+        event_name: EventName,
+        event: Rc<RefCell<dyn FnMut(web_sys::TouchEvent)>>,
+    ) {
+        self.events
+            .insert(event_name, EventHandler::TouchEvent(event));
+    }
+
+    /// Insert a focus event handler.
+    pub fn insert_focus_event(
+        &mut self,
+        event_name: EventName,
+        event: Rc<RefCell<dyn FnMut(web_sys::FocusEvent)>>,
+    ) {
+        self.events
+            .insert(event_name, EventHandler::FocusEvent(event));
+    }
 }
 
 impl Events {
@@ -3,6 +3,7 @@ use std::cell::{Cell, RefCell};
 use std::fmt::{Debug, Formatter};
 use std::ops::Deref;
 use std::rc::Rc;
+use wasm_bindgen::{JsCast, JsValue};
 
 /// Event handlers such as the closure in `onclick = |event| {}`.
 ///
@@ -13,13 +14,64 @@ use std::rc::Rc;
 pub enum EventHandler {
     /// A callback that does not contain any arguments.
     NoArgs(Rc<RefCell<dyn FnMut()>>),
-    /// Handle mouse events such as `onclick` and `oninput`
+    /// Handle mouse events such as `onclick` and `onmousemove`.
     MouseEvent(Rc<RefCell<dyn FnMut(MouseEvent)>>),
+    /// Handle input events such as `oninput` and `onbeforeinput`.
+    InputEvent(Rc<RefCell<dyn FnMut(web_sys::InputEvent)>>),
+    /// Handle keyboard events such as `onkeydown` and `onkeyup`.
+    KeyboardEvent(Rc<RefCell<dyn FnMut(web_sys::KeyboardEvent)>>),
+    /// Handle the `onsubmit` event.
+    SubmitEvent(Rc<RefCell<dyn FnMut(web_sys::SubmitEvent)>>),
+    /// Handle the `onwheel` event.
+    WheelEvent(Rc<RefCell<dyn FnMut(web_sys::WheelEvent)>>),
+    /// Handle drag and drop events such as `ondragstart` and `ondrop`.
+    DragEvent(Rc<RefCell<dyn FnMut(web_sys::DragEvent)>>),
+    /// Handle pointer events such as `onpointerdown` and `onpointermove`.
+    PointerEvent(Rc<RefCell<dyn FnMut(web_sys::PointerEvent)>>),
+    /// Handle touch events such as `ontouchstart` and `ontouchend`.
+    TouchEvent(Rc<RefCell<dyn FnMut(web_sys::TouchEvent)>>),
+    /// Handle focus events such as `onfocus` and `onblur`.
+    FocusEvent(Rc<RefCell<dyn FnMut(web_sys::FocusEvent)>>),
     /// EventHandler's that we do not have a dedicated type for.
     /// This is useful for custom events.
     UnsupportedSignature(EventAttribFn),
 }
 
+impl EventHandler {
+    /// Call the event handler with an event that was dispatched to the DOM.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this is a [`EventHandler::MouseEvent`] and the event is not a mouse event.
+    ///
+    /// The other typed event handlers do not check the type of the event. This lets events that
+    /// browsers sometimes dispatch using a more general type, such as an `input` event on a
+    /// checkbox being a plain `Event`, still be handled.
+    pub fn call_with_event(&self, event: &web_sys::Event) {
+        match self {
+            EventHandler::NoArgs(no_args) => (no_args.borrow_mut())(),
+            EventHandler::MouseEvent(mouse) => {
+                let mouse_event = event.clone().dyn_into().unwrap();
+                (mouse.borrow_mut())(MouseEvent::new(mouse_event));
+            }
+            EventHandler::InputEvent(cb) => (cb.borrow_mut())(event.clone().unchecked_into()),
+            EventHandler::KeyboardEvent(cb) => (cb.borrow_mut())(event.clone().unchecked_into()),
+            EventHandler::SubmitEvent(cb) => (cb.borrow_mut())(event.clone().unchecked_into()),
+            EventHandler::WheelEvent(cb) => (cb.borrow_mut())(event.clone().unchecked_into()),
+            EventHandler::DragEvent(cb) => (cb.borrow_mut())(event.clone().unchecked_into()),
+            EventHandler::PointerEvent(cb) => (cb.borrow_mut())(event.clone().unchecked_into()),
+            EventHandler::TouchEvent(cb) => (cb.borrow_mut())(event.clone().unchecked_into()),
+            EventHandler::FocusEvent(cb) => (cb.borrow_mut())(event.clone().unchecked_into()),
+            EventHandler::UnsupportedSignature(cb) => {
+                let cb: &js_sys::Function = cb.as_ref().as_ref().unchecked_ref();
+
+                let context = JsValue::NULL;
+                cb.call1(&context, event).unwrap();
+            }
+        };
+    }
+}
+
 /// A mouse event.
 ///
 /// [MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/MouseEvent)
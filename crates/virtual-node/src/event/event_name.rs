@@ -30,13 +30,127 @@ impl EventName {
 impl EventName {
     /// Whether or not this event gets handled by our event delegation system.
     /// If not, the event will be attached to the DOM element.
+    ///
+    /// All of the standard events that bubble are delegated, as well as `onfocus` and `onblur`
+    /// which are delegated using the `focusin` and `focusout` events.
+    ///
+    /// Other events that do not bubble, such as `onmouseenter`, `onmouseleave` and `onscroll`, as
+    /// well as any custom events, are attached directly to the DOM element.
     pub fn is_delegated(&self) -> bool {
-        if self == &Self::ONCLICK {
-            true
-        } else {
-            false
-        }
+        self.delegated_event().is_some()
+    }
+
+    /// If this event is delegated, how it gets delegated.
+    pub fn delegated_event(&self) -> Option<&'static DelegatedEvent> {
+        DELEGATED_EVENTS
+            .iter()
+            .find(|delegated| delegated.event_name == *self)
     }
+
+    /// All of the events that are handled by our event delegation system.
+    pub fn delegated_events() -> &'static [DelegatedEvent] {
+        DELEGATED_EVENTS
+    }
+}
+
+/// An event that is handled by a single event listener on the PercyDom's root node, instead of
+/// by an event listener on every element that handles the event.
+#[derive(Debug)]
+pub struct DelegatedEvent {
+    /// The event, such as `onclick`.
+    pub event_name: EventName,
+    /// The DOM event that the root node listens for, such as `click`.
+    ///
+    /// This is usually the same as the event name without the `on` prefix, but events that don't
+    /// bubble are delegated using a similar event that does. For example, `onfocus` is handled by
+    /// listening for `focusin`.
+    pub listen_for: &'static str,
+    /// Whether or not the event handlers of the target's ancestors get called.
+    ///
+    /// `false` for events that don't bubble, such as `onfocus`, even though the event that we
+    /// listen for does.
+    pub bubbles: bool,
+    /// Whether or not the listener is passive as long as none of the event's handlers can cancel
+    /// the event.
+    ///
+    /// `true` for wheel and touch events, since the browser has to wait for a listener that isn't
+    /// passive before it can scroll the page.
+    pub passive: bool,
+}
+
+macro_rules! delegated_events {
+    ($($event_name:literal => $listen_for:literal, $bubbles:literal $(, $passive:ident)?;)*) => {
+        const DELEGATED_EVENTS: &[DelegatedEvent] = &[
+            $(
+                DelegatedEvent {
+                    event_name: EventName(Cow::Borrowed($event_name)),
+                    listen_for: $listen_for,
+                    bubbles: $bubbles,
+                    passive: delegated_events!(@passive $($passive)?),
+                },
+            )*
+        ];
+    };
+    (@passive passive) => { true };
+    (@passive) => { false };
+}
+
+delegated_events! {
+    "onclick" => "click", true;
+    "ondblclick" => "dblclick", true;
+    "onauxclick" => "auxclick", true;
+    "oncontextmenu" => "contextmenu", true;
+    "onmousedown" => "mousedown", true;
+    "onmouseup" => "mouseup", true;
+    "onmousemove" => "mousemove", true;
+    "onmouseover" => "mouseover", true;
+    "onmouseout" => "mouseout", true;
+    "onpointerdown" => "pointerdown", true;
+    "onpointerup" => "pointerup", true;
+    "onpointermove" => "pointermove", true;
+    "onpointerover" => "pointerover", true;
+    "onpointerout" => "pointerout", true;
+    "onpointercancel" => "pointercancel", true;
+    "ongotpointercapture" => "gotpointercapture", true;
+    "onlostpointercapture" => "lostpointercapture", true;
+    "ontouchstart" => "touchstart", true, passive;
+    "ontouchmove" => "touchmove", true, passive;
+    "ontouchend" => "touchend", true, passive;
+    "ontouchcancel" => "touchcancel", true, passive;
+    "onwheel" => "wheel", true, passive;
+    "onkeydown" => "keydown", true;
+    "onkeyup" => "keyup", true;
+    "onkeypress" => "keypress", true;
+    "onbeforeinput" => "beforeinput", true;
+    "oninput" => "input", true;
+    "onchange" => "change", true;
+    "onselect" => "select", true;
+    "onsubmit" => "submit", true;
+    "onreset" => "reset", true;
+    "onfocusin" => "focusin", true;
+    "onfocusout" => "focusout", true;
+    "onfocus" => "focusin", false;
+    "onblur" => "focusout", false;
+    "oncompositionstart" => "compositionstart", true;
+    "oncompositionupdate" => "compositionupdate", true;
+    "oncompositionend" => "compositionend", true;
+    "oncopy" => "copy", true;
+    "oncut" => "cut", true;
+    "onpaste" => "paste", true;
+    "ondrag" => "drag", true;
+    "ondragstart" => "dragstart", true;
+    "ondragend" => "dragend", true;
+    "ondragenter" => "dragenter", true;
+    "ondragleave" => "dragleave", true;
+    "ondragover" => "dragover", true;
+    "ondrop" => "drop", true;
+    "onanimationstart" => "animationstart", true;
+    "onanimationend" => "animationend", true;
+    "onanimationiteration" => "animationiteration", true;
+    "ontransitionstart" => "transitionstart", true;
+    "ontransitionend" => "transitionend", true;
+    "ontransitionrun" => "transitionrun", true;
+    "ontransitioncancel" => "transitioncancel", true;
 }
 
 impl EventName {
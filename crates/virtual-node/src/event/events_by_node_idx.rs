@@ -4,6 +4,7 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 use wasm_bindgen::JsValue;
+use web_sys::{AddEventListenerOptions, Element};
 
 /// Private type used to attach identifiers to DOM elements so that we can look up their event
 /// callbacks.
@@ -41,6 +42,34 @@ pub struct EventsByNodeIdx {
     events: Events,
     // Never changes after creation.
     events_id_props_prefix: f64,
+    delegation_targets: Rc<RefCell<DelegationTargets>>,
+}
+
+/// Events that happen inside of a portal don't bubble up to the PercyDom's root node, so the
+/// event delegation listeners also get attached to the element that holds each portal's children.
+#[derive(Default)]
+struct DelegationTargets {
+    /// The DOM events that have at least one handler, and whether or not their listener can be
+    /// passive.
+    needed: HashMap<&'static str, bool>,
+    /// The attached listeners, and whether or not they are passive.
+    listeners: HashMap<&'static str, (js_sys::Function, bool)>,
+    portal_containers: Vec<Element>,
+}
+
+impl DelegationTargets {
+    /// Keep track of a handler for a delegated event, so that its DOM event gets a listener.
+    fn need_listener(&mut self, event_name: &EventName, handler: &EventHandler) {
+        let delegated = match event_name.delegated_event() {
+            Some(delegated) => delegated,
+            None => return,
+        };
+        // Handlers that don't receive the event can't prevent its default action.
+        let passive = delegated.passive && matches!(handler, EventHandler::NoArgs(_));
+
+        let needed = self.needed.entry(delegated.listen_for).or_insert(passive);
+        *needed = *needed && passive;
+    }
 }
 
 /// An event that to be managed by the PercyDom.
@@ -70,6 +99,7 @@ impl EventsByNodeIdx {
         EventsByNodeIdx {
             events: Rc::new(RefCell::new(Default::default())),
             events_id_props_prefix: js_sys::Math::random(),
+            delegation_targets: Rc::new(RefCell::new(Default::default())),
         }
     }
 
@@ -87,6 +117,12 @@ impl EventsByNodeIdx {
     pub fn insert_managed_event(&self, node_idx: u32, event_name: EventName, event: ManagedEvent) {
         assert_eq!(event_name.is_delegated(), event.is_delegated());
 
+        if let ManagedEvent::Delegated(handler) = &event {
+            self.delegation_targets
+                .borrow_mut()
+                .need_listener(&event_name, handler);
+        }
+
         self.events
             .borrow_mut()
             .entry(node_idx)
@@ -117,6 +153,12 @@ impl EventsByNodeIdx {
             ManagedEvent::NonDelegated(func, _) => func,
         };
 
+        if event_name.is_delegated() {
+            self.delegation_targets
+                .borrow_mut()
+                .need_listener(event_name, &event);
+        }
+
         *func = event;
     }
 
@@ -129,6 +171,27 @@ impl EventsByNodeIdx {
         }
     }
 
+    /// Move the events of many nodes at once.
+    ///
+    /// All of the old node IDs are removed before any of the new node IDs are inserted, so one
+    /// node's new ID can be another node's old ID.
+    pub fn move_events_batch(&mut self, old_and_new_node_ids: &[(u32, u32)]) {
+        let mut events = self.events.borrow_mut();
+
+        let moved: Vec<_> = old_and_new_node_ids
+            .iter()
+            .filter_map(|(old_node_id, new_node_id)| {
+                events
+                    .remove(old_node_id)
+                    .map(|old_events| (*new_node_id, old_events))
+            })
+            .collect();
+
+        for (new_node_id, old_events) in moved {
+            events.insert(new_node_id, old_events);
+        }
+    }
+
     /// Remove a managed event.
     pub fn remove_managed_event(&mut self, node_id: &u32, event_name: &EventName) -> ManagedEvent {
         self.events
@@ -183,4 +246,122 @@ impl EventsByNodeIdx {
     pub fn remove_node(&self, node_id: &u32) {
         self.events.borrow_mut().remove(node_id);
     }
+
+    /// Make sure that there is an `"input"` listener, even if no element has an `oninput` handler,
+    /// since the listener puts back the value of controlled form controls.
+    pub fn need_input_listener(&self) {
+        self.delegation_targets
+            .borrow_mut()
+            .needed
+            .entry("input")
+            .or_insert(false);
+    }
+
+    /// The DOM events, such as `"click"`, whose event delegation listener is missing, along with
+    /// whether or not the listener should be passive.
+    ///
+    /// A DOM event needs a listener once one of its delegated events has a handler. A passive
+    /// listener also gets replaced once a handler that can cancel the event gets inserted.
+    pub fn missing_delegated_listeners(&self) -> Vec<(&'static str, bool)> {
+        let targets = self.delegation_targets.borrow();
+
+        targets
+            .needed
+            .iter()
+            .filter(
+                |(listen_for, passive)| match targets.listeners.get(*listen_for) {
+                    Some((_, attached_passive)) => *attached_passive && !**passive,
+                    None => true,
+                },
+            )
+            .map(|(listen_for, passive)| (*listen_for, *passive))
+            .collect()
+    }
+
+    /// The DOM events that have an event delegation listener, along with whether or not the
+    /// listener is passive.
+    pub fn delegated_listeners(&self) -> Vec<(&'static str, bool)> {
+        self.delegation_targets
+            .borrow()
+            .listeners
+            .iter()
+            .map(|(listen_for, (_, passive))| (*listen_for, *passive))
+            .collect()
+    }
+
+    /// Set the event delegation listener for a DOM event, such as `"click"`, and attach it to
+    /// every portal container, replacing the previous listener for that DOM event.
+    ///
+    /// The PercyDom attaches the listener to its root node itself.
+    pub fn set_delegated_listener(
+        &self,
+        listen_for: &'static str,
+        listener: js_sys::Function,
+        passive: bool,
+    ) {
+        let mut targets = self.delegation_targets.borrow_mut();
+
+        let previous = targets
+            .listeners
+            .insert(listen_for, (listener.clone(), passive));
+
+        for container in targets.portal_containers.iter() {
+            if let Some((previous, _)) = &previous {
+                container
+                    .remove_event_listener_with_callback(listen_for, previous)
+                    .unwrap();
+            }
+
+            add_delegated_listener(container, listen_for, &listener, passive);
+        }
+    }
+
+    /// Attach the event delegation listeners to the element that holds a portal's children.
+    pub fn add_portal_container(&self, container: &Element) {
+        let mut targets = self.delegation_targets.borrow_mut();
+
+        for (listen_for, (listener, passive)) in targets.listeners.iter() {
+            add_delegated_listener(container, listen_for, listener, *passive);
+        }
+
+        targets.portal_containers.push(container.clone());
+    }
+
+    /// Detach the event delegation listeners from the element that holds a portal's children,
+    /// since the portal was removed.
+    pub fn remove_portal_container(&self, container: &Element) {
+        let mut targets = self.delegation_targets.borrow_mut();
+
+        for (listen_for, (listener, _)) in targets.listeners.iter() {
+            container
+                .remove_event_listener_with_callback(listen_for, listener)
+                .unwrap();
+        }
+
+        targets.portal_containers.retain(|c| c != container);
+    }
+
+    /// The elements that hold the children of the portals whose events are handled by these
+    /// events.
+    pub fn portal_containers(&self) -> Vec<Element> {
+        self.delegation_targets.borrow().portal_containers.clone()
+    }
+}
+
+/// Attach an event delegation listener to the root node or to a portal container.
+#[doc(hidden)]
+pub fn add_delegated_listener(
+    target: &web_sys::EventTarget,
+    listen_for: &str,
+    listener: &js_sys::Function,
+    passive: bool,
+) {
+    let options = AddEventListenerOptions::new();
+    options.set_passive(passive);
+
+    target
+        .add_event_listener_with_callback_and_add_event_listener_options(
+            listen_for, listener, &options,
+        )
+        .unwrap();
 }
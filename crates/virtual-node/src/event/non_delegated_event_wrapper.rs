@@ -1,10 +1,8 @@
-use crate::event::{
-    EventHandler, EventName, EventsByNodeIdx, ManagedEvent, MouseEvent, EVENTS_ID_PROP,
-};
+use crate::event::{EventHandler, EventName, EventsByNodeIdx, ManagedEvent, EVENTS_ID_PROP};
 use js_sys::Reflect;
 use std::rc::Rc;
 use wasm_bindgen::closure::Closure;
-use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen::JsCast;
 
 /// Insert a non-delegated event
 pub fn insert_non_delegated_event(
@@ -37,18 +35,7 @@ pub fn insert_non_delegated_event(
             .get_event_handler(&node_id, &event_name)
             .unwrap();
 
-        match cb {
-            EventHandler::NoArgs(no_args) => (no_args.borrow_mut())(),
-            EventHandler::MouseEvent(mouse) => {
-                (mouse.borrow_mut())(MouseEvent::new(event.dyn_into().unwrap()));
-            }
-            EventHandler::UnsupportedSignature(cb) => {
-                let cb: &js_sys::Function = cb.as_ref().as_ref().unchecked_ref();
-
-                let context = JsValue::NULL;
-                cb.call1(&context, &event).unwrap();
-            }
-        };
+        cb.call_with_event(&event);
     };
 
     let callback_wrapper = Box::new(callback_wrapper) as Box<dyn FnMut(_) -> ()>;
@@ -0,0 +1,532 @@
+//! Hydrating DOM nodes that were rendered on the server.
+//!
+//! Instead of creating new DOM nodes we walk the existing DOM alongside the virtual node tree,
+//! attaching events and calling on create element functions as if we had created the nodes
+//! ourselves.
+//!
+//! When the DOM does not match the virtual nodes we repair the DOM so that it matches, and record
+//! a [`HydrationMismatch`].
+
+use js_sys::Array;
+use wasm_bindgen::JsCast;
+use web_sys::{Comment, Element, Node, Text};
+
+use crate::event::EventsByNodeIdx;
+use crate::{
+    remove_element_attribute, set_dom_property, set_element_attribute, AttributeTarget,
+    AttributeValue, Namespace, Styles, VElement, VText, VirtualNode,
+};
+
+/// A difference between the DOM and the virtual node that was used to hydrate it.
+///
+/// The DOM has already been repaired by the time that a mismatch gets reported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HydrationMismatch {
+    /// The depth first index of the virtual node that did not match the DOM.
+    ///
+    /// For [`HydrationMismatchKind::ExtraNode`] this is the index of the parent.
+    pub node_idx: u32,
+    /// How the DOM differed from the virtual node.
+    pub kind: HydrationMismatchKind,
+}
+
+/// The ways that the DOM can differ from the virtual node that is hydrating it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HydrationMismatchKind {
+    /// The DOM node was a different kind of node, or an element with a different tag.
+    /// It was replaced with a newly created node.
+    WrongNode {
+        /// The node that the virtual node describes, such as `<div>` or `#text`.
+        expected: String,
+        /// The node that was in the DOM.
+        found: String,
+    },
+    /// There was no DOM node for the virtual node, so one was created.
+    MissingNode {
+        /// The node that the virtual node describes, such as `<div>` or `#text`.
+        expected: String,
+    },
+    /// A DOM node did not correspond to any virtual node, so it was removed.
+    ExtraNode {
+        /// The node that was in the DOM.
+        found: String,
+    },
+    /// A text node's text was different.
+    Text {
+        /// The text of the virtual node.
+        expected: String,
+        /// The text that was in the DOM.
+        found: String,
+    },
+    /// An attribute's value was different, or the attribute was missing or unexpected.
+    Attribute {
+        /// The name of the attribute.
+        name: String,
+        /// The attribute's value in the virtual node, or `None` if it should not be set.
+        expected: Option<String>,
+        /// The attribute's value in the DOM, or `None` if it was not set.
+        found: Option<String>,
+    },
+}
+
+impl VirtualNode {
+    /// Hydrate a DOM node that was created from this virtual node's HTML string, typically one
+    /// that was rendered on the server.
+    ///
+    /// Events get stored in the `EventsByNodeIdx` and on create element functions get called,
+    /// just like when using [`VirtualNode::create_dom_node`].
+    ///
+    /// Any differences between the DOM and the virtual node are repaired and pushed to the
+    /// `mismatches`.
+    ///
+    /// Returns the hydrated node. This is the node that was passed in unless it had to be
+    /// replaced.
+    pub fn hydrate_dom_node(
+        &self,
+        node: Node,
+        node_idx: u32,
+        events: &mut EventsByNodeIdx,
+        mismatches: &mut Vec<HydrationMismatch>,
+    ) -> Node {
+        let mut copy = node_idx;
+        self.hydrate_node(node, &mut copy, events, mismatches)
+    }
+
+    /// Hydrate this node, leaving the `node_idx` at the index of this node's last descendant.
+    fn hydrate_node(
+        &self,
+        node: Node,
+        node_idx: &mut u32,
+        events: &mut EventsByNodeIdx,
+        mismatches: &mut Vec<HydrationMismatch>,
+    ) -> Node {
+        match self {
+            VirtualNode::Text(text) => match node.dyn_into::<Text>() {
+                Ok(text_node) => {
+                    text.hydrate_text_node(&text_node, *node_idx, mismatches);
+                    text_node.into()
+                }
+                Err(node) => replace_node(self, &node, node_idx, events, mismatches),
+            },
+            VirtualNode::Element(element) => match node.dyn_into::<Element>() {
+                Ok(elem) if element.matches_tag(&elem) => {
+                    element.hydrate_element_node(&elem, node_idx, events, mismatches);
+                    elem.into()
+                }
+                Ok(elem) => replace_node(self, &elem.into(), node_idx, events, mismatches),
+                Err(node) => replace_node(self, &node, node_idx, events, mismatches),
+            },
+            // We keep the comment that is in the DOM even if its text is different, since a
+            // comment's text is never shown.
+            VirtualNode::Comment(_) => match node.dyn_into::<Comment>() {
+                Ok(comment) if !is_text_separator(&comment) => comment.into(),
+                Ok(comment) => replace_node(self, &comment.into(), node_idx, events, mismatches),
+                Err(node) => replace_node(self, &node, node_idx, events, mismatches),
+            },
+            // The portal's children aren't rendered into the HTML, so we keep the placeholder and
+            // create the portal's children.
+            VirtualNode::Portal(portal) => match node.dyn_into::<Comment>() {
+                Ok(comment) if !is_text_separator(&comment) => {
+                    portal
+                        .create_portal_container(&comment, node_idx, events)
+                        .unwrap();
+                    comment.into()
+                }
+                Ok(comment) => replace_node(self, &comment.into(), node_idx, events, mismatches),
+                Err(node) => replace_node(self, &node, node_idx, events, mismatches),
+            },
+            // A fragment's children get flattened into its parent, so the only fragment that we
+            // can be asked to hydrate is the root, which has no single DOM node to match.
+            VirtualNode::Fragment(_) => replace_node(self, &node, node_idx, events, mismatches),
+            VirtualNode::Component(component) => component
+                .rendered()
+                .hydrate_node(node, node_idx, events, mismatches),
+        }
+    }
+}
+
+impl VText {
+    fn hydrate_text_node(
+        &self,
+        text_node: &Text,
+        node_idx: u32,
+        mismatches: &mut Vec<HydrationMismatch>,
+    ) {
+        let found = text_node.data();
+
+        if found != self.text {
+            text_node.set_data(&self.text);
+
+            mismatches.push(HydrationMismatch {
+                node_idx,
+                kind: HydrationMismatchKind::Text {
+                    expected: self.text.clone(),
+                    found,
+                },
+            });
+        }
+    }
+}
+
+impl VElement {
+    fn hydrate_element_node(
+        &self,
+        element: &Element,
+        node_idx: &mut u32,
+        events: &mut EventsByNodeIdx,
+        mismatches: &mut Vec<HydrationMismatch>,
+    ) {
+        self.hydrate_attributes(element, *node_idx, mismatches);
+
+        self.add_events(element, events, *node_idx);
+
+        // The inner HTML replaces all of the children when creating an element, so there are no
+        // child nodes to hydrate.
+        if self.special_attributes.dangerous_inner_html.is_none() {
+            self.hydrate_children(element, node_idx, events, mismatches);
+        } else {
+            skip_descendants(self, node_idx);
+        }
+
+        // Hydrating never fails, so a property that the browser refuses keeps its server rendered
+        // value.
+        for (name, value) in self.dom_property_attributes() {
+            let _ = set_dom_property(element, name, value, events);
+        }
+
+        self.special_attributes.maybe_set_node_ref(element);
+        self.special_attributes
+            .maybe_call_on_create_element(element);
+    }
+
+    fn hydrate_attributes(
+        &self,
+        element: &Element,
+        node_idx: u32,
+        mismatches: &mut Vec<HydrationMismatch>,
+    ) {
+        for (name, value) in self.attrs.iter() {
+            if self.attribute_target(name) == AttributeTarget::Property {
+                continue;
+            }
+
+            let found = element.get_attribute(name);
+
+            let expected = match value {
+                AttributeValue::String(s) => Some(s.clone()),
+                AttributeValue::Bool(true) => Some(found.clone().unwrap_or_default()),
+                AttributeValue::Bool(false) => None,
+                _ => Some(value.to_string()),
+            };
+
+            if found == expected {
+                continue;
+            }
+
+            match &expected {
+                Some(_) => set_element_attribute(element, name, value).unwrap(),
+                None => remove_element_attribute(element, name).unwrap(),
+            };
+
+            mismatches.push(HydrationMismatch {
+                node_idx,
+                kind: HydrationMismatchKind::Attribute {
+                    name: name.clone(),
+                    expected,
+                    found,
+                },
+            });
+        }
+
+        self.hydrate_styles(element, node_idx, mismatches);
+
+        let names: Array = element.get_attribute_names();
+        for name in names.iter() {
+            let name = name.as_string().unwrap();
+
+            if self.attrs.contains_key(&name) || name == "style" {
+                continue;
+            }
+
+            let found = element.get_attribute(&name);
+            element.remove_attribute(&name).unwrap();
+
+            mismatches.push(HydrationMismatch {
+                node_idx,
+                kind: HydrationMismatchKind::Attribute {
+                    name,
+                    expected: None,
+                    found,
+                },
+            });
+        }
+    }
+
+    fn hydrate_styles(
+        &self,
+        element: &Element,
+        node_idx: u32,
+        mismatches: &mut Vec<HydrationMismatch>,
+    ) {
+        let found = element.get_attribute("style");
+
+        // Compare the parsed styles so that differences in whitespace don't count as mismatches.
+        let found_styles = Styles::parse(found.as_deref().unwrap_or_default());
+        if found_styles == self.styles {
+            return;
+        }
+
+        let expected = if self.styles.is_empty() {
+            element.remove_attribute("style").unwrap();
+            None
+        } else {
+            let expected = self.styles.to_string();
+            element.set_attribute("style", &expected).unwrap();
+            Some(expected)
+        };
+
+        mismatches.push(HydrationMismatch {
+            node_idx,
+            kind: HydrationMismatchKind::Attribute {
+                name: "style".to_string(),
+                expected,
+                found,
+            },
+        });
+    }
+
+    fn hydrate_children(
+        &self,
+        element: &Element,
+        node_idx: &mut u32,
+        events: &mut EventsByNodeIdx,
+        mismatches: &mut Vec<HydrationMismatch>,
+    ) {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let parent_idx = *node_idx;
+
+        let mut dom_child = element.first_child();
+        let mut previous_node_was_text = false;
+
+        let children: Vec<&VirtualNode> = self
+            .flat_children()
+            .into_iter()
+            .map(VirtualNode::rendered_node)
+            .collect();
+        for (idx, child) in children.iter().copied().enumerate() {
+            *node_idx += 1;
+
+            match child {
+                VirtualNode::Text(text) => {
+                    // Neighboring text nodes are separated by a comment, see
+                    // `VElement.append_children_to_dom`. HTML strings don't contain these
+                    // separators so we insert them.
+                    if previous_node_was_text {
+                        match dom_child.as_ref().filter(|c| is_text_separator(c)) {
+                            Some(separator) => dom_child = separator.next_sibling(),
+                            None => {
+                                let separator: Node = document.create_comment("ptns").into();
+                                element
+                                    .insert_before(&separator, dom_child.as_ref())
+                                    .unwrap();
+                            }
+                        };
+                    }
+
+                    let next_child_is_text =
+                        matches!(children.get(idx + 1), Some(VirtualNode::Text(_)));
+
+                    dom_child = match dom_child.map(|c| c.dyn_into::<Text>()) {
+                        Some(Ok(text_node)) => {
+                            // When neighboring text nodes get parsed from an HTML string they
+                            // get merged into one text node, so we split them back up.
+                            if next_child_is_text && text_node.data().starts_with(&text.text) {
+                                text_node
+                                    .split_text(text.text.encode_utf16().count() as u32)
+                                    .unwrap();
+                            }
+
+                            text.hydrate_text_node(&text_node, *node_idx, mismatches);
+                            text_node.next_sibling()
+                        }
+                        Some(Err(other)) if text.text.is_empty() => {
+                            // Empty text nodes don't show up in HTML strings.
+                            let text_node = text.create_text_node();
+                            element.insert_before(&text_node, Some(&other)).unwrap();
+                            Some(other)
+                        }
+                        Some(Err(other)) => {
+                            let next = other.next_sibling();
+                            replace_node(child, &other, node_idx, events, mismatches);
+                            next
+                        }
+                        None => {
+                            let text_node = text.create_text_node();
+                            element.append_child(&text_node).unwrap();
+
+                            if !text.text.is_empty() {
+                                mismatches.push(HydrationMismatch {
+                                    node_idx: *node_idx,
+                                    kind: HydrationMismatchKind::MissingNode {
+                                        expected: describe_virtual_node(child),
+                                    },
+                                });
+                            }
+
+                            None
+                        }
+                    };
+
+                    previous_node_was_text = true;
+                }
+                VirtualNode::Element(_) | VirtualNode::Comment(_) | VirtualNode::Portal(_) => {
+                    previous_node_was_text = false;
+
+                    dom_child = match dom_child {
+                        Some(node) => {
+                            let next = node.next_sibling();
+                            child.hydrate_node(node, node_idx, events, mismatches);
+                            next
+                        }
+                        None => {
+                            mismatches.push(HydrationMismatch {
+                                node_idx: *node_idx,
+                                kind: HydrationMismatchKind::MissingNode {
+                                    expected: describe_virtual_node(child),
+                                },
+                            });
+
+                            let namespace = Namespace::for_children_of(element);
+                            let created = create_node(child, node_idx, events, namespace);
+                            element.append_child(&created).unwrap();
+
+                            None
+                        }
+                    };
+                }
+                VirtualNode::Fragment(_) => {
+                    unreachable!("Fragments should have been flattened into their children.")
+                }
+                VirtualNode::Component(_) => {
+                    unreachable!("Components should have been replaced by their rendered nodes.")
+                }
+            }
+        }
+
+        while let Some(extra) = dom_child {
+            dom_child = extra.next_sibling();
+
+            element.remove_child(&extra).unwrap();
+
+            mismatches.push(HydrationMismatch {
+                node_idx: parent_idx,
+                kind: HydrationMismatchKind::ExtraNode {
+                    found: describe_dom_node(&extra),
+                },
+            });
+        }
+    }
+
+    fn matches_tag(&self, element: &Element) -> bool {
+        element.tag_name().eq_ignore_ascii_case(&self.tag)
+    }
+}
+
+/// Replace a DOM node that does not match its virtual node with a newly created node.
+fn replace_node(
+    vnode: &VirtualNode,
+    node: &Node,
+    node_idx: &mut u32,
+    events: &mut EventsByNodeIdx,
+    mismatches: &mut Vec<HydrationMismatch>,
+) -> Node {
+    mismatches.push(HydrationMismatch {
+        node_idx: *node_idx,
+        kind: HydrationMismatchKind::WrongNode {
+            expected: describe_virtual_node(vnode),
+            found: describe_dom_node(node),
+        },
+    });
+
+    let parent = node.parent_node();
+    let namespace = parent
+        .as_ref()
+        .map(Namespace::for_children_of)
+        .unwrap_or_default();
+
+    let created = create_node(vnode, node_idx, events, namespace);
+
+    if let Some(parent) = parent {
+        parent.replace_child(&created, node).unwrap();
+    }
+
+    created
+}
+
+/// Create a DOM node, leaving the `node_idx` at the index of the node's last descendant.
+fn create_node(
+    vnode: &VirtualNode,
+    node_idx: &mut u32,
+    events: &mut EventsByNodeIdx,
+    parent_namespace: Namespace,
+) -> Node {
+    match vnode {
+        VirtualNode::Text(text) => text.create_text_node().into(),
+        VirtualNode::Element(element) => element
+            .create_element_node(node_idx, events, parent_namespace)
+            .unwrap()
+            .into(),
+        VirtualNode::Fragment(fragment) => fragment
+            .create_document_fragment(node_idx, events, parent_namespace)
+            .unwrap()
+            .into(),
+        VirtualNode::Comment(comment) => comment.create_comment_node().into(),
+        VirtualNode::Component(component) => {
+            create_node(component.rendered(), node_idx, events, parent_namespace)
+        }
+        VirtualNode::Portal(portal) => portal
+            .create_portal_placeholder(node_idx, events)
+            .unwrap()
+            .into(),
+    }
+}
+
+/// Advance the `node_idx` to the index of the element's last descendant.
+fn skip_descendants(element: &VElement, node_idx: &mut u32) {
+    skip_children(&element.flat_children(), node_idx);
+}
+
+fn skip_children(children: &[&VirtualNode], node_idx: &mut u32) {
+    for child in children {
+        *node_idx += 1;
+
+        match child.rendered_node() {
+            VirtualNode::Element(child) => skip_descendants(child, node_idx),
+            VirtualNode::Portal(portal) => skip_children(&portal.flat_children(), node_idx),
+            _ => {}
+        }
+    }
+}
+
+fn is_text_separator(node: &Node) -> bool {
+    node.dyn_ref::<Comment>()
+        .map(|comment| comment.data() == "ptns")
+        .unwrap_or(false)
+}
+
+fn describe_virtual_node(node: &VirtualNode) -> String {
+    match node {
+        VirtualNode::Element(element) => format!("<{}>", element.tag),
+        VirtualNode::Text(_) => "#text".to_string(),
+        VirtualNode::Fragment(_) => "#document-fragment".to_string(),
+        VirtualNode::Comment(_) | VirtualNode::Portal(_) => "#comment".to_string(),
+        VirtualNode::Component(component) => describe_virtual_node(component.rendered()),
+    }
+}
+
+fn describe_dom_node(node: &Node) -> String {
+    match node.dyn_ref::<Element>() {
+        Some(element) => format!("<{}>", element.tag_name().to_lowercase()),
+        None => node.node_name().to_lowercase(),
+    }
+}
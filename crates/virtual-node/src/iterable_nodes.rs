@@ -66,12 +66,16 @@ impl<V: View> From<V> for IterableNodes {
     }
 }
 
+/// `None` becomes a placeholder comment so that the nodes after it keep the same positions
+/// whether or not there is a node.
+///
+/// html! { <div> { maybe_node } <span></span> </div> }
 impl<T: Into<IterableNodes>> From<Option<T>> for IterableNodes {
     fn from(opt: Option<T>) -> Self {
         if let Some(val) = opt {
             val.into()
         } else {
-            IterableNodes(vec![])
+            IterableNodes(vec![VirtualNode::placeholder()])
         }
     }
 }
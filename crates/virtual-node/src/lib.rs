@@ -12,20 +12,38 @@
 use std::fmt;
 
 use crate::event::EventsByNodeIdx;
+use wasm_bindgen::JsValue;
 use web_sys::{self, Node};
 
 pub use self::event::EventAttribFn;
+pub use self::hydrate::*;
 pub use self::iterable_nodes::*;
+pub use self::memo::*;
+pub use self::namespace::*;
+pub use self::render_html::HtmlChunks;
+pub use self::vcomment::*;
+pub use self::vcomponent::*;
 pub use self::velement::*;
+pub use self::vfragment::*;
+pub use self::vportal::*;
 pub use self::vtext::*;
 
 pub mod event;
 pub mod test_utils;
 
 mod create_element;
+mod escape;
+mod hydrate;
 
 mod iterable_nodes;
+mod memo;
+mod namespace;
+mod render_html;
+mod vcomment;
+mod vcomponent;
 mod velement;
+mod vfragment;
+mod vportal;
 mod vtext;
 
 /// When building your views you'll typically use the `html!` macro to generate
@@ -53,6 +71,26 @@ pub enum VirtualNode {
     /// order to enable custom methods like `create_text_node()` on the
     /// wrapped type.
     Text(VText),
+    /// A group of sibling nodes that gets placed directly into the parent, without an element
+    /// of its own.
+    ///
+    /// A fragment cannot be the root node of a `PercyDom`, since the root needs to be a single
+    /// DOM node.
+    Fragment(VFragment),
+    /// A comment node (node type `COMMENT_NODE`).
+    ///
+    /// Used as a placeholder for a block that has no nodes, such as a `None`, so that the
+    /// block's siblings keep their positions.
+    Comment(VComment),
+    /// A [`Component`], which gets replaced by the node that it renders.
+    ///
+    /// [`Component`]: trait.Component.html
+    Component(VComponent),
+    /// A group of nodes that get placed into a different DOM element, such as `document.body`,
+    /// while being diffed as part of this tree.
+    ///
+    /// See [`VPortal`].
+    Portal(VPortal),
 }
 
 impl VirtualNode {
@@ -88,6 +126,97 @@ impl VirtualNode {
         VirtualNode::Text(VText::new(text.into()))
     }
 
+    /// Create a new, empty virtual fragment.
+    ///
+    /// ```
+    /// # use virtual_node::VirtualNode;
+    /// let _fragment = VirtualNode::fragment();
+    /// ```
+    pub fn fragment() -> Self {
+        VirtualNode::Fragment(VFragment::new())
+    }
+
+    /// Create a new virtual comment node with the given text.
+    ///
+    /// These get patched into the DOM using `document.createComment`
+    ///
+    /// ```
+    /// # use virtual_node::VirtualNode;
+    /// let _comment = VirtualNode::comment("My comment");
+    /// ```
+    pub fn comment<S>(text: S) -> Self
+    where
+        S: Into<String>,
+    {
+        VirtualNode::Comment(VComment::new(text))
+    }
+
+    /// Create a placeholder comment node that renders as `<!-- -->`.
+    ///
+    /// The `html!` macro uses this for blocks that evaluate to `None`.
+    ///
+    /// ```
+    /// # use virtual_node::VirtualNode;
+    /// assert_eq!(VirtualNode::placeholder().to_string(), "<!-- -->");
+    /// ```
+    pub fn placeholder() -> Self {
+        VirtualNode::Comment(VComment::placeholder())
+    }
+
+    /// Create a new virtual component node, which gets rendered the first time that its rendered
+    /// node is needed.
+    ///
+    /// ```
+    /// # use virtual_node::{Component, ComponentState, VirtualNode};
+    /// #[derive(PartialEq)]
+    /// struct Greeting;
+    ///
+    /// impl Component for Greeting {
+    ///     type State = ();
+    ///
+    ///     fn render(&self, _state: &ComponentState<()>) -> VirtualNode {
+    ///         VirtualNode::text("Hello")
+    ///     }
+    /// }
+    ///
+    /// let _component = VirtualNode::component(Greeting);
+    /// ```
+    pub fn component<C: Component>(component: C) -> Self {
+        VirtualNode::Component(VComponent::new(component))
+    }
+
+    /// Create a new memo node, which only gets rendered and diffed again when its key changes.
+    ///
+    /// See [`Memo`].
+    ///
+    /// ```
+    /// # use virtual_node::VirtualNode;
+    /// let _footer = VirtualNode::memo((), || VirtualNode::element("footer"));
+    /// ```
+    ///
+    /// [`Memo`]: struct.Memo.html
+    pub fn memo<K, F>(key: K, render: F) -> Self
+    where
+        K: PartialEq + 'static,
+        F: Fn() -> VirtualNode + 'static,
+    {
+        VirtualNode::component(Memo::new(key, render))
+    }
+
+    /// Create a new, empty virtual portal that places its children into the first element that
+    /// matches the CSS selector.
+    ///
+    /// ```
+    /// # use virtual_node::VirtualNode;
+    /// let _portal = VirtualNode::portal("body");
+    /// ```
+    pub fn portal<S>(mount_selector: S) -> Self
+    where
+        S: Into<String>,
+    {
+        VirtualNode::Portal(VPortal::new(mount_selector))
+    }
+
     /// Return a [`VElement`] reference, if this is an [`Element`] variant.
     ///
     /// [`VElement`]: struct.VElement.html
@@ -134,18 +263,173 @@ impl VirtualNode {
         }
     }
 
+    /// Return a [`VFragment`] reference, if this is a [`Fragment`] variant.
+    ///
+    /// [`VFragment`]: struct.VFragment.html
+    /// [`Fragment`]: enum.VirtualNode.html#variant.Fragment
+    pub fn as_vfragment_ref(&self) -> Option<&VFragment> {
+        match self {
+            VirtualNode::Fragment(ref fragment) => Some(fragment),
+            _ => None,
+        }
+    }
+
+    /// Return a mutable [`VFragment`] reference, if this is a [`Fragment`] variant.
+    ///
+    /// [`VFragment`]: struct.VFragment.html
+    /// [`Fragment`]: enum.VirtualNode.html#variant.Fragment
+    pub fn as_vfragment_mut(&mut self) -> Option<&mut VFragment> {
+        match self {
+            VirtualNode::Fragment(ref mut fragment) => Some(fragment),
+            _ => None,
+        }
+    }
+
+    /// Return a [`VComment`] reference, if this is a [`Comment`] variant.
+    ///
+    /// [`VComment`]: struct.VComment.html
+    /// [`Comment`]: enum.VirtualNode.html#variant.Comment
+    pub fn as_vcomment_ref(&self) -> Option<&VComment> {
+        match self {
+            VirtualNode::Comment(ref comment) => Some(comment),
+            _ => None,
+        }
+    }
+
+    /// Return a mutable [`VComment`] reference, if this is a [`Comment`] variant.
+    ///
+    /// [`VComment`]: struct.VComment.html
+    /// [`Comment`]: enum.VirtualNode.html#variant.Comment
+    pub fn as_vcomment_mut(&mut self) -> Option<&mut VComment> {
+        match self {
+            VirtualNode::Comment(ref mut comment) => Some(comment),
+            _ => None,
+        }
+    }
+
+    /// Return a [`VComponent`] reference, if this is a [`Component`] variant.
+    ///
+    /// [`VComponent`]: struct.VComponent.html
+    /// [`Component`]: enum.VirtualNode.html#variant.Component
+    pub fn as_vcomponent_ref(&self) -> Option<&VComponent> {
+        match self {
+            VirtualNode::Component(ref component) => Some(component),
+            _ => None,
+        }
+    }
+
+    /// Return a mutable [`VComponent`] reference, if this is a [`Component`] variant.
+    ///
+    /// [`VComponent`]: struct.VComponent.html
+    /// [`Component`]: enum.VirtualNode.html#variant.Component
+    pub fn as_vcomponent_mut(&mut self) -> Option<&mut VComponent> {
+        match self {
+            VirtualNode::Component(ref mut component) => Some(component),
+            _ => None,
+        }
+    }
+
+    /// Return a [`VPortal`] reference, if this is a [`Portal`] variant.
+    ///
+    /// [`VPortal`]: struct.VPortal.html
+    /// [`Portal`]: enum.VirtualNode.html#variant.Portal
+    pub fn as_vportal_ref(&self) -> Option<&VPortal> {
+        match self {
+            VirtualNode::Portal(ref portal) => Some(portal),
+            _ => None,
+        }
+    }
+
+    /// Return a mutable [`VPortal`] reference, if this is a [`Portal`] variant.
+    ///
+    /// [`VPortal`]: struct.VPortal.html
+    /// [`Portal`]: enum.VirtualNode.html#variant.Portal
+    pub fn as_vportal_mut(&mut self) -> Option<&mut VPortal> {
+        match self {
+            VirtualNode::Portal(ref mut portal) => Some(portal),
+            _ => None,
+        }
+    }
+
+    /// The node that ends up in the DOM in place of this one.
+    ///
+    /// For a component this is the node that the component rendered (rendering the component if
+    /// needed). Every other node is its own rendered node.
+    pub fn rendered_node(&self) -> &VirtualNode {
+        match self {
+            VirtualNode::Component(component) => component.rendered().rendered_node(),
+            _ => self,
+        }
+    }
+
     /// Create and return a `CreatedNode` instance (containing a DOM `Node`
     /// together with potentially related closures) for this virtual node.
+    ///
+    /// Fragments become a `DocumentFragment`, with the fragment's children indexed as if the
+    /// fragment was their parent element.
+    ///
+    /// The node gets created as if its parent was an HTML element. Use
+    /// [`VirtualNode::create_dom_node_in`] to create a node that goes inside of an SVG or MathML
+    /// element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node can't be created, see [`VirtualNode::try_create_dom_node_in`].
     pub fn create_dom_node(&self, node_idx: u32, events: &mut EventsByNodeIdx) -> Node {
+        self.create_dom_node_in(node_idx, events, Namespace::Html)
+    }
+
+    /// Create a DOM node for this virtual node, where the node's parent element is in the given
+    /// namespace.
+    ///
+    /// So an `<a>` that is going to be appended to an `<svg>` gets created as an SVG element.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the node can't be created, see [`VirtualNode::try_create_dom_node_in`].
+    pub fn create_dom_node_in(
+        &self,
+        node_idx: u32,
+        events: &mut EventsByNodeIdx,
+        parent_namespace: Namespace,
+    ) -> Node {
+        self.try_create_dom_node_in(node_idx, events, parent_namespace)
+            .unwrap()
+    }
+
+    /// Create a DOM node for this virtual node, where the node's parent element is in the given
+    /// namespace.
+    ///
+    /// Returns the error that the browser threw if a node could not be created, such as when an
+    /// element's tag or an attribute's name is not valid, or when there is no element that
+    /// matches a portal's mount selector.
+    pub fn try_create_dom_node_in(
+        &self,
+        node_idx: u32,
+        events: &mut EventsByNodeIdx,
+        parent_namespace: Namespace,
+    ) -> Result<Node, JsValue> {
         let mut copy = node_idx;
         let copy = &mut copy;
 
-        match self {
+        let node = match self {
             VirtualNode::Text(text_node) => text_node.create_text_node().into(),
-            VirtualNode::Element(element_node) => {
-                element_node.create_element_node(copy, events).into()
+            VirtualNode::Element(element_node) => element_node
+                .create_element_node(copy, events, parent_namespace)?
+                .into(),
+            VirtualNode::Fragment(fragment) => fragment
+                .create_document_fragment(copy, events, parent_namespace)?
+                .into(),
+            VirtualNode::Comment(comment) => comment.create_comment_node().into(),
+            VirtualNode::Component(component) => {
+                component
+                    .rendered()
+                    .try_create_dom_node_in(node_idx, events, parent_namespace)?
             }
-        }
+            VirtualNode::Portal(portal) => portal.create_portal_placeholder(copy, events)?.into(),
+        };
+
+        Ok(node)
     }
 
     /// Used by html-macro to insert space before text that is inside of a block that came after
@@ -184,6 +468,15 @@ impl VirtualNode {
 pub trait View {
     /// Render a VirtualNode, or any IntoIter<VirtualNode>
     fn render(&self) -> VirtualNode;
+
+    // Used by the html! macro
+    #[doc(hidden)]
+    fn __into_virtual_node(self) -> VirtualNode
+    where
+        Self: Sized,
+    {
+        self.render()
+    }
 }
 
 impl<V> From<&V> for VirtualNode
@@ -207,6 +500,30 @@ impl From<VElement> for VirtualNode {
     }
 }
 
+impl From<VFragment> for VirtualNode {
+    fn from(other: VFragment) -> Self {
+        VirtualNode::Fragment(other)
+    }
+}
+
+impl From<VComment> for VirtualNode {
+    fn from(other: VComment) -> Self {
+        VirtualNode::Comment(other)
+    }
+}
+
+impl From<VComponent> for VirtualNode {
+    fn from(other: VComponent) -> Self {
+        VirtualNode::Component(other)
+    }
+}
+
+impl From<VPortal> for VirtualNode {
+    fn from(other: VPortal) -> Self {
+        VirtualNode::Portal(other)
+    }
+}
+
 impl From<&str> for VirtualNode {
     fn from(other: &str) -> Self {
         VirtualNode::text(other)
@@ -240,6 +557,10 @@ impl fmt::Debug for VirtualNode {
         match self {
             VirtualNode::Element(e) => write!(f, "Node::{:?}", e),
             VirtualNode::Text(t) => write!(f, "Node::{:?}", t),
+            VirtualNode::Fragment(fragment) => write!(f, "Node::{:?}", fragment),
+            VirtualNode::Comment(comment) => write!(f, "Node::{:?}", comment),
+            VirtualNode::Component(component) => write!(f, "Node::{:?}", component),
+            VirtualNode::Portal(portal) => write!(f, "Node::{:?}", portal),
         }
     }
 }
@@ -250,6 +571,10 @@ impl fmt::Display for VirtualNode {
         match self {
             VirtualNode::Element(element) => write!(f, "{}", element),
             VirtualNode::Text(text) => write!(f, "{}", text),
+            VirtualNode::Fragment(_) => self.write_html(f),
+            VirtualNode::Comment(comment) => write!(f, "{}", comment),
+            VirtualNode::Component(_) => self.write_html(f),
+            VirtualNode::Portal(portal) => write!(f, "{}", portal),
         }
     }
 }
@@ -310,4 +635,63 @@ mod tests {
 
         assert_eq!(button.to_string(), expected);
     }
+
+    /// Verify that text and attribute values are escaped when turned into a string.
+    #[test]
+    fn escapes_text_and_attributes() {
+        let mut div = VElement::new("div");
+        div.attrs
+            .insert("title".into(), r#""><script>alert("x")</script>"#.into());
+        div.children.push(VirtualNode::text(
+            "</div><script>alert('y')</script> & more",
+        ));
+
+        let expected = concat!(
+            r#"<div title="&quot;&gt;&lt;script&gt;alert(&quot;x&quot;)&lt;/script&gt;">"#,
+            "&lt;/div&gt;&lt;script&gt;alert('y')&lt;/script&gt; &amp; more",
+            "</div>"
+        );
+
+        assert_eq!(VirtualNode::Element(div).to_string(), expected);
+    }
+
+    /// Verify that the text inside of a script element is not escaped, but cannot close the
+    /// script element early.
+    #[test]
+    fn script_text_is_not_escaped() {
+        let mut script = VElement::new("script");
+        script
+            .children
+            .push(VirtualNode::text(r#"if (a < b && c) { x = "</script>"; }"#));
+
+        let expected = r#"<script>if (a < b && c) { x = "<\/script>"; }</script>"#;
+
+        assert_eq!(VirtualNode::Element(script).to_string(), expected);
+    }
+
+    /// Verify that the dangerous inner html is written without being escaped, in place of the
+    /// element's children.
+    #[test]
+    fn dangerous_inner_html_is_not_escaped() {
+        let mut div = VElement::new("div");
+        div.children.push(VirtualNode::text("replaced"));
+        div.special_attributes.dangerous_inner_html = Some("<b>Bold</b> & <i>Italic</i>".into());
+
+        let expected = "<div><b>Bold</b> & <i>Italic</i></div>";
+
+        assert_eq!(VirtualNode::Element(div).to_string(), expected);
+    }
+
+    /// Verify that comments are rendered with their text escaped.
+    #[test]
+    fn comment_to_string() {
+        let mut div = VElement::new("div");
+        div.children.push(VirtualNode::placeholder());
+        div.children
+            .push(VirtualNode::comment("--><script></script>"));
+
+        let expected = "<div><!-- --><!----&gt;&lt;script&gt;&lt;/script&gt;--></div>";
+
+        assert_eq!(VirtualNode::Element(div).to_string(), expected);
+    }
 }
@@ -0,0 +1,106 @@
+use crate::{Component, ComponentState, VirtualNode};
+
+/// A section of a view that is only rendered and diffed again when its key changes.
+///
+/// Useful for large sections that rarely change, such as a footer, a navigation bar or a long
+/// documentation page. When a parent re-renders, a memo whose key is equal to the key of the memo
+/// that was in the same spot reuses the old rendered node, so the memo's render function is not
+/// called and its nodes are skipped while diffing.
+///
+/// The render function is called the first time that the memo's rendered node is needed, so when
+/// rendering HTML on the server the memo is rendered right away.
+///
+/// ```
+/// # use virtual_node::VirtualNode;
+/// let year = 2024;
+///
+/// let footer = VirtualNode::memo(year, move || VirtualNode::text(format!("© {}", year)));
+/// assert_eq!(footer.to_string(), "© 2024");
+/// ```
+///
+/// A memo is a [`Component`] whose props are its key, so just like a component it must render a
+/// single node, and a memo only takes the place of an old memo that was created by the same line of
+/// code.
+///
+/// [`Component`]: trait.Component.html
+pub struct Memo<K, F> {
+    key: K,
+    render: F,
+}
+
+impl<K, F> Memo<K, F>
+where
+    K: PartialEq + 'static,
+    F: Fn() -> VirtualNode + 'static,
+{
+    /// Create a memo that renders using the render function, until the key changes.
+    pub fn new(key: K, render: F) -> Self {
+        Memo { key, render }
+    }
+}
+
+impl<K, F> Component for Memo<K, F>
+where
+    K: PartialEq + 'static,
+    F: Fn() -> VirtualNode + 'static,
+{
+    type State = ();
+
+    fn render(&self, _state: &ComponentState<()>) -> VirtualNode {
+        (self.render)()
+    }
+}
+
+impl<K: PartialEq, F> PartialEq for Memo<K, F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VComponent;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// Verify that a memo reuses the old memo's rendered node when the keys are equal, without
+    /// calling the render function.
+    #[test]
+    fn equal_key_reuses_render() {
+        let renders = Rc::new(Cell::new(0));
+
+        let old = counted_memo(1, &renders);
+        old.rendered();
+
+        let new = counted_memo(1, &renders);
+        assert!(new.take_over(&old));
+
+        assert!(std::ptr::eq(new.rendered(), old.rendered()));
+        assert_eq!(renders.get(), 1);
+    }
+
+    /// Verify that a memo gets rendered again when its key changes.
+    #[test]
+    fn changed_key_renders() {
+        let renders = Rc::new(Cell::new(0));
+
+        let old = counted_memo(1, &renders);
+        old.rendered();
+
+        let new = counted_memo(2, &renders);
+        assert!(!new.take_over(&old));
+
+        assert_eq!(new.rendered(), &VirtualNode::text("2"));
+        assert_eq!(renders.get(), 2);
+    }
+
+    fn counted_memo(key: u32, renders: &Rc<Cell<u32>>) -> VComponent {
+        let renders = renders.clone();
+
+        VComponent::new(Memo::new(key, move || {
+            renders.set(renders.get() + 1);
+            VirtualNode::text(key.to_string())
+        }))
+    }
+}
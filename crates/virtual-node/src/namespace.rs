@@ -0,0 +1,202 @@
+//! The namespaces that elements and attributes get created in.
+//!
+//! An element inherits its parent's namespace, so an `<a>` inside of an `<svg>` is an SVG element
+//! while an `<a>` inside of a `<div>` is an HTML element. The `<svg>` and `<math>` tags start a new
+//! namespace, an `<svg>`'s `<foreignObject>` goes back to HTML for its children, and an `xmlns`
+//! attribute overrides the namespace of an element and its descendants.
+
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{Document, Element, Node};
+
+use crate::{AttributeValue, VElement};
+
+const HTML_NAMESPACE: &str = "http://www.w3.org/1999/xhtml";
+const SVG_NAMESPACE: &str = "http://www.w3.org/2000/svg";
+const MATHML_NAMESPACE: &str = "http://www.w3.org/1998/Math/MathML";
+
+const XLINK_NAMESPACE: &str = "http://www.w3.org/1999/xlink";
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+const XMLNS_NAMESPACE: &str = "http://www.w3.org/2000/xmlns/";
+
+/// The namespace of an element.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Namespace {
+    /// An HTML element, such as a `<div>`.
+    #[default]
+    Html,
+    /// An SVG element, such as an `<svg>` or a `<circle>`.
+    Svg,
+    /// A MathML element, such as a `<math>` or an `<mi>`.
+    MathMl,
+}
+
+impl Namespace {
+    /// The namespace URI, such as `http://www.w3.org/2000/svg`.
+    pub fn uri(&self) -> &'static str {
+        match self {
+            Namespace::Html => HTML_NAMESPACE,
+            Namespace::Svg => SVG_NAMESPACE,
+            Namespace::MathMl => MATHML_NAMESPACE,
+        }
+    }
+
+    /// The namespace with the given URI, or `None` if it isn't one that we know of.
+    pub fn from_uri(uri: &str) -> Option<Self> {
+        match uri {
+            HTML_NAMESPACE => Some(Namespace::Html),
+            SVG_NAMESPACE => Some(Namespace::Svg),
+            MATHML_NAMESPACE => Some(Namespace::MathMl),
+            _ => None,
+        }
+    }
+
+    /// The namespace that new children of a DOM element get created in.
+    ///
+    /// Used when creating nodes to append to, or to replace a child of, an element that is already
+    /// in the DOM.
+    pub fn for_children_of(parent: &Node) -> Self {
+        let element: &Element = match parent.dyn_ref() {
+            Some(element) => element,
+            None => return Namespace::Html,
+        };
+
+        let namespace = element
+            .namespace_uri()
+            .and_then(|uri| Namespace::from_uri(&uri))
+            .unwrap_or_default();
+
+        children_namespace(namespace, &element.local_name())
+    }
+
+    /// The namespace of an element whose parent is in this namespace.
+    pub fn of_child(self, element: &VElement) -> Self {
+        if let Some(namespace) = element
+            .attrs
+            .get("xmlns")
+            .and_then(|xmlns| Namespace::from_uri(&xmlns.to_string()))
+        {
+            return namespace;
+        }
+
+        match element.tag.as_str() {
+            "svg" => Namespace::Svg,
+            "math" => Namespace::MathMl,
+            // Elements such as a `<circle>` can only be SVG elements, so they can be created on
+            // their own without an `<svg>` parent.
+            tag if self == Namespace::Html && html_validation::is_svg_namespace(tag) => {
+                Namespace::Svg
+            }
+            tag if self == Namespace::Html && html_validation::is_mathml_namespace(tag) => {
+                Namespace::MathMl
+            }
+            _ => self,
+        }
+    }
+
+    /// The namespace of an element's children, where the element is in this namespace.
+    pub fn of_children(self, element: &VElement) -> Self {
+        children_namespace(self, &element.tag)
+    }
+
+    /// Create an element in this namespace.
+    pub(crate) fn create_element(self, document: &Document, tag: &str) -> Result<Element, JsValue> {
+        match self {
+            Namespace::Html => document.create_element(tag),
+            _ => document.create_element_ns(Some(self.uri()), tag),
+        }
+    }
+}
+
+fn children_namespace(namespace: Namespace, tag: &str) -> Namespace {
+    match (namespace, tag) {
+        (Namespace::Svg, "foreignObject") => Namespace::Html,
+        _ => namespace,
+    }
+}
+
+/// The namespace of an attribute with a prefix, such as `xlink:href`, or `None` for attributes
+/// without a namespace.
+pub fn attribute_namespace(name: &str) -> Option<&'static str> {
+    if name == "xmlns" {
+        return Some(XMLNS_NAMESPACE);
+    }
+
+    match name.split_once(':')?.0 {
+        "xlink" => Some(XLINK_NAMESPACE),
+        "xml" => Some(XML_NAMESPACE),
+        "xmlns" => Some(XMLNS_NAMESPACE),
+        _ => None,
+    }
+}
+
+/// Set an attribute on a DOM element, using `setAttributeNS` for attributes such as `xlink:href`.
+///
+/// A `false` boolean attribute gets removed.
+pub fn set_element_attribute(
+    element: &Element,
+    name: &str,
+    value: &AttributeValue,
+) -> Result<(), JsValue> {
+    let value = match value {
+        AttributeValue::String(s) => s.clone(),
+        AttributeValue::Bool(true) => "".to_string(),
+        AttributeValue::Bool(false) => return remove_element_attribute(element, name),
+        _ => value.to_string(),
+    };
+
+    match attribute_namespace(name) {
+        Some(namespace) => element.set_attribute_ns(Some(namespace), name, &value),
+        None => element.set_attribute(name, &value),
+    }
+}
+
+/// Remove an attribute from a DOM element, using `removeAttributeNS` for attributes such as
+/// `xlink:href`.
+pub fn remove_element_attribute(element: &Element, name: &str) -> Result<(), JsValue> {
+    match attribute_namespace(name) {
+        Some(namespace) => {
+            let local_name = name.split_once(':').map(|(_, local)| local).unwrap_or(name);
+            element.remove_attribute_ns(Some(namespace), local_name)
+        }
+        None => element.remove_attribute(name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that elements inherit their parent's namespace unless they start a new one.
+    #[test]
+    fn child_namespace() {
+        let html = Namespace::Html;
+        let svg = Namespace::Svg;
+
+        assert_eq!(html.of_child(&VElement::new("a")), Namespace::Html);
+        assert_eq!(html.of_child(&VElement::new("svg")), Namespace::Svg);
+        assert_eq!(html.of_child(&VElement::new("circle")), Namespace::Svg);
+        assert_eq!(html.of_child(&VElement::new("math")), Namespace::MathMl);
+        assert_eq!(html.of_child(&VElement::new("mi")), Namespace::MathMl);
+
+        assert_eq!(svg.of_child(&VElement::new("a")), Namespace::Svg);
+        assert_eq!(svg.of_child(&VElement::new("title")), Namespace::Svg);
+        assert_eq!(
+            svg.of_children(&VElement::new("foreignObject")),
+            Namespace::Html
+        );
+
+        let mut div = VElement::new("div");
+        div.attrs.insert("xmlns".to_string(), SVG_NAMESPACE.into());
+        assert_eq!(html.of_child(&div), Namespace::Svg);
+    }
+
+    /// Verify that we find the namespaces of prefixed attributes.
+    #[test]
+    fn attribute_namespaces() {
+        assert_eq!(attribute_namespace("xlink:href"), Some(XLINK_NAMESPACE));
+        assert_eq!(attribute_namespace("xml:lang"), Some(XML_NAMESPACE));
+        assert_eq!(attribute_namespace("xmlns:xlink"), Some(XMLNS_NAMESPACE));
+        assert_eq!(attribute_namespace("xmlns"), Some(XMLNS_NAMESPACE));
+        assert_eq!(attribute_namespace("href"), None);
+    }
+}
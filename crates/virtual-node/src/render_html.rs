@@ -0,0 +1,439 @@
+//! Rendering virtual nodes into HTML strings.
+//!
+//! The `Display` implementations, [`VirtualNode::write_html`], [`VirtualNode::write_html_io`] and
+//! [`VirtualNode::html_chunks`] all use the same `HtmlRenderer`, so they always produce the same
+//! bytes.
+//!
+//! The renderer walks the tree using its own stack instead of recursing, which lets it pause after
+//! any piece of HTML. This is what powers rendering into chunks.
+
+use std::fmt::{self, Write};
+use std::io;
+
+use crate::escape::{is_raw_text_element, AttributeWriter, EscapeAttribute, EscapeText, RawText};
+use crate::{AttributeTarget, AttributeValue, VElement, VirtualNode};
+
+impl VirtualNode {
+    /// Write this node's HTML into a `fmt::Write`, such as a `String`.
+    ///
+    /// ```
+    /// # use virtual_node::VirtualNode;
+    /// let node = VirtualNode::element("div");
+    ///
+    /// let mut html = String::new();
+    /// node.write_html(&mut html).unwrap();
+    ///
+    /// assert_eq!(html, "<div></div>");
+    /// ```
+    pub fn write_html<W: fmt::Write>(&self, writer: &mut W) -> fmt::Result {
+        HtmlRenderer::new(Root::Node(self)).write_all(writer)
+    }
+
+    /// Write this node's HTML into an `io::Write`, such as a file or a socket.
+    ///
+    /// Many small writes get made, so you'll typically want to wrap unbuffered writers in a
+    /// `std::io::BufWriter`.
+    pub fn write_html_io<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        let mut writer = IoWriter {
+            inner: writer,
+            error: None,
+        };
+
+        match self.write_html(&mut writer) {
+            Ok(()) => Ok(()),
+            Err(fmt::Error) => Err(writer
+                .error
+                .unwrap_or_else(|| io::Error::other("formatter error"))),
+        }
+    }
+
+    /// Render this node's HTML in chunks that are at least `min_chunk_len` bytes long, except
+    /// for the last one.
+    ///
+    /// Chunks get rendered lazily, so this can be used to stream a response body without
+    /// rendering the entire page up front.
+    ///
+    /// ```
+    /// # use virtual_node::VirtualNode;
+    /// let node = VirtualNode::element("div");
+    ///
+    /// let html: String = node.html_chunks(2).collect();
+    ///
+    /// assert_eq!(html, "<div></div>");
+    /// ```
+    pub fn html_chunks(&self, min_chunk_len: usize) -> HtmlChunks<'_> {
+        HtmlChunks {
+            renderer: HtmlRenderer::new(Root::Node(self)),
+            min_chunk_len,
+        }
+    }
+}
+
+impl fmt::Display for VElement {
+    // Turn a VElement and all of it's children (recursively) into an HTML string.
+    //
+    // Text and attribute values are escaped. The `dangerous_inner_html` special attribute is the
+    // only thing that gets written without being escaped.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        HtmlRenderer::new(Root::Element(self)).write_all(f)
+    }
+}
+
+/// An iterator over chunks of a virtual node's HTML.
+///
+/// See [`VirtualNode::html_chunks`].
+pub struct HtmlChunks<'a> {
+    renderer: HtmlRenderer<'a>,
+    min_chunk_len: usize,
+}
+
+impl Iterator for HtmlChunks<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut chunk = String::with_capacity(self.min_chunk_len);
+
+        while chunk.len() < self.min_chunk_len.max(1) {
+            // Writing to a String never fails.
+            if !self.renderer.write_next(&mut chunk).unwrap() {
+                break;
+            }
+        }
+
+        if chunk.is_empty() {
+            None
+        } else {
+            Some(chunk)
+        }
+    }
+}
+
+enum Root<'a> {
+    Node(&'a VirtualNode),
+    Element(&'a VElement),
+}
+
+/// Writes HTML one piece at a time, where a piece is an opening tag, a closing tag or a text node.
+struct HtmlRenderer<'a> {
+    root: Option<Root<'a>>,
+    /// Elements and fragments whose children are being written.
+    open_nodes: Vec<OpenNode<'a>>,
+}
+
+/// An element whose opening tag has been written, or a fragment, along with the index of the next
+/// child to write.
+struct OpenNode<'a> {
+    /// The element to close once all of the children have been written, or `None` for a fragment.
+    element: Option<&'a VElement>,
+    children: &'a [VirtualNode],
+    next_child: usize,
+    /// The tag of the raw text element, such as `<script>`, that the children are inside of.
+    raw_text: Option<&'a str>,
+}
+
+impl<'a> HtmlRenderer<'a> {
+    fn new(root: Root<'a>) -> Self {
+        HtmlRenderer {
+            root: Some(root),
+            open_nodes: vec![],
+        }
+    }
+
+    fn write_all<W: fmt::Write>(mut self, writer: &mut W) -> fmt::Result {
+        while self.write_next(writer)? {}
+
+        Ok(())
+    }
+
+    /// Write the next piece of HTML.
+    ///
+    /// Returns `false` if there was nothing left to write.
+    fn write_next<W: fmt::Write>(&mut self, writer: &mut W) -> Result<bool, fmt::Error> {
+        if let Some(root) = self.root.take() {
+            match root {
+                Root::Node(node) => self.write_node(node, None, writer)?,
+                Root::Element(element) => self.write_element(element, writer)?,
+            };
+
+            return Ok(true);
+        }
+
+        let open = match self.open_nodes.last_mut() {
+            Some(open) => open,
+            None => return Ok(false),
+        };
+
+        match open.children.get(open.next_child) {
+            Some(child) => {
+                open.next_child += 1;
+                let raw_text = open.raw_text;
+                self.write_node(child, raw_text, writer)?;
+            }
+            None => {
+                let element = open.element;
+                self.open_nodes.pop();
+
+                if let Some(element) = element {
+                    write_closing_tag(element, writer)?;
+                }
+            }
+        };
+
+        Ok(true)
+    }
+
+    fn write_node<W: fmt::Write>(
+        &mut self,
+        node: &'a VirtualNode,
+        raw_text_parent: Option<&'a str>,
+        writer: &mut W,
+    ) -> fmt::Result {
+        match node {
+            VirtualNode::Text(text) => match raw_text_parent {
+                Some(tag) => write!(
+                    writer,
+                    "{}",
+                    RawText {
+                        tag,
+                        text: &text.text
+                    }
+                ),
+                None => write!(writer, "{}", EscapeText(&text.text)),
+            },
+            VirtualNode::Element(element) => self.write_element(element, writer),
+            VirtualNode::Comment(comment) => write!(writer, "{}", comment),
+            VirtualNode::Portal(portal) => write!(writer, "{}", portal),
+            VirtualNode::Component(component) => {
+                self.write_node(component.rendered(), raw_text_parent, writer)
+            }
+            // Fragments don't have any HTML of their own, so we move on to their children.
+            VirtualNode::Fragment(fragment) => {
+                self.open_nodes.push(OpenNode {
+                    element: None,
+                    children: &fragment.children,
+                    next_child: 0,
+                    raw_text: raw_text_parent,
+                });
+                Ok(())
+            }
+        }
+    }
+
+    fn write_element<W: fmt::Write>(
+        &mut self,
+        element: &'a VElement,
+        writer: &mut W,
+    ) -> fmt::Result {
+        write!(writer, "<{}", element.tag)?;
+
+        for (attr, value) in element.attrs.iter() {
+            if element.attribute_target(attr) == AttributeTarget::Property {
+                continue;
+            }
+
+            match value {
+                AttributeValue::String(value_str) => {
+                    write!(writer, r#" {}="{}""#, attr, EscapeAttribute(value_str))?;
+                }
+                AttributeValue::Bool(value_bool) => {
+                    if *value_bool {
+                        write!(writer, " {}", attr)?;
+                    }
+                }
+                _ => {
+                    write!(writer, r#" {}=""#, attr)?;
+                    write!(AttributeWriter(writer), "{}", value)?;
+                    writer.write_str("\"")?;
+                }
+            }
+        }
+
+        if !element.styles.is_empty() {
+            writer.write_str(r#" style=""#)?;
+            write!(AttributeWriter(writer), "{}", element.styles)?;
+            writer.write_str("\"")?;
+        }
+
+        writer.write_str(">")?;
+
+        match &element.special_attributes.dangerous_inner_html {
+            // When creating a DOM element the inner HTML replaces any children, so we do the same.
+            Some(inner_html) => {
+                writer.write_str(inner_html)?;
+                write_closing_tag(element, writer)
+            }
+            None => {
+                self.open_nodes.push(OpenNode {
+                    element: Some(element),
+                    children: &element.children,
+                    next_child: 0,
+                    raw_text: Some(element.tag.as_str()).filter(|tag| is_raw_text_element(tag)),
+                });
+                Ok(())
+            }
+        }
+    }
+}
+
+fn write_closing_tag<W: fmt::Write>(element: &VElement, writer: &mut W) -> fmt::Result {
+    if !html_validation::is_self_closing(&element.tag) {
+        write!(writer, "</{}>", element.tag)?;
+    }
+
+    Ok(())
+}
+
+/// Lets us render into an `io::Write` using a `fmt::Write`, holding on to the `io::Error` since a
+/// `fmt::Error` can't hold any information.
+struct IoWriter<W> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> fmt::Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{VFragment, VText};
+
+    /// Verify that writing into a `fmt::Write`, an `io::Write` and chunks all produce the same
+    /// HTML as `Display`.
+    #[test]
+    fn same_output_as_display() {
+        let node = tree();
+        let expected = node.to_string();
+
+        let mut html = String::new();
+        node.write_html(&mut html).unwrap();
+        assert_eq!(html, expected);
+
+        let mut bytes = vec![];
+        node.write_html_io(&mut bytes).unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap(), expected);
+
+        for min_chunk_len in [0, 1, 5, 64, 10_000].iter() {
+            let chunks: Vec<String> = node.html_chunks(*min_chunk_len).collect();
+            assert_eq!(chunks.concat(), expected);
+        }
+    }
+
+    /// Verify that fragments render their children without any markup of their own.
+    #[test]
+    fn fragments() {
+        let mut inner = VFragment::new();
+        inner.children.push(VirtualNode::text("b"));
+        inner.children.push(VirtualNode::element("br"));
+
+        let mut fragment = VFragment::new();
+        fragment.children.push(VirtualNode::text("a"));
+        fragment.children.push(inner.into());
+        fragment.children.push(VirtualNode::fragment());
+
+        let mut div = VElement::new("div");
+        div.children.push(fragment.into());
+        div.children.push(VirtualNode::element("em"));
+
+        let node = VirtualNode::Element(div);
+        assert_eq!(node.to_string(), "<div>ab<br><em></em></div>");
+
+        let mut root = VFragment::new();
+        root.children.push(VirtualNode::text("<"));
+        root.children.push(VirtualNode::element("p"));
+        assert_eq!(VirtualNode::Fragment(root).to_string(), "&lt;<p></p>");
+    }
+
+    /// Verify that we render the inline styles as a style attribute.
+    #[test]
+    fn styles() {
+        let mut div = VElement::new("div");
+        div.styles.set("color", "red");
+        div.styles.set("font-family", r#""A" & B"#);
+
+        assert_eq!(
+            VirtualNode::Element(div).to_string(),
+            r#"<div style="color: red; font-family: &quot;A&quot; &amp; B"></div>"#
+        );
+    }
+
+    /// Verify that attributes that only get set as DOM properties are not rendered.
+    #[test]
+    fn dom_properties_not_rendered() {
+        let mut input = VElement::new("input");
+        input.attrs.insert("indeterminate".to_string(), true.into());
+        input.attrs.insert("checked".to_string(), true.into());
+        input.set_dom_property("customState", "on");
+
+        assert_eq!(VirtualNode::Element(input).to_string(), "<input checked>");
+    }
+
+    /// Verify that every chunk except for the last is at least the minimum length.
+    #[test]
+    fn chunk_lengths() {
+        let node = tree();
+
+        let chunks: Vec<String> = node.html_chunks(16).collect();
+        assert!(chunks.len() > 1);
+
+        for chunk in chunks[..chunks.len() - 1].iter() {
+            assert!(chunk.len() >= 16);
+        }
+    }
+
+    /// Verify that we return the io error that stopped rendering.
+    #[test]
+    fn io_error() {
+        struct Broken;
+        impl io::Write for Broken {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken"))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let err = tree().write_html_io(Broken).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    fn tree() -> VirtualNode {
+        let mut script = VElement::new("script");
+        script
+            .children
+            .push(VirtualNode::text("a < b && '</script>'"));
+
+        let mut inner_html = VElement::new("div");
+        inner_html.special_attributes.dangerous_inner_html = Some("<b>Raw</b>".into());
+
+        let mut input = VElement::new("input");
+        input.attrs.insert("disabled".into(), true.into());
+
+        let mut list = VElement::new("ul");
+        list.attrs.insert("class".into(), "a \"list\"".into());
+        for idx in 0..5 {
+            let mut item = VElement::new("li");
+            item.children.push(VirtualNode::Text(VText::new(format!(
+                "Item {} & more",
+                idx
+            ))));
+            list.children.push(item.into());
+        }
+
+        let mut root = VElement::new("div");
+        root.children.push(VirtualNode::text("<Hello>"));
+        root.children.push(script.into());
+        root.children.push(inner_html.into());
+        root.children.push(input.into());
+        root.children.push(list.into());
+
+        root.into()
+    }
+}
@@ -28,12 +28,25 @@ impl VirtualNode {
     pub fn children_recursive<'a>(&'a self) -> Vec<&'a VirtualNode> {
         let mut descendants: Vec<&'a VirtualNode> = vec![];
         match self {
-            VirtualNode::Text(_) => {}
+            VirtualNode::Text(_) | VirtualNode::Comment(_) => {}
             VirtualNode::Element(element_node) => {
                 for child in element_node.children.iter() {
                     get_descendants(&mut descendants, child);
                 }
             }
+            VirtualNode::Fragment(fragment) => {
+                for child in fragment.children.iter() {
+                    get_descendants(&mut descendants, child);
+                }
+            }
+            VirtualNode::Component(component) => {
+                get_descendants(&mut descendants, component.rendered());
+            }
+            VirtualNode::Portal(portal) => {
+                for child in portal.children.iter() {
+                    get_descendants(&mut descendants, child);
+                }
+            }
         }
 
         descendants.into_iter().collect()
@@ -43,12 +56,25 @@ impl VirtualNode {
 fn get_descendants<'a>(descendants: &mut Vec<&'a VirtualNode>, node: &'a VirtualNode) {
     descendants.push(node);
     match node {
-        VirtualNode::Text(_) => {}
+        VirtualNode::Text(_) | VirtualNode::Comment(_) => {}
         VirtualNode::Element(element_node) => {
             for child in element_node.children.iter() {
                 get_descendants(descendants, child);
             }
         }
+        VirtualNode::Fragment(fragment) => {
+            for child in fragment.children.iter() {
+                get_descendants(descendants, child);
+            }
+        }
+        VirtualNode::Component(component) => {
+            get_descendants(descendants, component.rendered());
+        }
+        VirtualNode::Portal(portal) => {
+            for child in portal.children.iter() {
+                get_descendants(descendants, child);
+            }
+        }
     }
 }
 
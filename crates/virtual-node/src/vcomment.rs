@@ -0,0 +1,53 @@
+use std::fmt;
+
+use web_sys::Comment;
+
+use crate::escape::EscapeComment;
+
+/// Represents a comment node.
+///
+/// The `html!` macro uses an empty comment as a placeholder for a `None` block, so that the
+/// siblings after the block keep the same position whether or not the block has a node.
+///
+/// The text `ptns` is reserved for the comments that separate neighboring text nodes in the DOM.
+#[derive(PartialEq)]
+pub struct VComment {
+    /// The text inside of the comment.
+    pub text: String,
+}
+
+impl VComment {
+    /// Create an new `VComment` instance with the specified text.
+    pub fn new<S>(text: S) -> Self
+    where
+        S: Into<String>,
+    {
+        VComment { text: text.into() }
+    }
+
+    /// Create a placeholder comment, which renders as `<!-- -->`.
+    pub fn placeholder() -> Self {
+        VComment::new(" ")
+    }
+
+    /// Return a `Comment` node from a `VirtualNode`, typically right before adding it
+    /// into the DOM.
+    pub(crate) fn create_comment_node(&self) -> Comment {
+        let document = web_sys::window().unwrap().document().unwrap();
+        document.create_comment(&self.text)
+    }
+}
+
+impl fmt::Debug for VComment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Comment({})", self.text)
+    }
+}
+
+// Turn a VComment into an HTML string, escaping any characters that would let the text end the
+// comment early.
+impl fmt::Display for VComment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<!--{}-->", EscapeComment(&self.text))
+    }
+}
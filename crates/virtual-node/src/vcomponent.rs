@@ -0,0 +1,428 @@
+use std::any::{self, Any};
+use std::cell::{Cell, OnceCell, Ref, RefCell};
+use std::fmt;
+use std::rc::Rc;
+
+use crate::VirtualNode;
+
+/// A view with props and local state that only re-renders when it needs to.
+///
+/// The component's fields are its props. When a parent re-renders, a component whose props are
+/// equal to the props that it was last rendered with (and whose state has not changed since then)
+/// is not rendered or diffed again.
+///
+/// Changing the component's [`ComponentState`] re-renders only that component, see
+/// `PercyDom::update_components`.
+///
+/// ```
+/// # use virtual_node::{Component, ComponentState, VirtualNode};
+/// #[derive(PartialEq)]
+/// struct Counter {
+///     label: &'static str,
+/// }
+///
+/// impl Component for Counter {
+///     type State = u32;
+///
+///     fn render(&self, state: &ComponentState<u32>) -> VirtualNode {
+///         VirtualNode::text(format!("{}: {}", self.label, *state.get()))
+///     }
+/// }
+///
+/// let counter = VirtualNode::component(Counter { label: "Clicks" });
+/// assert_eq!(counter.to_string(), "Clicks: 0");
+/// ```
+///
+/// In the `html!` macro components are written like elements, `<Counter label="Clicks" />`.
+///
+/// A type should implement either `Component` or [`View`], not both.
+///
+/// [`View`]: trait.View.html
+pub trait Component: PartialEq + 'static {
+    /// The component's local state. It starts out as `Default::default()` the first time that the
+    /// component gets rendered, and is kept for as long as the component stays in the same spot.
+    type State: Default + 'static;
+
+    /// Render the component.
+    ///
+    /// Components must render a single node, so they cannot render a fragment.
+    fn render(&self, state: &ComponentState<Self::State>) -> VirtualNode;
+
+    // Used by the html! macro
+    #[doc(hidden)]
+    fn __into_virtual_node(self) -> VirtualNode
+    where
+        Self: Sized,
+    {
+        VirtualNode::component(self)
+    }
+}
+
+/// A handle to a component's local state.
+///
+/// Cloning the handle gives you another handle to the same state, so you can move it into your
+/// event handlers.
+pub struct ComponentState<S> {
+    value: Rc<RefCell<S>>,
+    changes: Rc<StateChanges>,
+}
+
+/// Tracks changes to a component's state, regardless of the state's type.
+#[derive(Default)]
+struct StateChanges {
+    version: Cell<u32>,
+    listener: RefCell<Option<Rc<dyn Fn()>>>,
+}
+
+impl<S> ComponentState<S> {
+    /// Borrow the state.
+    pub fn get(&self) -> Ref<'_, S> {
+        self.value.borrow()
+    }
+
+    /// Update the state, marking the component as needing to be re-rendered.
+    pub fn update(&self, update: impl FnOnce(&mut S)) {
+        update(&mut self.value.borrow_mut());
+
+        self.changes
+            .version
+            .set(self.changes.version.get().wrapping_add(1));
+
+        let listener = self.changes.listener.borrow().clone();
+        if let Some(listener) = listener {
+            listener();
+        }
+    }
+}
+
+impl<S> Clone for ComponentState<S> {
+    fn clone(&self) -> Self {
+        ComponentState {
+            value: self.value.clone(),
+            changes: self.changes.clone(),
+        }
+    }
+}
+
+/// A component along with its state and the node that it rendered.
+///
+/// The component is rendered the first time that its rendered node is needed.
+pub struct VComponent {
+    component: Box<dyn AnyComponent>,
+    state: OnceCell<AnyState>,
+    rendered: OnceCell<Rendered>,
+}
+
+#[derive(Clone)]
+struct AnyState {
+    value: Rc<dyn Any>,
+    changes: Rc<StateChanges>,
+}
+
+struct Rendered {
+    node: Rc<VirtualNode>,
+    /// The state's version when the node was rendered.
+    state_version: u32,
+}
+
+impl VComponent {
+    /// Create a new `VComponent` that has not been rendered yet.
+    pub fn new<C: Component>(component: C) -> Self {
+        VComponent {
+            component: Box::new(component),
+            state: OnceCell::new(),
+            rendered: OnceCell::new(),
+        }
+    }
+
+    /// The node that the component rendered, rendering the component if it has not been rendered
+    /// yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the component renders a fragment.
+    pub fn rendered(&self) -> &VirtualNode {
+        &self.rendered.get_or_init(|| self.render()).node
+    }
+
+    /// Let this component take the place of an old component that was rendered in the same spot,
+    /// typically while diffing.
+    ///
+    /// If both are the same type of component, this component keeps the old component's state.
+    /// If their props are also equal and the state has not changed since the old component was
+    /// rendered, this component reuses the old component's rendered node instead of rendering.
+    ///
+    /// Returns whether or not the old rendered node was reused.
+    pub fn take_over(&self, old: &VComponent) -> bool {
+        if self.component.as_any().type_id() != old.component.as_any().type_id() {
+            return false;
+        }
+
+        if let Some(old_state) = old.state.get() {
+            if self.state.set(old_state.clone()).is_err() {
+                return false;
+            }
+        }
+
+        if old.state_changed() || !self.component.props_eq(old.component.as_any()) {
+            return false;
+        }
+
+        match old.rendered.get() {
+            Some(rendered) => self
+                .rendered
+                .set(Rendered {
+                    node: rendered.node.clone(),
+                    state_version: rendered.state_version,
+                })
+                .is_ok(),
+            None => false,
+        }
+    }
+
+    /// Whether or not the component's state changed since the component was rendered.
+    pub fn state_changed(&self) -> bool {
+        match (self.state.get(), self.rendered.get()) {
+            (Some(state), Some(rendered)) => state.changes.version.get() != rendered.state_version,
+            _ => false,
+        }
+    }
+
+    /// Render the component again, returning the node that it rendered before.
+    pub fn rerender(&mut self) -> Option<Rc<VirtualNode>> {
+        let previous = self.rendered.take().map(|rendered| rendered.node);
+        self.rendered();
+        previous
+    }
+
+    /// A mutable reference to the component's rendered node, if it has been rendered and the node
+    /// is not shared with another `VComponent`.
+    pub fn rendered_mut(&mut self) -> Option<&mut VirtualNode> {
+        Rc::get_mut(&mut self.rendered.get_mut()?.node)
+    }
+
+    /// Call the listener whenever the component's state gets updated.
+    ///
+    /// Replaces any listener that was previously set for the state.
+    pub fn set_state_listener(&self, listener: Rc<dyn Fn()>) {
+        *self.state().changes.listener.borrow_mut() = Some(listener);
+    }
+
+    fn state(&self) -> &AnyState {
+        self.state.get_or_init(|| self.component.new_state())
+    }
+
+    fn render(&self) -> Rendered {
+        let state = self.state();
+        let state_version = state.changes.version.get();
+
+        let node = self.component.render(state);
+        assert!(
+            node.as_vfragment_ref().is_none(),
+            "The component {} rendered a fragment. Components must render a single node, \
+             try wrapping the fragment in an element.",
+            self.component.type_name()
+        );
+
+        Rendered {
+            node: Rc::new(node),
+            state_version,
+        }
+    }
+}
+
+/// A [`Component`] whose type has been erased so that any component can be stored in a
+/// [`VComponent`].
+trait AnyComponent {
+    fn as_any(&self) -> &dyn Any;
+
+    fn props_eq(&self, other: &dyn Any) -> bool;
+
+    fn new_state(&self) -> AnyState;
+
+    fn render(&self, state: &AnyState) -> VirtualNode;
+
+    fn type_name(&self) -> &'static str;
+}
+
+impl<C: Component> AnyComponent for C {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn props_eq(&self, other: &dyn Any) -> bool {
+        other.downcast_ref::<C>().is_some_and(|other| self == other)
+    }
+
+    fn new_state(&self) -> AnyState {
+        AnyState {
+            value: Rc::new(RefCell::new(C::State::default())),
+            changes: Rc::new(StateChanges::default()),
+        }
+    }
+
+    fn render(&self, state: &AnyState) -> VirtualNode {
+        let state = ComponentState {
+            value: state
+                .value
+                .clone()
+                .downcast::<RefCell<C::State>>()
+                .unwrap_or_else(|_| unreachable!("The state was created by this component type.")),
+            changes: state.changes.clone(),
+        };
+
+        Component::render(self, &state)
+    }
+
+    fn type_name(&self) -> &'static str {
+        any::type_name::<C>()
+    }
+}
+
+impl PartialEq for VComponent {
+    fn eq(&self, other: &Self) -> bool {
+        self.component.props_eq(other.component.as_any())
+    }
+}
+
+impl fmt::Debug for VComponent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Component({})", self.component.type_name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VFragment;
+
+    /// Verify that a component reuses the old component's rendered node when its props are equal,
+    /// instead of rendering.
+    #[test]
+    fn take_over_reuses_render_when_props_equal() {
+        let renders = Rc::new(Cell::new(0));
+
+        let old = VComponent::new(Counter::new("a", &renders));
+        old.rendered();
+
+        let new = VComponent::new(Counter::new("a", &renders));
+        assert!(new.take_over(&old));
+
+        assert!(std::ptr::eq(new.rendered(), old.rendered()));
+        assert_eq!(renders.get(), 1);
+    }
+
+    /// Verify that a component renders when its props changed, keeping the old component's state.
+    #[test]
+    fn take_over_keeps_state_when_props_change() {
+        let renders = Rc::new(Cell::new(0));
+
+        let old = VComponent::new(Counter::new("a", &renders));
+        old.rendered();
+        update_counter(&old, 2);
+
+        let new = VComponent::new(Counter::new("b", &renders));
+        assert!(!new.take_over(&old));
+
+        assert_eq!(new.rendered(), &VirtualNode::text("b 2"));
+        assert_eq!(renders.get(), 2);
+    }
+
+    /// Verify that a component whose state changed since it was rendered gets rendered again,
+    /// even if its props are equal.
+    #[test]
+    fn state_change_rerenders() {
+        let renders = Rc::new(Cell::new(0));
+
+        let mut old = VComponent::new(Counter::new("a", &renders));
+        old.rendered();
+        assert!(!old.state_changed());
+
+        update_counter(&old, 1);
+        assert!(old.state_changed());
+
+        let new = VComponent::new(Counter::new("a", &renders));
+        assert!(!new.take_over(&old));
+        assert_eq!(new.rendered(), &VirtualNode::text("a 1"));
+
+        let previous = old.rerender().unwrap();
+        assert_eq!(*previous, VirtualNode::text("a 0"));
+        assert!(!old.state_changed());
+    }
+
+    /// Verify that we call the state listener when the state gets updated.
+    #[test]
+    fn state_listener() {
+        let updates = Rc::new(Cell::new(0));
+
+        let component = VComponent::new(Counter::new("a", &Rc::new(Cell::new(0))));
+        component.rendered();
+
+        let updates_clone = updates.clone();
+        component.set_state_listener(Rc::new(move || updates_clone.set(updates_clone.get() + 1)));
+
+        update_counter(&component, 1);
+        update_counter(&component, 2);
+        assert_eq!(updates.get(), 2);
+    }
+
+    /// Verify that rendering a fragment from a component panics, since a component must render a
+    /// single node.
+    #[test]
+    #[should_panic(expected = "Components must render a single node")]
+    fn rendering_fragment_panics() {
+        #[derive(PartialEq)]
+        struct RendersFragment;
+
+        impl Component for RendersFragment {
+            type State = ();
+
+            fn render(&self, _state: &ComponentState<()>) -> VirtualNode {
+                VFragment::new().into()
+            }
+        }
+
+        VComponent::new(RendersFragment).rendered();
+    }
+
+    /// Renders its label and its count, and counts how many times it gets rendered.
+    struct Counter {
+        label: &'static str,
+        renders: Rc<Cell<u32>>,
+    }
+
+    impl Counter {
+        fn new(label: &'static str, renders: &Rc<Cell<u32>>) -> Self {
+            Counter {
+                label,
+                renders: renders.clone(),
+            }
+        }
+    }
+
+    impl PartialEq for Counter {
+        fn eq(&self, other: &Self) -> bool {
+            self.label == other.label
+        }
+    }
+
+    impl Component for Counter {
+        type State = u32;
+
+        fn render(&self, state: &ComponentState<u32>) -> VirtualNode {
+            self.renders.set(self.renders.get() + 1);
+            VirtualNode::text(format!("{} {}", self.label, *state.get()))
+        }
+    }
+
+    fn update_counter(component: &VComponent, count: u32) {
+        let state = component.state();
+        let value = state.value.clone().downcast::<RefCell<u32>>().unwrap();
+
+        ComponentState {
+            value,
+            changes: state.changes.clone(),
+        }
+        .update(|c| *c = count);
+    }
+}
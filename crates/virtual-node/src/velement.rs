@@ -1,21 +1,34 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 
 use crate::event::Events;
+use crate::vfragment::flatten_fragments;
 use crate::VirtualNode;
 
 pub use self::attribute_value::*;
+pub use self::bind::*;
+pub use self::dom_property::*;
+pub use self::node_ref::*;
 pub use self::special_attributes::*;
+pub use self::styles::*;
 
 mod attribute_value;
+mod bind;
+mod dom_property;
+mod node_ref;
 mod special_attributes;
+mod styles;
 
 #[derive(PartialEq)]
 pub struct VElement {
     /// The HTML tag, such as "div"
     pub tag: String,
-    /// HTML attributes such as id, class, style, etc
+    /// HTML attributes such as id, class, etc
     pub attrs: HashMap<String, AttributeValue>,
+    /// The inline styles from the `style` attribute.
+    ///
+    /// These get patched one property at a time, so don't also put a `style` into the `attrs`.
+    pub styles: Styles,
     /// Events that will get added to your real DOM element via `.addEventListener`
     ///
     /// Events natively handled in HTML such as onclick, onchange, oninput and others
@@ -26,6 +39,10 @@ pub struct VElement {
     pub children: Vec<VirtualNode>,
     /// See [`SpecialAttributes`]
     pub special_attributes: SpecialAttributes,
+    /// The attributes that only get set as DOM properties, besides the ones that always are.
+    ///
+    /// See [`VElement::set_dom_property`].
+    pub dom_properties: HashSet<String>,
 }
 
 impl VElement {
@@ -36,51 +53,76 @@ impl VElement {
         VElement {
             tag: tag.into(),
             attrs: HashMap::new(),
+            styles: Styles::new(),
             events: Events::new(),
             children: vec![],
             special_attributes: SpecialAttributes::default(),
+            dom_properties: HashSet::new(),
         }
     }
-}
 
-impl fmt::Debug for VElement {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "Element(<{}>, attrs: {:?}, children: {:?})",
-            self.tag, self.attrs, self.children,
-        )
-    }
-}
+    /// Set an attribute, or remove it if the value is `None`.
+    ///
+    /// The `style` attribute gets parsed into the element's [`VElement.styles`]. A list of styles
+    /// such as `["color: red", "width: 5px"]` gets parsed one item at a time.
+    ///
+    /// ```
+    /// # use virtual_node::{AttributeValue, VElement};
+    /// let mut div = VElement::new("div");
+    ///
+    /// div.set_attribute("tabindex", Some(1));
+    /// assert_eq!(div.attrs["tabindex"], AttributeValue::Int(1));
+    ///
+    /// div.set_attribute("tabindex", None::<i32>);
+    /// assert!(div.attrs.get("tabindex").is_none());
+    /// ```
+    pub fn set_attribute<N, V>(&mut self, name: N, value: V)
+    where
+        N: Into<String>,
+        V: IntoAttributeValue,
+    {
+        let name = name.into();
 
-impl fmt::Display for VElement {
-    // Turn a VElement and all of it's children (recursively) into an HTML string
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "<{}", self.tag).unwrap();
+        if name == "style" {
+            self.styles = Styles::new();
 
-        for (attr, value) in self.attrs.iter() {
-            match value {
-                AttributeValue::String(value_str) => {
-                    write!(f, r#" {}="{}""#, attr, value_str)?;
-                }
-                AttributeValue::Bool(value_bool) => {
-                    if *value_bool {
-                        write!(f, " {}", attr)?;
+            match value.into_attribute_value() {
+                Some(AttributeValue::List(declarations)) => {
+                    for css in declarations.iter() {
+                        self.styles.extend_from_css(css);
                     }
                 }
-            }
-        }
+                Some(css) => self.styles.extend_from_css(&css.to_string()),
+                None => {}
+            };
 
-        write!(f, ">")?;
-
-        for child in self.children.iter() {
-            write!(f, "{}", child.to_string())?;
+            return;
         }
 
-        if !html_validation::is_self_closing(&self.tag) {
-            write!(f, "</{}>", self.tag)?;
-        }
+        match value.into_attribute_value() {
+            Some(value) => {
+                self.attrs.insert(name, value);
+            }
+            None => {
+                self.attrs.remove(&name);
+            }
+        };
+    }
 
-        Ok(())
+    /// This element's children, with any fragments replaced by their own children.
+    ///
+    /// These are the children that end up in the DOM.
+    pub fn flat_children(&self) -> Vec<&VirtualNode> {
+        flatten_fragments(&self.children)
+    }
+}
+
+impl fmt::Debug for VElement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Element(<{}>, attrs: {:?}, children: {:?})",
+            self.tag, self.attrs, self.children,
+        )
     }
 }
@@ -1,3 +1,4 @@
+use std::convert::TryFrom;
 use std::fmt::{Display, Formatter};
 use wasm_bindgen::JsValue;
 
@@ -6,11 +7,18 @@ use wasm_bindgen::JsValue;
 /// For <button disabled=true></button>, the element attribute value would be
 /// `ElementAttributeValue::Bool(true)`
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AttributeValue {
     /// A string attribute such as value="My text input contents"
     String(String),
     /// A boolean attribute disabled=true
     Bool(bool),
+    /// An integer attribute such as tabindex=1
+    Int(i64),
+    /// A floating point attribute such as step=0.5
+    Float(f64),
+    /// A space separated list such as class=["big", "button"]
+    List(Vec<String>),
 }
 
 impl AttributeValue {
@@ -29,37 +37,115 @@ impl AttributeValue {
             _ => None,
         }
     }
+
+    /// If the attribute is a list, return it. Otherwise return None.
+    pub fn as_list(&self) -> Option<&[String]> {
+        match self {
+            AttributeValue::List(list) => Some(list),
+            _ => None,
+        }
+    }
+}
+
+/// Converts a value into an element's attribute value, where `None` means that the element does
+/// not have the attribute.
+///
+/// The `html!` macro uses this for every attribute, so `Option`s can be used for attributes that
+/// are only sometimes present.
+///
+/// ```
+/// # use virtual_node::{AttributeValue, IntoAttributeValue};
+/// let title: Option<&str> = None;
+/// assert_eq!(title.into_attribute_value(), None);
+///
+/// assert_eq!(Some(5).into_attribute_value(), Some(AttributeValue::Int(5)));
+/// ```
+pub trait IntoAttributeValue {
+    /// The attribute value, or `None` if the attribute should not be set.
+    fn into_attribute_value(self) -> Option<AttributeValue>;
+}
+
+impl<T: Into<AttributeValue>> IntoAttributeValue for T {
+    fn into_attribute_value(self) -> Option<AttributeValue> {
+        Some(self.into())
+    }
+}
+
+impl<T: Into<AttributeValue>> IntoAttributeValue for Option<T> {
+    fn into_attribute_value(self) -> Option<AttributeValue> {
+        self.map(Into::into)
+    }
 }
 
 // Implements
-//   From<T> and From<&T> -> AttributeValue::String(T.to_string())
-macro_rules! to_string_impls {
-    ($ty:ty) => {
-        impl From<$ty> for AttributeValue {
-            fn from(val: $ty) -> Self {
-                AttributeValue::String(val.to_string())
+//   From<T> and From<&T> -> AttributeValue::$variant(T.into())
+macro_rules! lossless_impls {
+    ($variant:ident, $inner:ty, $($tys:ty),*) => {
+        $(
+            impl From<$tys> for AttributeValue {
+                fn from(val: $tys) -> Self {
+                    AttributeValue::$variant(<$inner>::from(val))
+                }
             }
-        }
 
-        impl From<&$ty> for AttributeValue {
-            fn from(val: &$ty) -> Self {
-                AttributeValue::String(val.to_string())
+            impl From<&$tys> for AttributeValue {
+                fn from(val: &$tys) -> Self {
+                    AttributeValue::$variant(<$inner>::from(*val))
+                }
             }
-        }
+        )*
+    };
+}
+lossless_impls!(Int, i64, u8, u16, u32, i8, i16, i32, i64);
+lossless_impls!(Float, f64, f64);
+
+// Implements
+//   From<T> and From<&T> -> AttributeValue::Int(T), falling back to AttributeValue::String for
+//   integers that do not fit into an i64 so that they don't lose their precision.
+macro_rules! int_impls {
+    ($($tys:ty),*) => {
+        $(
+            impl From<$tys> for AttributeValue {
+                fn from(val: $tys) -> Self {
+                    match i64::try_from(val) {
+                        Ok(int) => AttributeValue::Int(int),
+                        Err(_) => AttributeValue::String(val.to_string()),
+                    }
+                }
+            }
+
+            impl From<&$tys> for AttributeValue {
+                fn from(val: &$tys) -> Self {
+                    AttributeValue::from(*val)
+                }
+            }
+        )*
     };
+}
+int_impls!(u64, u128, i128, usize, isize);
+
+impl From<f32> for AttributeValue {
+    fn from(val: f32) -> Self {
+        // Going through the string so that 0.1f32 becomes 0.1f64, instead of the f64 that is
+        // closest to the f32, which would render as 0.10000000149011612.
+        AttributeValue::Float(val.to_string().parse().unwrap())
+    }
+}
 
-    ($ty:ty, $($tys:ty),*) => {
-        to_string_impls!( $ty );
-        to_string_impls! ( $($tys),* );
+impl From<&f32> for AttributeValue {
+    fn from(val: &f32) -> Self {
+        AttributeValue::from(*val)
     }
 }
-to_string_impls!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
 
 impl Into<JsValue> for AttributeValue {
     fn into(self) -> JsValue {
         match self {
             AttributeValue::String(s) => s.into(),
             AttributeValue::Bool(b) => b.into(),
+            AttributeValue::Int(i) => (i as f64).into(),
+            AttributeValue::Float(f) => f.into(),
+            AttributeValue::List(list) => list.join(" ").into(),
         }
     }
 }
@@ -84,33 +170,13 @@ impl From<&str> for AttributeValue {
 
 impl<S: AsRef<str>, const N: usize> From<[S; N]> for AttributeValue {
     fn from(vals: [S; N]) -> Self {
-        let mut combined = "".to_string();
-
-        for (idx, val) in vals.iter().enumerate() {
-            if idx != 0 {
-                combined += " ";
-            }
-
-            combined += val.as_ref();
-        }
-
-        AttributeValue::String(combined)
+        AttributeValue::List(vals.iter().map(|val| val.as_ref().to_string()).collect())
     }
 }
 
 impl<S: AsRef<str>> From<Vec<S>> for AttributeValue {
     fn from(vals: Vec<S>) -> Self {
-        let mut combined = "".to_string();
-
-        for (idx, val) in vals.iter().enumerate() {
-            if idx != 0 {
-                combined += " ";
-            }
-
-            combined += val.as_ref();
-        }
-
-        AttributeValue::String(combined)
+        AttributeValue::List(vals.iter().map(|val| val.as_ref().to_string()).collect())
     }
 }
 
@@ -131,10 +197,54 @@ impl Display for AttributeValue {
         match self {
             AttributeValue::String(s) => s.fmt(f),
             AttributeValue::Bool(b) => b.fmt(f),
+            AttributeValue::Int(i) => i.fmt(f),
+            AttributeValue::Float(float) => float.fmt(f),
+            AttributeValue::List(list) => {
+                for (idx, item) in list.iter().enumerate() {
+                    if idx != 0 {
+                        f.write_str(" ")?;
+                    }
+                    f.write_str(item)?;
+                }
+
+                Ok(())
+            }
         }
     }
 }
 
+// Used by the html! macro to allow lists such as `class=["a", cond.then(|| "b")]`, where some
+// items are `AsRef<str>` and others are `Option<impl AsRef<str>>`.
+//
+// A single trait can't be implemented for both, since the standard library could one day
+// implement `AsRef<str>` for `Option`. Instead the macro calls `(&ListItem(&item)).list_item()`.
+// Method resolution first tries `&ListItem<T>` as the receiver, which only has a `list_item`
+// method when `T` is an `Option`, before trying `&&ListItem<T>`.
+#[doc(hidden)]
+pub struct ListItem<'a, T>(pub &'a T);
+
+#[doc(hidden)]
+pub trait OptionalListItem {
+    fn list_item(&self) -> Option<&str>;
+}
+
+impl<'a, S: AsRef<str>> OptionalListItem for ListItem<'a, Option<S>> {
+    fn list_item(&self) -> Option<&str> {
+        self.0.as_ref().map(|item| item.as_ref())
+    }
+}
+
+#[doc(hidden)]
+pub trait RequiredListItem {
+    fn list_item(&self) -> Option<&str>;
+}
+
+impl<'a, 'b, S: AsRef<str>> RequiredListItem for &'b ListItem<'a, S> {
+    fn list_item(&self) -> Option<&str> {
+        Some(self.0.as_ref())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -143,7 +253,11 @@ mod tests {
     fn array_of_as_ref_str() {
         assert_eq!(
             AttributeValue::from(["hello", "world"]),
-            AttributeValue::String("hello world".to_string())
+            AttributeValue::List(vec!["hello".to_string(), "world".to_string()])
+        );
+        assert_eq!(
+            AttributeValue::from(["hello", "world"]).to_string(),
+            "hello world"
         );
     }
 
@@ -151,7 +265,38 @@ mod tests {
     fn vec_of_as_ref_str() {
         assert_eq!(
             AttributeValue::from(vec!["foo", "bar"]),
-            AttributeValue::String("foo bar".to_string())
+            AttributeValue::List(vec!["foo".to_string(), "bar".to_string()])
+        );
+        assert_eq!(
+            AttributeValue::from(vec!["foo", "bar"]).to_string(),
+            "foo bar"
+        );
+    }
+
+    /// Verify that numbers stay typed, and that they render the same way that JavaScript would.
+    #[test]
+    fn numbers() {
+        assert_eq!(AttributeValue::from(5u8), AttributeValue::Int(5));
+        assert_eq!(AttributeValue::from(&-5i64), AttributeValue::Int(-5));
+        assert_eq!(AttributeValue::from(5usize), AttributeValue::Int(5));
+        assert_eq!(
+            AttributeValue::from(u64::MAX),
+            AttributeValue::String(u64::MAX.to_string())
         );
+
+        assert_eq!(AttributeValue::from(0.1f32).to_string(), "0.1");
+        assert_eq!(AttributeValue::from(2.0f64).to_string(), "2");
+        assert_eq!(AttributeValue::from(0.5f64).to_string(), "0.5");
+    }
+
+    /// Verify that `None` list items are skipped.
+    // Borrowed the same way that the html! macro borrows them.
+    #[allow(clippy::needless_borrow)]
+    #[test]
+    fn optional_list_items() {
+        assert_eq!((&ListItem(&"a")).list_item(), Some("a"));
+        assert_eq!((&ListItem(&Some("b"))).list_item(), Some("b"));
+        assert_eq!((&ListItem(&None::<String>)).list_item(), None);
+        assert_eq!((&ListItem(&"c".to_string())).list_item(), Some("c"));
     }
 }
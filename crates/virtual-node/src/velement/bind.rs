@@ -0,0 +1,53 @@
+//! The event handlers that the `html!` macro's `bind:value`, `bind:checked` and `bind:group`
+//! attributes expand to.
+
+use std::cell::RefCell;
+
+use js_sys::Reflect;
+use wasm_bindgen::JsValue;
+
+use crate::CONTROLLED_VALUE_PROP;
+
+/// Store the value of the form control that the event targets.
+#[doc(hidden)]
+pub fn bind_value(event: &web_sys::Event, bound: &RefCell<String>) {
+    let target = match event.target() {
+        Some(target) => target,
+        None => return,
+    };
+    let value = Reflect::get(&target, &"value".into()).unwrap_or(JsValue::UNDEFINED);
+
+    // The bound value is what gets rendered next, so the value that the user typed is not put
+    // back before the next render.
+    Reflect::set(&target, &CONTROLLED_VALUE_PROP.into(), &value).unwrap();
+
+    *bound.borrow_mut() = value.as_string().unwrap_or_default();
+}
+
+/// Store whether or not the checkbox that the event targets is checked.
+#[doc(hidden)]
+pub fn bind_checked(event: &web_sys::Event, bound: &RefCell<bool>) {
+    if let Some(target) = event.target() {
+        *bound.borrow_mut() = is_checked(&target);
+    }
+}
+
+/// Store the value of the radio button that the event targets, if it got checked.
+#[doc(hidden)]
+pub fn bind_group(event: &web_sys::Event, bound: &RefCell<String>) {
+    let target = match event.target() {
+        Some(target) => target,
+        None => return,
+    };
+
+    if is_checked(&target) {
+        let value = Reflect::get(&target, &"value".into()).unwrap_or(JsValue::UNDEFINED);
+        *bound.borrow_mut() = value.as_string().unwrap_or_default();
+    }
+}
+
+fn is_checked(target: &JsValue) -> bool {
+    Reflect::get(target, &"checked".into())
+        .map(|checked| checked.is_truthy())
+        .unwrap_or(false)
+}
@@ -0,0 +1,179 @@
+use wasm_bindgen::JsValue;
+use web_sys::Element;
+
+use crate::event::EventsByNodeIdx;
+use crate::{AttributeValue, VElement};
+
+/// Private property that holds the value that a form control was last rendered with, so that the
+/// value can be put back when the user changes it and the application does not render the change.
+#[doc(hidden)]
+pub const CONTROLLED_VALUE_PROP: &str = "__percy_controlled_value__";
+
+/// How one of an element's attributes gets applied to a DOM element.
+///
+/// For most attributes setting the HTML attribute is enough. Form controls are different, since
+/// the user can change them. For example, the `checked` attribute of a checkbox only sets whether
+/// it is checked by default, so after the user clicks the checkbox only setting the `checked`
+/// DOM property changes whether it is checked.
+///
+/// Attributes that are set as DOM properties get set again every time that the element gets
+/// patched, even if they did not change, since the user may have changed them.
+///
+/// A form control that gets rendered with a `value` is controlled by the application. After the
+/// user changes its value, percy-dom puts back the rendered value unless the application renders
+/// the new value.
+///
+/// See [`VElement::attribute_target`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AttributeTarget {
+    /// Set as an HTML attribute.
+    Attribute,
+    /// Set as an HTML attribute, and as the DOM property with the same name.
+    ///
+    /// ex: `checked` on an `<input>` or `selected` on an `<option>`.
+    AttributeAndProperty,
+    /// Only set as the DOM property with the same name, since there is no HTML attribute for it.
+    ///
+    /// ex: `indeterminate` on an `<input>` or `scrollTop` on any element.
+    Property,
+}
+
+impl VElement {
+    /// Set an attribute that only gets set as the DOM property with the same name, instead of as
+    /// an HTML attribute.
+    ///
+    /// ```
+    /// # use virtual_node::{AttributeTarget, VElement};
+    /// let mut video = VElement::new("video");
+    /// video.set_dom_property("playbackRate", "2");
+    ///
+    /// assert_eq!(video.attribute_target("playbackRate"), AttributeTarget::Property);
+    /// ```
+    pub fn set_dom_property<N, V>(&mut self, name: N, value: V)
+    where
+        N: Into<String>,
+        V: Into<AttributeValue>,
+    {
+        let name = name.into();
+
+        self.dom_properties.insert(name.clone());
+        self.attrs.insert(name, value.into());
+    }
+
+    /// How the attribute gets applied to the DOM element.
+    ///
+    /// These attributes get set as DOM properties:
+    ///
+    /// - `checked` and `value` on an `<input>`, and `value` on a `<textarea>` or `<select>`
+    /// - `selected` on an `<option>`
+    /// - `muted` on an `<audio>` or `<video>`
+    /// - `indeterminate` on an `<input>`, which has no HTML attribute
+    /// - `scrollTop` and `scrollLeft` on any element, which have no HTML attribute
+    /// - any attribute set using [`VElement::set_dom_property`]
+    pub fn attribute_target(&self, name: &str) -> AttributeTarget {
+        // Checked first since most elements have none, which is faster than hashing the name.
+        if !self.dom_properties.is_empty() && self.dom_properties.contains(name) {
+            return AttributeTarget::Property;
+        }
+
+        match (self.tag.as_str(), name) {
+            ("input", "checked")
+            | ("input" | "textarea" | "select", "value")
+            | ("option", "selected")
+            | ("audio" | "video", "muted") => AttributeTarget::AttributeAndProperty,
+            ("input", "indeterminate") | (_, "scrollTop") | (_, "scrollLeft") => {
+                AttributeTarget::Property
+            }
+            _ => AttributeTarget::Attribute,
+        }
+    }
+
+    /// The attributes that get set as DOM properties.
+    pub fn dom_property_attributes(&self) -> impl Iterator<Item = (&String, &AttributeValue)> {
+        self.attrs
+            .iter()
+            .filter(move |(name, _)| self.attribute_target(name) != AttributeTarget::Attribute)
+    }
+}
+
+/// Set a DOM property on an element.
+///
+/// The property only gets set if it is different, since setting a form control's value can move
+/// its caret to the end even when the value stays the same.
+///
+/// A `value` makes the element a controlled form control, so the events get an `"input"`
+/// listener that puts the value back after the user changes it.
+///
+/// Returns an error if the browser throws while setting the property, for example since it is a
+/// read only property.
+pub fn set_dom_property(
+    element: &Element,
+    name: &str,
+    value: &AttributeValue,
+    events: &EventsByNodeIdx,
+) -> Result<(), JsValue> {
+    let value: JsValue = value.clone().into();
+
+    if name == "value" {
+        js_sys::Reflect::set(element, &CONTROLLED_VALUE_PROP.into(), &value)?;
+        events.need_input_listener();
+    }
+
+    let name: JsValue = name.into();
+    if js_sys::Reflect::get(element, &name).ok().as_ref() != Some(&value) {
+        js_sys::Reflect::set(element, &name, &value)?;
+    }
+
+    Ok(())
+}
+
+/// Reset a DOM property whose attribute was removed from an element.
+///
+/// Boolean properties become `false` and string properties become empty. Other properties, such
+/// as `scrollTop`, are left as they are.
+///
+/// Returns an error if the browser throws while resetting the property.
+pub fn remove_dom_property(element: &Element, name: &str) -> Result<(), JsValue> {
+    if name == "value" {
+        js_sys::Reflect::delete_property(element, &CONTROLLED_VALUE_PROP.into())?;
+    }
+
+    let name: JsValue = name.into();
+    let current = js_sys::Reflect::get(element, &name)?;
+
+    let reset = if current.as_bool().is_some() {
+        JsValue::FALSE
+    } else if current.is_string() {
+        JsValue::from_str("")
+    } else {
+        return Ok(());
+    };
+
+    js_sys::Reflect::set(element, &name, &reset)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that we set form control state as DOM properties.
+    #[test]
+    fn attribute_targets() {
+        let input = VElement::new("input");
+        assert_eq!(
+            input.attribute_target("checked"),
+            AttributeTarget::AttributeAndProperty
+        );
+        assert_eq!(
+            input.attribute_target("indeterminate"),
+            AttributeTarget::Property
+        );
+        assert_eq!(input.attribute_target("type"), AttributeTarget::Attribute);
+
+        let div = VElement::new("div");
+        assert_eq!(div.attribute_target("value"), AttributeTarget::Attribute);
+        assert_eq!(div.attribute_target("scrollTop"), AttributeTarget::Property);
+    }
+}
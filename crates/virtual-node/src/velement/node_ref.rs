@@ -0,0 +1,83 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use wasm_bindgen::JsCast;
+use web_sys::Element;
+
+/// A handle to the DOM element that a virtual element was rendered into, for when you need to use
+/// the element directly, such as to focus it, draw on a canvas or measure its layout.
+///
+/// Give it to an element using `ref=my_ref` in the `html!` macro, or using
+/// [`SpecialAttributes.set_node_ref`].
+///
+/// The element gets set when it is created, updated when the element gets replaced by a new
+/// element with the same `NodeRef`, and cleared when it gets removed.
+///
+/// Cloning a `NodeRef` gives you another handle to the same element, so you can keep one in your
+/// application state and pass a clone to the `html!` macro every time that you render.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use virtual_node::{NodeRef, VirtualNode};
+/// let input_ref = NodeRef::new();
+///
+/// let mut node = VirtualNode::element("input");
+/// node.as_velement_mut()
+///     .unwrap()
+///     .special_attributes
+///     .set_node_ref(input_ref.clone());
+///
+/// // After the node was rendered.
+/// if let Some(input) = input_ref.cast::<web_sys::HtmlInputElement>() {
+///     input.focus().unwrap();
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct NodeRef(Rc<RefCell<Option<Element>>>);
+
+impl NodeRef {
+    /// Create a `NodeRef` that does not have an element yet.
+    pub fn new() -> Self {
+        NodeRef::default()
+    }
+
+    /// The element, or `None` if the element has not been created yet or was removed.
+    pub fn get(&self) -> Option<Element> {
+        self.0.borrow().clone()
+    }
+
+    /// The element cast to a more specific type, such as a `web_sys::HtmlInputElement`.
+    ///
+    /// Returns `None` if there is no element or if it is not of that type.
+    pub fn cast<T: JsCast>(&self) -> Option<T> {
+        self.get()?.dyn_into().ok()
+    }
+
+    pub(crate) fn set(&self, element: &Element) {
+        *self.0.borrow_mut() = Some(element.clone());
+    }
+
+    /// Clear the element, unless the `NodeRef` was already set to a different element, such as
+    /// the element that replaced this one.
+    pub(crate) fn clear(&self, element: &Element) {
+        let mut current = self.0.borrow_mut();
+
+        if current.as_ref() == Some(element) {
+            *current = None;
+        }
+    }
+}
+
+impl PartialEq for NodeRef {
+    fn eq(&self, rhs: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &rhs.0)
+    }
+}
+
+impl fmt::Debug for NodeRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("NodeRef").field(&self.get()).finish()
+    }
+}
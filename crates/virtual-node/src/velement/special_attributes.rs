@@ -1,7 +1,17 @@
 use std::borrow::Cow;
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::ops::DerefMut;
 
+use wasm_bindgen::JsValue;
+
+use crate::{AttributeValue, NodeRef};
+
+/// Private property that marks an element that was removed from the virtual dom but is still in
+/// the DOM, because its on remove element function deferred its removal.
+#[doc(hidden)]
+pub const DEFERRED_REMOVAL_PROP: &str = "__deferred_removal__";
+
 /// A specially supported attributes.
 #[derive(Default, PartialEq)]
 pub struct SpecialAttributes {
@@ -13,12 +23,20 @@ pub struct SpecialAttributes {
     ///
     /// See [`SpecialAttributes.set_on_remove_element`] for more documentation.
     on_remove_element: Option<KeyAndElementFn>,
+    /// A function that gets called after the element gets patched.
+    ///
+    /// See [`SpecialAttributes.set_on_update_element`] for more documentation.
+    on_update_element: Option<KeyAndUpdateElementFn>,
     /// Allows setting the innerHTML of an element.
     ///
     /// # Danger
     ///
     /// Be sure to escape all untrusted input to avoid cross site scripting attacks.
     pub dangerous_inner_html: Option<String>,
+    /// Holds the element that the virtual node was rendered into.
+    ///
+    /// See [`SpecialAttributes.set_node_ref`] for more documentation.
+    node_ref: Option<NodeRef>,
 }
 
 impl SpecialAttributes {
@@ -155,6 +173,65 @@ impl SpecialAttributes {
         });
     }
 
+    /// Set an [`SpecialAttributes.on_remove_element`] function that removes the element itself,
+    /// such as after an exit animation, by calling [`DeferredRemoval::finish`].
+    ///
+    /// Until then the element stays in the DOM. It no longer is a part of the virtual dom, so it
+    /// does not get patched and does not handle any delegated events.
+    ///
+    /// If an element with the same tag and `key` attribute gets added to the same spot before the
+    /// removal finishes, such as when a node gets hidden and then shown again, the new element
+    /// takes the old element's place right away and finishing the removal does nothing.
+    ///
+    /// The removal is only deferred when the element is removed from its parent. When one of its
+    /// ancestors gets removed the element gets removed along with it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use virtual_node::{DeferredRemoval, VirtualNode};
+    /// use wasm_bindgen::closure::Closure;
+    /// use wasm_bindgen::JsCast;
+    ///
+    /// let mut node = VirtualNode::element("div");
+    ///
+    /// let fade_out = move |elem: web_sys::Element, removal: DeferredRemoval| {
+    ///     elem.class_list().add_1("fade-out").unwrap();
+    ///
+    ///     let finish = Closure::once_into_js(move || removal.finish());
+    ///     web_sys::window()
+    ///         .unwrap()
+    ///         .set_timeout_with_callback_and_timeout_and_arguments_0(finish.unchecked_ref(), 300)
+    ///         .unwrap();
+    /// };
+    ///
+    /// node
+    ///     .as_velement_mut()
+    ///     .unwrap()
+    ///     .special_attributes
+    ///     .set_on_remove_element_deferred("fade-out", fade_out);
+    /// ```
+    pub fn set_on_remove_element_deferred<Key, Func>(&mut self, key: Key, func: Func)
+    where
+        Key: Into<Cow<'static, str>>,
+        Func: FnMut(web_sys::Element, DeferredRemoval) + 'static,
+    {
+        self.on_remove_element = Some(KeyAndElementFn {
+            key: key.into(),
+            func: RefCell::new(ElementFunc::Deferred(Box::new(func))),
+        });
+    }
+
+    /// Whether the `on_remove_element` function removes the element itself.
+    ///
+    /// See [`SpecialAttributes.set_on_remove_element_deferred`].
+    pub fn defers_element_removal(&self) -> bool {
+        self.on_remove_element
+            .as_ref()
+            .map(|on_remove_elem| matches!(*on_remove_elem.func.borrow(), ElementFunc::Deferred(_)))
+            .unwrap_or(false)
+    }
+
     /// If an `on_remove_element` function was set, call it.
     pub fn maybe_call_on_remove_element(&self, element: &web_sys::Element) {
         if let Some(on_remove_elem) = &self.on_remove_element {
@@ -163,6 +240,166 @@ impl SpecialAttributes {
 
         let _ = element;
     }
+
+    /// Mark the element as removed, leaving it in the DOM, and call the `on_remove_element`
+    /// function so that it can finish removing the element.
+    ///
+    /// Returns an error if the element could not be marked, in which case the function is not
+    /// called.
+    pub fn defer_element_removal(&self, element: &web_sys::Element) -> Result<(), JsValue> {
+        js_sys::Reflect::set(element, &DEFERRED_REMOVAL_PROP.into(), &JsValue::TRUE)?;
+
+        self.maybe_call_on_remove_element(element);
+
+        Ok(())
+    }
+}
+
+impl SpecialAttributes {
+    /// The key for the on update element function
+    pub fn on_update_element_key(&self) -> Option<&Cow<'static, str>> {
+        self.on_update_element.as_ref().map(|k| &k.key)
+    }
+
+    /// Set the [`SpecialAttributes.on_update_element`] function.
+    ///
+    /// The function gets called when a virtual node gets patched over the element and that changed
+    /// the element or its children, after all of those patches have been applied. It receives the
+    /// element along with the old and the new virtual node's attributes.
+    ///
+    /// This lets you keep something that percy-dom does not manage, such as a chart or a map that
+    /// was rendered into the element by a JavaScript library, in sync with the element.
+    ///
+    /// The function does not get called when the element is created or replaced.
+    ///
+    /// # Key
+    ///
+    /// The key identifies the function, for example when patches get sent to another thread.
+    ///
+    /// Unlike with the on create element function, the function gets called whether or not the key
+    /// changed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use virtual_node::VirtualNode;
+    /// let mut node = VirtualNode::element("div");
+    ///
+    /// let on_update_elem = move |elem: web_sys::Element, old_attrs: &_, new_attrs: &_| {
+    ///     if old_attrs != new_attrs {
+    ///         // Update a chart that was drawn into the element...
+    ///     }
+    /// };
+    ///
+    /// node
+    ///     .as_velement_mut()
+    ///     .unwrap()
+    ///     .special_attributes
+    ///     .set_on_update_element("chart", on_update_elem);
+    /// ```
+    pub fn set_on_update_element<Key, Func>(&mut self, key: Key, func: Func)
+    where
+        Key: Into<Cow<'static, str>>,
+        Func: FnMut(
+                web_sys::Element,
+                &HashMap<String, AttributeValue>,
+                &HashMap<String, AttributeValue>,
+            ) + 'static,
+    {
+        self.on_update_element = Some(KeyAndUpdateElementFn {
+            key: key.into(),
+            func: RefCell::new(Box::new(func)),
+        });
+    }
+
+    // Used by the html-macro
+    #[doc(hidden)]
+    pub fn set_on_update_element_no_args<Key, Func>(&mut self, key: Key, mut func: Func)
+    where
+        Key: Into<Cow<'static, str>>,
+        Func: FnMut() + 'static,
+    {
+        self.set_on_update_element(key, move |_, _, _| func());
+    }
+
+    /// If an `on_update_element` function was set, call it.
+    pub fn maybe_call_on_update_element(
+        &self,
+        element: &web_sys::Element,
+        old_attrs: &HashMap<String, AttributeValue>,
+        new_attrs: &HashMap<String, AttributeValue>,
+    ) {
+        if let Some(on_update_elem) = &self.on_update_element {
+            (on_update_elem.func.borrow_mut())(element.clone(), old_attrs, new_attrs);
+        }
+    }
+}
+
+impl SpecialAttributes {
+    /// The element's [`NodeRef`].
+    pub fn node_ref(&self) -> Option<&NodeRef> {
+        self.node_ref.as_ref()
+    }
+
+    /// Set the [`NodeRef`] that holds the element that the virtual node gets rendered into.
+    ///
+    /// When a node gets patched over an element whose node had a different `NodeRef`, the old
+    /// `NodeRef` gets cleared and the new one gets set to the element.
+    pub fn set_node_ref(&mut self, node_ref: NodeRef) {
+        self.node_ref = Some(node_ref);
+    }
+
+    /// If a [`NodeRef`] was set, set it to the element.
+    pub fn maybe_set_node_ref(&self, element: &web_sys::Element) {
+        if let Some(node_ref) = &self.node_ref {
+            node_ref.set(element);
+        }
+    }
+
+    /// If a [`NodeRef`] was set, clear it if it still holds the element.
+    pub fn maybe_clear_node_ref(&self, element: &web_sys::Element) {
+        if let Some(node_ref) = &self.node_ref {
+            node_ref.clear(element);
+        }
+    }
+}
+
+/// Removes an element whose removal was deferred by its on remove element function.
+///
+/// See [`SpecialAttributes.set_on_remove_element_deferred`].
+pub struct DeferredRemoval {
+    element: web_sys::Element,
+}
+
+impl DeferredRemoval {
+    /// Remove the element from the DOM.
+    ///
+    /// Does nothing if an element that was added back took the element's place, or if the element
+    /// was removed along with one of its ancestors.
+    pub fn finish(self) {
+        if is_removal_deferred(&self.element) {
+            // The element gets removed either way, so there is nothing to do if unmarking it fails.
+            let _ = cancel_deferred_removal(&self.element);
+            self.element.remove();
+        }
+    }
+}
+
+/// Whether the node was removed from the virtual dom but is still in the DOM while its on remove
+/// element function finishes removing it.
+#[doc(hidden)]
+pub fn is_removal_deferred(node: &web_sys::Node) -> bool {
+    js_sys::Reflect::get(node, &DEFERRED_REMOVAL_PROP.into())
+        .map(|deferred| deferred.is_truthy())
+        .unwrap_or(false)
+}
+
+/// Unmark an element whose removal was deferred, so that finishing its removal does nothing.
+#[doc(hidden)]
+pub fn cancel_deferred_removal(element: &web_sys::Element) -> Result<(), JsValue> {
+    js_sys::Reflect::set(element, &DEFERRED_REMOVAL_PROP.into(), &JsValue::UNDEFINED)?;
+
+    Ok(())
 }
 
 struct KeyAndElementFn {
@@ -173,6 +410,7 @@ struct KeyAndElementFn {
 enum ElementFunc {
     NoArgs(Box<dyn FnMut()>),
     OneArg(Box<dyn FnMut(web_sys::Element)>),
+    Deferred(Box<dyn FnMut(web_sys::Element, DeferredRemoval)>),
 }
 
 impl KeyAndElementFn {
@@ -180,6 +418,7 @@ impl KeyAndElementFn {
         match self.func.borrow_mut().deref_mut() {
             ElementFunc::NoArgs(func) => (func)(),
             ElementFunc::OneArg(func) => (func)(element),
+            ElementFunc::Deferred(func) => (func)(element.clone(), DeferredRemoval { element }),
         };
     }
 }
@@ -189,3 +428,18 @@ impl PartialEq for KeyAndElementFn {
         self.key == rhs.key
     }
 }
+
+type UpdateElementFn = Box<
+    dyn FnMut(web_sys::Element, &HashMap<String, AttributeValue>, &HashMap<String, AttributeValue>),
+>;
+
+struct KeyAndUpdateElementFn {
+    key: Cow<'static, str>,
+    func: RefCell<UpdateElementFn>,
+}
+
+impl PartialEq for KeyAndUpdateElementFn {
+    fn eq(&self, rhs: &Self) -> bool {
+        self.key == rhs.key
+    }
+}
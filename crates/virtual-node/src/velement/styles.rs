@@ -0,0 +1,203 @@
+use std::fmt;
+
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CssStyleDeclaration, Element};
+
+/// An element's inline styles, such as `style="color: red; width: 5px"`, kept as CSS property
+/// names and their values.
+///
+/// Keeping the properties separate lets us patch only the properties that changed, instead of
+/// replacing the entire `style` attribute, which would cancel any running CSS transitions.
+///
+/// The properties are kept in the order that they were set in, since a shorthand property such as
+/// `margin` overrides any longhand properties such as `margin-top` that come before it.
+///
+/// ```
+/// # use virtual_node::Styles;
+/// let mut styles = Styles::parse("color: red; width: 5px");
+/// styles.set("color", "blue");
+///
+/// assert_eq!(styles.get("color"), Some("blue"));
+/// assert_eq!(styles.to_string(), "color: blue; width: 5px");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Styles {
+    properties: Vec<(String, String)>,
+}
+
+impl Styles {
+    /// Create an empty set of styles.
+    pub fn new() -> Self {
+        Styles::default()
+    }
+
+    /// Parse the declarations of a `style` attribute, such as `"color: red; width: 5px"`.
+    ///
+    /// Declarations without a `:` are ignored.
+    pub fn parse(css: &str) -> Self {
+        let mut styles = Styles::new();
+        styles.extend_from_css(css);
+
+        styles
+    }
+
+    /// Parse the declarations of a `style` attribute and set each of their properties.
+    pub fn extend_from_css(&mut self, css: &str) {
+        for declaration in split_declarations(css) {
+            if let Some((name, value)) = declaration.split_once(':') {
+                let (name, value) = (name.trim(), value.trim());
+
+                if !name.is_empty() && !value.is_empty() {
+                    self.set(name, value);
+                }
+            }
+        }
+    }
+
+    /// Set a property, replacing its previous value.
+    pub fn set<N, V>(&mut self, name: N, value: V)
+    where
+        N: Into<String>,
+        V: Into<String>,
+    {
+        let name = name.into();
+        let value = value.into();
+
+        match self.properties.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, old_value)) => *old_value = value,
+            None => self.properties.push((name, value)),
+        };
+    }
+
+    /// The value of a property.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Remove a property, returning its value.
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let position = self.properties.iter().position(|(n, _)| n == name)?;
+        Some(self.properties.remove(position).1)
+    }
+
+    /// The property names and values, in the order that they were set in.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.properties
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Whether or not there are no properties.
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+    }
+}
+
+impl fmt::Display for Styles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, (name, value)) in self.properties.iter().enumerate() {
+            if idx != 0 {
+                f.write_str("; ")?;
+            }
+
+            write!(f, "{}: {}", name, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Split on the `;`s that are not inside of quotes or parentheses, such as the one in
+/// `background: url("data:image/png;base64,...")`.
+fn split_declarations(css: &str) -> Vec<&str> {
+    let mut declarations = vec![];
+
+    let mut quote = None;
+    let mut parens = 0;
+    let mut start = 0;
+
+    for (idx, c) in css.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (_, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {}
+            ('(', None) => parens += 1,
+            (')', None) => parens -= 1,
+            (';', None) if parens <= 0 => {
+                declarations.push(&css[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    declarations.push(&css[start..]);
+
+    declarations
+}
+
+/// Set one of an element's inline style properties using `CSSStyleDeclaration.setProperty`.
+///
+/// A value that ends with `!important` gets set with the important priority.
+///
+/// Returns an error if the browser throws, for example since the element has no `style`.
+pub fn set_style_property(element: &Element, name: &str, value: &str) -> Result<(), JsValue> {
+    let (value, priority) = match value.strip_suffix("!important") {
+        Some(value) => (value.trim_end(), "important"),
+        None => (value, ""),
+    };
+
+    style_declaration(element)?.set_property_with_priority(name, value, priority)
+}
+
+/// Remove one of an element's inline style properties.
+///
+/// Returns an error if the browser throws, for example since the element has no `style`.
+pub fn remove_style_property(element: &Element, name: &str) -> Result<(), JsValue> {
+    style_declaration(element)?.remove_property(name)?;
+
+    Ok(())
+}
+
+// Both HTML and SVG elements have a `style`, so we don't cast to either of them.
+fn style_declaration(element: &Element) -> Result<CssStyleDeclaration, JsValue> {
+    Ok(js_sys::Reflect::get(element, &"style".into())?.unchecked_into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verify that we parse the declarations in a style attribute.
+    #[test]
+    fn parse() {
+        let styles = Styles::parse(
+            r#" color:red;; width : 5px ; background: url("a;b.png"); invalid; color: blue; "#,
+        );
+
+        assert_eq!(
+            styles.iter().collect::<Vec<_>>(),
+            vec![
+                ("color", "blue"),
+                ("width", "5px"),
+                ("background", r#"url("a;b.png")"#)
+            ]
+        );
+    }
+
+    /// Verify that we can turn styles back into a style attribute.
+    #[test]
+    fn display() {
+        let mut styles = Styles::new();
+        assert_eq!(styles.to_string(), "");
+
+        styles.set("margin", "0");
+        styles.set("margin-top", "5px !important");
+        assert_eq!(styles.to_string(), "margin: 0; margin-top: 5px !important");
+
+        styles.remove("margin");
+        assert_eq!(styles.to_string(), "margin-top: 5px !important");
+    }
+}
@@ -0,0 +1,50 @@
+use std::fmt;
+
+use crate::VirtualNode;
+
+/// A group of sibling nodes that does not have an element of its own.
+///
+/// Fragments don't exist in the DOM. Their children get placed directly inside of the fragment's
+/// parent, so a fragment's children are diffed, patched and indexed as if they were the children
+/// of the fragment's parent.
+///
+/// `html! { <ul> <li>A</li> <>{"B"} <li>C</li></> </ul> }` becomes `<ul><li>A</li>B<li>C</li></ul>`.
+#[derive(PartialEq, Default)]
+pub struct VFragment {
+    /// The nodes in this fragment.
+    pub children: Vec<VirtualNode>,
+}
+
+impl VFragment {
+    /// Create an empty `VFragment`.
+    pub fn new() -> Self {
+        VFragment { children: vec![] }
+    }
+
+    /// This fragment's children, with any nested fragments replaced by their own children.
+    pub fn flat_children(&self) -> Vec<&VirtualNode> {
+        flatten_fragments(&self.children)
+    }
+}
+
+/// The nodes with every fragment replaced by its children, recursively.
+pub(crate) fn flatten_fragments(nodes: &[VirtualNode]) -> Vec<&VirtualNode> {
+    let mut flattened = Vec::with_capacity(nodes.len());
+    push_flattened(nodes, &mut flattened);
+    flattened
+}
+
+fn push_flattened<'a>(nodes: &'a [VirtualNode], flattened: &mut Vec<&'a VirtualNode>) {
+    for node in nodes {
+        match node {
+            VirtualNode::Fragment(fragment) => push_flattened(&fragment.children, flattened),
+            _ => flattened.push(node),
+        }
+    }
+}
+
+impl fmt::Debug for VFragment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Fragment(children: {:?})", self.children)
+    }
+}
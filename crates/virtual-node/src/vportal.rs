@@ -0,0 +1,102 @@
+use std::fmt;
+
+use wasm_bindgen::JsCast;
+use web_sys::{Comment, Element, Node};
+
+use crate::vfragment::flatten_fragments;
+use crate::VirtualNode;
+
+/// Private property that links a portal's placeholder comment to the element that holds the
+/// portal's children.
+#[doc(hidden)]
+pub const PORTAL_CONTAINER_PROP: &str = "__portal_container__";
+
+/// Private property that links the element that holds a portal's children back to the portal's
+/// placeholder comment.
+#[doc(hidden)]
+pub const PORTAL_PLACEHOLDER_PROP: &str = "__portal_placeholder__";
+
+/// A group of nodes that get placed into a different DOM element than their parent, such as a
+/// modal that gets placed into `document.body`.
+///
+/// The portal's children are diffed, patched and indexed as if the portal was an element in the
+/// spot that it was rendered in, so they can be owned by a deeply nested view.
+///
+/// In the DOM the portal leaves a `<!--portal-->` comment in its spot, and its children get placed
+/// inside of a `<div>` that gets appended to the first element that matches the portal's
+/// `mount_selector`. Events that happen inside of the portal bubble up to the portal's parent,
+/// just as if the portal's children were in the portal's spot.
+///
+/// When rendering HTML on the server only the comment gets rendered. The portal's children get
+/// created when the page is hydrated.
+///
+/// ```
+/// # use virtual_node::{VirtualNode, VPortal};
+/// let mut modal = VPortal::new("body");
+/// modal.children.push(VirtualNode::text("Are you sure?"));
+///
+/// let _modal: VirtualNode = modal.into();
+/// ```
+#[derive(PartialEq)]
+pub struct VPortal {
+    /// A CSS selector for the element that the portal's children get placed into, such as
+    /// `"body"` or `"#modals"`.
+    pub mount_selector: String,
+    /// The nodes in this portal.
+    pub children: Vec<VirtualNode>,
+}
+
+impl VPortal {
+    /// Create an empty `VPortal` that places its children into the first element that matches the
+    /// selector.
+    pub fn new<S>(mount_selector: S) -> Self
+    where
+        S: Into<String>,
+    {
+        VPortal {
+            mount_selector: mount_selector.into(),
+            children: vec![],
+        }
+    }
+
+    /// This portal's children, with any fragments replaced by their own children.
+    pub fn flat_children(&self) -> Vec<&VirtualNode> {
+        flatten_fragments(&self.children)
+    }
+}
+
+/// The element that holds a portal's children, if the node is a portal's placeholder comment.
+#[doc(hidden)]
+pub fn portal_container(placeholder: &Node) -> Option<Element> {
+    js_sys::Reflect::get(placeholder, &PORTAL_CONTAINER_PROP.into())
+        .ok()?
+        .dyn_into()
+        .ok()
+}
+
+/// The portal's placeholder comment, if the node is the element that holds a portal's children.
+#[doc(hidden)]
+pub fn portal_placeholder(container: &Node) -> Option<Comment> {
+    js_sys::Reflect::get(container, &PORTAL_PLACEHOLDER_PROP.into())
+        .ok()?
+        .dyn_into()
+        .ok()
+}
+
+impl fmt::Debug for VPortal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Portal(mount_selector: {}, children: {:?})",
+            self.mount_selector, self.children
+        )
+    }
+}
+
+// The portal's children don't get rendered in the portal's spot, so we only render its
+// placeholder.
+impl fmt::Display for VPortal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<!--portal-->")
+    }
+}
@@ -2,6 +2,8 @@ use std::fmt;
 
 use web_sys::Text;
 
+use crate::escape::EscapeText;
+
 /// Represents a text node
 #[derive(PartialEq)]
 pub struct VText {
@@ -45,9 +47,10 @@ impl fmt::Debug for VText {
     }
 }
 
-// Turn a VText into an HTML string
+// Turn a VText into an HTML string, escaping any characters that would otherwise be parsed as
+// markup.
 impl fmt::Display for VText {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.text)
+        write!(f, "{}", EscapeText(&self.text))
     }
 }
@@ -33,7 +33,7 @@ impl ComponentPreviewWebClient {
         std::panic::set_hook(Box::new(console_error_panic_hook::hook));
 
         let mut pdom = create_percy_dom(dom_selector_of_mount);
-        pdom.update(render_active_route());
+        pdom.update(render_active_route()).unwrap();
 
         let render = Arc::new(Mutex::new(Box::new(|| {}) as Box<dyn FnMut() -> ()>));
         let render_clone = Arc::clone(&render);
@@ -72,7 +72,10 @@ impl Client {
         let root_node = document()
             .get_element_by_id("isomorphic-rust-web-app")
             .unwrap();
-        let pdom = PercyDom::new_replace_mount(app.render(), root_node);
+        let (pdom, mismatches) = PercyDom::hydrate(app.render(), root_node);
+        for mismatch in mismatches {
+            web_sys::console::warn_1(&format!("Hydration mismatch: {:?}", mismatch).into());
+        }
 
         let store = Rc::clone(&app.store);
         intercept_relative_links(store);
@@ -82,7 +85,7 @@ impl Client {
 
     pub fn render(&mut self) {
         let vdom = self.app.render();
-        self.pdom.update(vdom);
+        self.pdom.update(vdom).unwrap();
     }
 }
 